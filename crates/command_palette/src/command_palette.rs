@@ -113,6 +113,10 @@ impl CommandPalette {
         self.picker
             .update(cx, |picker, cx| picker.set_query(query, cx))
     }
+
+    pub fn query(&self, cx: &AppContext) -> String {
+        self.picker.read(cx).query(cx)
+    }
 }
 
 impl EventEmitter<DismissEvent> for CommandPalette {}
@@ -125,7 +129,10 @@ impl FocusableView for CommandPalette {
 
 impl Render for CommandPalette {
     fn render(&mut self, _cx: &mut ViewContext<Self>) -> impl IntoElement {
-        v_flex().w(rems(34.)).child(self.picker.clone())
+        v_flex()
+            .key_context("CommandPalette")
+            .w(rems(34.))
+            .child(self.picker.clone())
     }
 }
 
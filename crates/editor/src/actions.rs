@@ -179,6 +179,7 @@ gpui::actions!(
         AcceptPartialInlineCompletion,
         AddSelectionAbove,
         AddSelectionBelow,
+        AutoIndent,
         Backspace,
         Cancel,
         CancelLanguageServerWork,
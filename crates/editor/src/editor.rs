@@ -526,6 +526,7 @@ pub struct Editor {
     hovered_link_state: Option<HoveredLinkState>,
     inline_completion_provider: Option<RegisteredInlineCompletionProvider>,
     active_inline_completion: Option<(Inlay, Option<Range<Anchor>>)>,
+    preview_inlays: Vec<InlayId>,
     show_inline_completions: bool,
     inlay_hint_cache: InlayHintCache,
     expanded_hunks: ExpandedHunks,
@@ -1869,6 +1870,7 @@ impl Editor {
             hovered_link_state: Default::default(),
             inline_completion_provider: None,
             active_inline_completion: None,
+            preview_inlays: Vec::new(),
             inlay_hint_cache: InlayHintCache::new(inlay_hint_settings),
             expanded_hunks: ExpandedHunks::default(),
             gutter_hovered: false,
@@ -5777,6 +5779,16 @@ impl Editor {
         });
     }
 
+    pub fn autoindent(&mut self, _: &AutoIndent, cx: &mut ViewContext<Self>) {
+        if self.read_only(cx) {
+            return;
+        }
+        let selections = self.selections.all_adjusted(cx);
+        self.buffer.update(cx, |buffer, cx| {
+            buffer.autoindent_ranges(selections.iter().map(|selection| selection.range()), cx)
+        });
+    }
+
     pub fn delete_line(&mut self, _: &DeleteLine, cx: &mut ViewContext<Self>) {
         let display_map = self.display_map.update(cx, |map, cx| map.snapshot(cx));
         let selections = self.selections.all::<Point>(cx);
@@ -6660,6 +6672,7 @@ impl Editor {
         text: &String,
         clipboard_selections: Option<Vec<ClipboardSelection>>,
         handle_entire_lines: bool,
+        auto_indent: bool,
         cx: &mut ViewContext<Self>,
     ) {
         if self.read_only(cx) {
@@ -6719,7 +6732,7 @@ impl Editor {
 
                     buffer.edit(
                         edits,
-                        Some(AutoindentMode::Block {
+                        auto_indent.then(|| AutoindentMode::Block {
                             original_indent_columns,
                         }),
                         cx,
@@ -6728,8 +6741,10 @@ impl Editor {
 
                 let selections = this.selections.all::<usize>(cx);
                 this.change_selections(Some(Autoscroll::fit()), cx, |s| s.select(selections));
-            } else {
+            } else if auto_indent {
                 this.insert(&clipboard_text, cx);
+            } else {
+                this.insert_with_autoindent_mode(&clipboard_text, None, cx);
             }
         });
     }
@@ -6746,9 +6761,10 @@ impl Editor {
                         clipboard_string.text(),
                         clipboard_string.metadata_json::<Vec<ClipboardSelection>>(),
                         true,
+                        true,
                         cx,
                     ),
-                _ => self.do_paste(&item.text().unwrap_or_default(), None, true, cx),
+                _ => self.do_paste(&item.text().unwrap_or_default(), None, true, true, cx),
             }
         }
     }
@@ -10939,6 +10955,27 @@ impl Editor {
         self.clear_background_highlights::<SearchWithinRange>(cx);
     }
 
+    /// Shows each text as a suggestion at its position, replacing those shown
+    /// by the last call. This previews an edit without making it.
+    pub fn set_preview_inlays(
+        &mut self,
+        inlays: impl IntoIterator<Item = (Anchor, String)>,
+        cx: &mut ViewContext<Self>,
+    ) {
+        let to_remove = mem::take(&mut self.preview_inlays);
+        let to_insert = inlays
+            .into_iter()
+            .map(|(position, text)| {
+                Inlay::suggestion(post_inc(&mut self.next_inlay_id), position, text)
+            })
+            .collect::<Vec<_>>();
+        if to_remove.is_empty() && to_insert.is_empty() {
+            return;
+        }
+        self.preview_inlays = to_insert.iter().map(|inlay| inlay.id).collect();
+        self.splice_inlays(to_remove, to_insert, cx);
+    }
+
     pub fn highlight_background<T: 'static>(
         &mut self,
         ranges: &[Range<Anchor>],
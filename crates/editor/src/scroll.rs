@@ -24,6 +24,8 @@ use workspace::{ItemId, WorkspaceId};
 
 pub const SCROLL_EVENT_SEPARATION: Duration = Duration::from_millis(28);
 const SCROLLBAR_SHOW_INTERVAL: Duration = Duration::from_secs(1);
+/// The number of columns kept visible on either side of the cursor.
+pub const DEFAULT_HORIZONTAL_SCROLL_MARGIN: f32 = 3.;
 
 #[derive(Default)]
 pub struct ScrollbarAutoHide(pub bool);
@@ -130,6 +132,7 @@ impl OngoingScroll {
 
 pub struct ScrollManager {
     pub(crate) vertical_scroll_margin: f32,
+    pub(crate) horizontal_scroll_margin: f32,
     anchor: ScrollAnchor,
     ongoing: OngoingScroll,
     autoscroll_request: Option<(Autoscroll, bool)>,
@@ -145,6 +148,7 @@ impl ScrollManager {
     pub fn new(cx: &mut WindowContext) -> Self {
         ScrollManager {
             vertical_scroll_margin: EditorSettings::get_global(cx).vertical_scroll_margin,
+            horizontal_scroll_margin: DEFAULT_HORIZONTAL_SCROLL_MARGIN,
             anchor: ScrollAnchor::new(),
             ongoing: OngoingScroll::new(),
             autoscroll_request: None,
@@ -350,6 +354,19 @@ impl Editor {
         cx.notify();
     }
 
+    pub fn horizontal_scroll_margin(&self) -> usize {
+        self.scroll_manager.horizontal_scroll_margin as usize
+    }
+
+    pub fn set_horizontal_scroll_margin(
+        &mut self,
+        margin_columns: usize,
+        cx: &mut ViewContext<Self>,
+    ) {
+        self.scroll_manager.horizontal_scroll_margin = margin_columns as f32;
+        cx.notify();
+    }
+
     pub fn visible_line_count(&self) -> Option<f32> {
         self.scroll_manager.visible_line_count
     }
@@ -238,13 +238,15 @@ impl Editor {
         {
             target_left = px(f32::INFINITY);
             target_right = px(0.);
+            let margin = self.scroll_manager.horizontal_scroll_margin as u32;
             for selection in selections {
                 let head = selection.head().to_display_point(&display_map);
                 if head.row() >= start_row
                     && head.row() < DisplayRow(start_row.0 + layouts.len() as u32)
                 {
-                    let start_column = head.column().saturating_sub(3);
-                    let end_column = cmp::min(display_map.line_len(head.row()), head.column() + 3);
+                    let start_column = head.column().saturating_sub(margin);
+                    let end_column =
+                        cmp::min(display_map.line_len(head.row()), head.column() + margin);
                     target_left = target_left.min(
                         layouts[head.row().minus(start_row) as usize]
                             .x_for_index(start_column as usize),
@@ -1535,6 +1535,31 @@ impl MultiBuffer {
         result
     }
 
+    /// Recomputes the indentation of the lines in the given ranges, in
+    /// whichever buffers they fall.
+    pub fn autoindent_ranges<I, S>(&mut self, ranges: I, cx: &mut ModelContext<Self>)
+    where
+        I: IntoIterator<Item = Range<S>>,
+        S: ToOffset,
+    {
+        let mut ranges_by_buffer =
+            HashMap::<BufferId, (Model<Buffer>, Vec<Range<usize>>)>::default();
+        for range in ranges {
+            for (buffer, range, _) in self.range_to_buffer_ranges(range, cx) {
+                let buffer_id = buffer.read(cx).remote_id();
+                ranges_by_buffer
+                    .entry(buffer_id)
+                    .or_insert_with(|| (buffer, Vec::new()))
+                    .1
+                    .push(range);
+            }
+        }
+
+        for (buffer, ranges) in ranges_by_buffer.into_values() {
+            buffer.update(cx, |buffer, cx| buffer.autoindent_ranges(ranges, cx));
+        }
+    }
+
     pub fn remove_excerpts(
         &mut self,
         excerpt_ids: impl IntoIterator<Item = ExcerptId>,
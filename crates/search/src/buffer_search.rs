@@ -735,6 +735,10 @@ impl BufferSearchBar {
         }
     }
 
+    pub fn search_options(&self) -> SearchOptions {
+        self.search_options
+    }
+
     pub fn set_search_options(
         &mut self,
         search_options: SearchOptions,
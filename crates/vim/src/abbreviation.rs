@@ -0,0 +1,245 @@
+//! Insert mode abbreviations (`:iabbrev`), which replace a word with its
+//! expansion once it's followed by a character that can't be part of it.
+
+use collections::HashMap;
+use editor::Editor;
+use gpui::{actions, impl_actions, ViewContext, WindowContext};
+use language::{char_kind, CharKind, Point};
+use serde::Deserialize;
+use workspace::Workspace;
+
+use crate::{normal::repeat::observe_insertion, state::Mode, Vim};
+
+/// Makes `trigger` expand to `expansion` in insert mode (`:iabbrev`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Abbreviate {
+    trigger: String,
+    expansion: String,
+}
+
+actions!(vim, [ClearAbbreviations]);
+impl_actions!(vim, [Abbreviate]);
+
+impl Abbreviate {
+    /// Returns `None` if `trigger` isn't one of vim's kinds of abbreviation,
+    /// or there is nothing to expand it to.
+    pub(crate) fn new(trigger: &str, expansion: &str) -> Option<Self> {
+        if expansion.is_empty() {
+            return None;
+        }
+        AbbreviationKind::of(trigger, |c| char_kind(&None, c) == CharKind::Word)?;
+        Some(Self {
+            trigger: trigger.to_string(),
+            expansion: expansion.to_string(),
+        })
+    }
+}
+
+/// The kinds of abbreviation, which differ in what may come before them for
+/// them to expand.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum AbbreviationKind {
+    /// Only keyword characters, like `teh`.
+    FullId,
+    /// A keyword character after only non-keyword characters, like `#i`.
+    EndId,
+    /// Ending with a non-keyword character, like `def#`.
+    NonId,
+}
+
+impl AbbreviationKind {
+    fn of(trigger: &str, is_keyword: impl Fn(char) -> bool) -> Option<Self> {
+        let mut chars = trigger.chars().rev();
+        let last = chars.next()?;
+        if trigger.chars().any(char::is_whitespace) {
+            return None;
+        }
+        if !is_keyword(last) {
+            Some(Self::NonId)
+        } else if trigger.chars().all(&is_keyword) {
+            Some(Self::FullId)
+        } else if chars.all(|c| !is_keyword(c)) {
+            Some(Self::EndId)
+        } else {
+            None
+        }
+    }
+
+    /// Whether an abbreviation expands when `before` is the character in
+    /// front of it, or `None` at the start of the line.
+    fn expands_after(
+        self,
+        trigger: &str,
+        before: Option<char>,
+        is_keyword: impl Fn(char) -> bool,
+    ) -> bool {
+        match (self, before) {
+            (_, None | Some(' ' | '\t')) => true,
+            // A single character is only expanded on its own.
+            (Self::FullId, Some(c)) => trigger.chars().nth(1).is_some() && !is_keyword(c),
+            (Self::EndId, Some(c)) => is_keyword(c),
+            (Self::NonId, Some(_)) => false,
+        }
+    }
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_, action: &Abbreviate, cx| {
+        Vim::update(cx, |vim, _| {
+            vim.workspace_state
+                .abbreviations
+                .insert(action.trigger.clone(), action.expansion.clone());
+        })
+    });
+
+    workspace.register_action(|_, _: &ClearAbbreviations, cx| {
+        Vim::update(cx, |vim, _| vim.workspace_state.abbreviations.clear())
+    });
+}
+
+/// Called after `text` is typed, to expand the abbreviation before it if it
+/// is a single non-keyword character. The expansion is recorded as an
+/// insertion for `.` and macros, so that they replay it without looking the
+/// abbreviation up again.
+pub(crate) fn expand_abbreviation(text: &str, cx: &mut WindowContext) {
+    let mut chars = text.chars();
+    let (Some(typed), None) = (chars.next(), chars.next()) else {
+        return;
+    };
+    let Some(abbreviations) = Vim::read(cx).abbreviations_to_expand() else {
+        return;
+    };
+    let expanded = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            expand_in_editor(editor, typed, &abbreviations, cx)
+        })
+    })
+    .flatten();
+
+    if let Some(trigger) = expanded {
+        let expansion = &abbreviations[&trigger];
+        let replaced = trigger.encode_utf16().count() + typed.len_utf16();
+        observe_insertion(
+            &format!("{expansion}{typed}").into(),
+            Some(-(replaced as isize)..0),
+            cx,
+        );
+    }
+}
+
+impl Vim {
+    fn abbreviations_to_expand(&self) -> Option<HashMap<String, String>> {
+        // While replaying, the expansions are already among the insertions.
+        let expands = self.state().mode == Mode::Insert
+            && self.workspace_state.replayer.is_none()
+            && !self.workspace_state.abbreviations.is_empty();
+        expands.then(|| self.workspace_state.abbreviations.clone())
+    }
+}
+
+/// Replaces the abbreviation before `typed` at each cursor, returning the
+/// trigger that was expanded at the newest one.
+fn expand_in_editor(
+    editor: &mut Editor,
+    typed: char,
+    abbreviations: &HashMap<String, String>,
+    cx: &mut ViewContext<Editor>,
+) -> Option<String> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let newest_id = editor.selections.newest_anchor().id;
+    let mut edits = Vec::new();
+    let mut expanded = None;
+
+    for selection in editor.selections.all::<usize>(cx) {
+        let Some(end) = selection.head().checked_sub(typed.len_utf8()) else {
+            continue;
+        };
+        let scope = snapshot.language_scope_at(end);
+        let is_keyword = |c| char_kind(&scope, c) == CharKind::Word;
+        if is_keyword(typed) || snapshot.chars_at(end).next() != Some(typed) {
+            continue;
+        }
+        let line_start = snapshot.point_to_offset(Point::new(snapshot.offset_to_point(end).row, 0));
+        let line: String = snapshot.text_for_range(line_start..end).collect();
+
+        let trigger = abbreviations
+            .keys()
+            .filter(|trigger| {
+                let Some(before) = line.strip_suffix(trigger.as_str()) else {
+                    return false;
+                };
+                AbbreviationKind::of(trigger, is_keyword).is_some_and(|kind| {
+                    kind.expands_after(trigger, before.chars().last(), is_keyword)
+                })
+            })
+            .max_by_key(|trigger| trigger.len());
+        if let Some(trigger) = trigger {
+            edits.push((end - trigger.len()..end, abbreviations[trigger].clone()));
+            if selection.id == newest_id {
+                expanded = Some(trigger.clone());
+            }
+        }
+    }
+
+    if !edits.is_empty() {
+        editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+    }
+    expanded
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[gpui::test]
+    async fn test_abbreviations(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes(": i a b space t e h space t h e enter");
+        cx.simulate_keystrokes("i t e h space");
+        cx.assert_state("the ˇ", Mode::Insert);
+
+        // not in the middle of a word, or until the word ends
+        cx.simulate_keystrokes("x t e h space t e h");
+        cx.assert_state("the xteh tehˇ", Mode::Insert);
+        cx.simulate_keystrokes(",");
+        cx.assert_state("the xteh the,ˇ", Mode::Insert);
+
+        // repeating the insertion types the expansion, and undoing takes back
+        // the expansion with the rest of the insertion
+        cx.simulate_keystrokes("escape");
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes("i t e h space escape");
+        cx.assert_state("theˇ ", Mode::Normal);
+        cx.simulate_keystrokes(".");
+        cx.assert_state("thetheˇ  ", Mode::Normal);
+        cx.simulate_keystrokes("u");
+        cx.assert_state("theˇ ", Mode::Normal);
+        cx.simulate_keystrokes("u");
+        cx.assert_state("ˇ", Mode::Normal);
+
+        cx.simulate_keystrokes(": a b c l e a r enter");
+        cx.simulate_keystrokes("cc t e h space");
+        cx.assert_state("teh ˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_abbreviation_kinds(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.simulate_keystrokes(": i a b space # i space i n c l u d e enter");
+        cx.simulate_keystrokes(": i a b space d e f # space d e f i n e enter");
+
+        // end-id after a space or a keyword character, but not punctuation
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes("i # i space x # i space . # i space");
+        cx.assert_state("include xinclude .#i ˇ", Mode::Insert);
+
+        // non-id only after a space
+        cx.simulate_keystrokes("escape");
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes("i d e f # space x d e f # space");
+        cx.assert_state("define xdef# ˇ", Mode::Insert);
+    }
+}
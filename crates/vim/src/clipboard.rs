@@ -0,0 +1,193 @@
+//! The system clipboards behind the `+` and `*` registers, and behind the
+//! unnamed register when `use_system_clipboard` asks for it. Over SSH, where
+//! the OS's clipboard isn't the user's, [`Osc52Clipboard`] can go through the
+//! surrounding terminal instead. Tests can give vim a [`TestClipboard`], to
+//! set and check what's on each clipboard without touching the one the OS
+//! provides.
+
+use std::{
+    cell::RefCell,
+    io::{IsTerminal, Write},
+};
+
+use base64::prelude::*;
+use gpui::{AppContext, ClipboardItem};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use util::ResultExt;
+
+/// Where the `+` and `*` registers are kept, as set by the `clipboard`
+/// setting.
+#[derive(Copy, Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ClipboardBackend {
+    /// The clipboards of the platform that Zed is running on.
+    #[default]
+    System,
+    /// The clipboard of the terminal that Zed was started from, which is
+    /// written with OSC 52 escape sequences.
+    Osc52,
+}
+
+/// Which of the system's clipboards a register is kept in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum ClipboardKind {
+    /// The clipboard used to copy and paste, behind `+`.
+    Clipboard,
+    /// The selection that middle-click pastes on Linux, behind `*`. Elsewhere
+    /// there is only the one clipboard, so `*` uses that.
+    Primary,
+}
+
+pub(crate) trait ClipboardProvider {
+    fn read(&self, kind: ClipboardKind, cx: &AppContext) -> Option<ClipboardItem>;
+    fn write(&self, kind: ClipboardKind, item: ClipboardItem, cx: &AppContext);
+}
+
+/// The clipboards of the platform that Zed is running on.
+pub(crate) struct SystemClipboard;
+
+impl ClipboardProvider for SystemClipboard {
+    fn read(&self, kind: ClipboardKind, cx: &AppContext) -> Option<ClipboardItem> {
+        match kind {
+            ClipboardKind::Clipboard => cx.read_from_clipboard(),
+            #[cfg(target_os = "linux")]
+            ClipboardKind::Primary => cx.read_from_primary(),
+            #[cfg(not(target_os = "linux"))]
+            ClipboardKind::Primary => cx.read_from_clipboard(),
+        }
+    }
+
+    fn write(&self, kind: ClipboardKind, item: ClipboardItem, cx: &AppContext) {
+        match kind {
+            ClipboardKind::Clipboard => cx.write_to_clipboard(item),
+            #[cfg(target_os = "linux")]
+            ClipboardKind::Primary => cx.write_to_primary(item),
+            #[cfg(not(target_os = "linux"))]
+            ClipboardKind::Primary => cx.write_to_clipboard(item),
+        }
+    }
+}
+
+/// Writes to the terminal's clipboard with OSC 52 escape sequences on
+/// standard output, which terminals pass on to the clipboard of the machine
+/// they're running on. Few terminals answer requests to read the clipboard,
+/// so reads get back what vim last wrote instead. When standard output isn't
+/// a terminal, as when Zed wasn't started from one, the system clipboard is
+/// used instead.
+pub(crate) struct Osc52Clipboard {
+    output: Option<Box<dyn Fn(&str)>>,
+    clipboard: RefCell<Option<ClipboardItem>>,
+    primary: RefCell<Option<ClipboardItem>>,
+}
+
+impl Default for Osc52Clipboard {
+    fn default() -> Self {
+        if !std::io::stdout().is_terminal() {
+            return Self::without_terminal();
+        }
+        Self::new(|sequence| {
+            let mut stdout = std::io::stdout();
+            stdout
+                .write_all(sequence.as_bytes())
+                .and_then(|_| stdout.flush())
+                .log_err();
+        })
+    }
+}
+
+impl Osc52Clipboard {
+    pub fn new(output: impl Fn(&str) + 'static) -> Self {
+        Self {
+            output: Some(Box::new(output)),
+            clipboard: Default::default(),
+            primary: Default::default(),
+        }
+    }
+
+    /// A clipboard with no terminal to write to, which keeps the registers in
+    /// the system clipboard.
+    pub fn without_terminal() -> Self {
+        Self {
+            output: None,
+            clipboard: Default::default(),
+            primary: Default::default(),
+        }
+    }
+
+    fn contents(&self, kind: ClipboardKind) -> &RefCell<Option<ClipboardItem>> {
+        match kind {
+            ClipboardKind::Clipboard => &self.clipboard,
+            ClipboardKind::Primary => &self.primary,
+        }
+    }
+}
+
+/// The OSC 52 sequence that puts `text` on the terminal's clipboard, or on
+/// its primary selection.
+fn osc52_sequence(kind: ClipboardKind, text: &str) -> String {
+    let selection = match kind {
+        ClipboardKind::Clipboard => 'c',
+        ClipboardKind::Primary => 'p',
+    };
+    format!("\x1b]52;{selection};{}\x07", BASE64_STANDARD.encode(text))
+}
+
+impl ClipboardProvider for Osc52Clipboard {
+    fn read(&self, kind: ClipboardKind, cx: &AppContext) -> Option<ClipboardItem> {
+        if self.output.is_none() {
+            return SystemClipboard.read(kind, cx);
+        }
+        self.contents(kind).borrow().clone()
+    }
+
+    fn write(&self, kind: ClipboardKind, item: ClipboardItem, cx: &AppContext) {
+        let Some(output) = self.output.as_ref() else {
+            return SystemClipboard.write(kind, item, cx);
+        };
+        if let Some(text) = item.text() {
+            output(&osc52_sequence(kind, &text));
+        }
+        self.contents(kind).replace(Some(item));
+    }
+}
+
+/// Clipboards that only exist in the test. The clipboard and the primary
+/// selection are kept apart, as on Linux, whatever platform the test runs on.
+#[cfg(test)]
+#[derive(Default)]
+pub(crate) struct TestClipboard {
+    clipboard: RefCell<Option<ClipboardItem>>,
+    primary: RefCell<Option<ClipboardItem>>,
+}
+
+#[cfg(test)]
+impl TestClipboard {
+    fn contents(&self, kind: ClipboardKind) -> &RefCell<Option<ClipboardItem>> {
+        match kind {
+            ClipboardKind::Clipboard => &self.clipboard,
+            ClipboardKind::Primary => &self.primary,
+        }
+    }
+
+    /// Puts `text` on the clipboard, as though it had been copied elsewhere.
+    pub fn set_text(&self, kind: ClipboardKind, text: &str) {
+        self.contents(kind)
+            .replace(Some(ClipboardItem::new_string(text.to_string())));
+    }
+
+    pub fn text(&self, kind: ClipboardKind) -> Option<String> {
+        self.contents(kind).borrow().as_ref()?.text()
+    }
+}
+
+#[cfg(test)]
+impl ClipboardProvider for TestClipboard {
+    fn read(&self, kind: ClipboardKind, _: &AppContext) -> Option<ClipboardItem> {
+        self.contents(kind).borrow().clone()
+    }
+
+    fn write(&self, kind: ClipboardKind, item: ClipboardItem, _: &AppContext) {
+        self.contents(kind).replace(Some(item));
+    }
+}
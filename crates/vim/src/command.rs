@@ -1,28 +1,45 @@
-use std::{iter::Peekable, ops::Range, str::Chars, sync::OnceLock};
+use std::{
+    cmp::Ordering,
+    io::Write,
+    iter::Peekable,
+    ops::Range,
+    process::{Command, Stdio},
+    str::Chars,
+    sync::OnceLock,
+};
 
 use anyhow::{anyhow, Result};
 use command_palette_hooks::CommandInterceptResult;
-use editor::{
-    actions::{SortLinesCaseInsensitive, SortLinesCaseSensitive},
-    Editor, ToPoint,
+use editor::{display_map::ToDisplayPoint, Bias, Editor, ToOffset, ToPoint};
+use gpui::{
+    actions, impl_actions, Action, AppContext, AsyncWindowContext, DismissEvent, Global, Keystroke,
+    Modifiers, ViewContext,
 };
-use gpui::{actions, impl_actions, Action, AppContext, Global, ViewContext};
-use language::Point;
-use multi_buffer::MultiBufferRow;
+use language::{Point, SelectionGoal};
+use multi_buffer::{Anchor, MultiBufferRow, MultiBufferSnapshot};
+use regex::Regex;
 use serde::Deserialize;
 use ui::WindowContext;
 use util::ResultExt;
-use workspace::{notifications::NotifyResultExt, SaveIntent, Workspace};
+use workspace::{
+    notifications::{NotificationId, NotifyResultExt, NotifyTaskExt},
+    SaveIntent, Toast, Workspace,
+};
 
 use crate::{
+    abbreviation::{Abbreviate, ClearAbbreviations},
+    digraph::ShowDigraphs,
+    expression::evaluate,
+    mapping::{Map, MapMode, Unmap},
     motion::{EndOfDocument, Motion, StartOfDocument},
     normal::{
+        character_info::ShowCharacterInfo,
         move_cursor,
-        search::{FindCommand, ReplaceCommand, Replacement},
-        JoinLines,
+        search::{FindCommand, RepeatSubstitute, ReplaceCommand, Replacement},
+        Indent, JoinLines, JoinLinesNoWhitespace, Outdent,
     },
     state::Mode,
-    visual::VisualDeleteLine,
+    visual::{VisualDeleteLine, VisualYankLine},
     Vim,
 };
 
@@ -36,10 +53,127 @@ pub struct WithRange {
     is_count: bool,
     range: CommandRange,
     action: Box<dyn Action>,
+    register: Option<char>,
+    /// A count for the action, as when `:>>` shifts by two levels.
+    count: Option<usize>,
+}
+
+/// A command typed on the command line, which is remembered for `q:` before
+/// `action` runs.
+#[derive(Debug)]
+pub struct HistoryCommand {
+    command: String,
+    action: Box<dyn Action>,
+}
+
+/// Runs `keys` as though they were typed in normal mode (`:normal`).
+/// With a range the keys are run once per line, starting from the
+/// beginning of each line. Mappings apply to the keys unless `noremap` is
+/// set (`:normal!`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct NormalCommand {
+    range: Option<CommandRange>,
+    keys: String,
+    noremap: bool,
+}
+
+/// Pipes the lines in `range` (or the whole buffer) to the standard input of
+/// `command` and shows its output, leaving the buffer unchanged (`:w !cmd`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct WriteToCommand {
+    range: Option<CommandRange>,
+    command: String,
+}
+
+/// Replaces the lines in `range` with the output of `command` when they are
+/// piped to its standard input (`:{range}!cmd`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FilterCommand {
+    range: CommandRange,
+    command: String,
 }
 
-actions!(vim, [VisualCommand, CountCommand]);
-impl_actions!(vim, [GoToLine, WithRange]);
+/// Runs `command` on each line in `range` (by default the whole buffer) that
+/// matches `pattern`, or that doesn't match it if `invert` is set (`:g` and `:v`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct GlobalCommand {
+    range: Option<CommandRange>,
+    pattern: String,
+    command: String,
+    invert: bool,
+}
+
+/// Sorts the lines in `range` (by default the whole buffer) in a single edit
+/// (`:sort`). With a `pattern`, lines are sorted by the text after its first
+/// match instead.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct SortCommand {
+    range: Option<CommandRange>,
+    reverse: bool,
+    numeric: bool,
+    unique: bool,
+    ignore_case: bool,
+    pattern: Option<String>,
+}
+
+/// Removes the spaces and tabs at the ends of the lines in `range` (by default
+/// the whole buffer), leaving blank lines empty rather than deleting them.
+/// Whitespace inside a multi-line string is kept unless `in_strings` is set
+/// (`:TrimTrailingWhitespace!`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct TrimWhitespaceCommand {
+    range: Option<CommandRange>,
+    in_strings: bool,
+}
+
+/// Inserts the contents of `register` (by default the unnamed register) as
+/// lines below the addressed line, or above it if `before` is set (`:put`).
+/// `:0put` inserts above the first line. With the `=` register, `expression`
+/// is evaluated and its result is put instead (`:put =1+1`).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct PutCommand {
+    range: Option<CommandRange>,
+    register: Option<char>,
+    expression: Option<String>,
+    before: bool,
+}
+
+/// Moves the lines in `range` (by default the current line) to below the line
+/// at `address` (`:move`), or copies them there if `copy` is set (`:copy` or
+/// `:t`). An `address` of 0 puts them above the first line.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MoveLinesCommand {
+    range: Option<CommandRange>,
+    address: CommandRange,
+    copy: bool,
+}
+
+actions!(
+    vim,
+    [
+        VisualCommand,
+        CountCommand,
+        ClearRegisters,
+        ExMode,
+        ExitExMode
+    ]
+);
+impl_actions!(
+    vim,
+    [
+        GoToLine,
+        WithRange,
+        HistoryCommand,
+        NormalCommand,
+        WriteToCommand,
+        FilterCommand,
+        GlobalCommand,
+        SortCommand,
+        TrimWhitespaceCommand,
+        PutCommand,
+        MoveLinesCommand
+    ]
+);
 
 impl<'de> Deserialize<'de> for WithRange {
     fn deserialize<D>(_: D) -> Result<Self, D::Error>
@@ -52,7 +186,10 @@ impl<'de> Deserialize<'de> for WithRange {
 
 impl PartialEq for WithRange {
     fn eq(&self, other: &Self) -> bool {
-        self.range == other.range && self.action.partial_eq(&*other.action)
+        self.range == other.range
+            && self.register == other.register
+            && self.count == other.count
+            && self.action.partial_eq(&*other.action)
     }
 }
 
@@ -62,6 +199,43 @@ impl Clone for WithRange {
             is_count: self.is_count,
             range: self.range.clone(),
             action: self.action.boxed_clone(),
+            register: self.register,
+            count: self.count,
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for HistoryCommand {
+    fn deserialize<D>(_: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        Err(serde::de::Error::custom(
+            "Cannot deserialize HistoryCommand",
+        ))
+    }
+}
+
+impl PartialEq for HistoryCommand {
+    fn eq(&self, other: &Self) -> bool {
+        self.command == other.command && self.action.partial_eq(&*other.action)
+    }
+}
+
+impl Clone for HistoryCommand {
+    fn clone(&self) -> Self {
+        Self {
+            command: self.command.clone(),
+            action: self.action.boxed_clone(),
+        }
+    }
+}
+
+impl HistoryCommand {
+    pub(crate) fn new(command: &str, action: Box<dyn Action>) -> Self {
+        Self {
+            command: command.trim_start_matches(':').to_string(),
+            action,
         }
     }
 }
@@ -80,6 +254,37 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         );
     });
 
+    workspace.register_action(|_, action: &HistoryCommand, cx| {
+        let ex_mode = Vim::update(cx, |vim, _| {
+            push_history(&mut vim.workspace_state.command_history, &action.command);
+            let state = &mut vim.workspace_state;
+            state.ex_command_ran = state.ex_mode;
+            state.ex_mode
+        });
+        cx.dispatch_action(action.action.boxed_clone());
+        if ex_mode {
+            // once the command has run, in case it was `:visual`
+            cx.defer(|workspace, cx| {
+                if Vim::read(cx).workspace_state.ex_mode {
+                    prompt_ex_command(workspace, cx);
+                }
+            });
+        }
+    });
+
+    workspace.register_action(|workspace, _: &ExMode, cx| enter_ex_mode(workspace, cx));
+    workspace.register_action(|_, _: &ExitExMode, cx| {
+        Vim::update(cx, |vim, _| vim.workspace_state.ex_mode = false)
+    });
+
+    workspace.register_action(|_, _: &ClearRegisters, cx| {
+        Vim::update(cx, |vim, _| {
+            vim.workspace_state
+                .registers
+                .retain(|register, _| !register.is_ascii_alphanumeric() && *register != '"');
+        })
+    });
+
     workspace.register_action(|workspace: &mut Workspace, action: &GoToLine, cx| {
         Vim::update(cx, |vim, cx| {
             vim.switch_mode(Mode::Normal, false, cx);
@@ -114,20 +319,42 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
                     return anyhow::Ok(());
                 };
                 let range = range?;
-                vim.update_active_editor(cx, |_, editor, cx| {
-                    editor.change_selections(None, cx, |s| {
-                        let end = Point::new(range.end.0, s.buffer().line_len(range.end));
-                        s.select_ranges([end..Point::new(range.start.0, 0)]);
+                // Like `dd`, `:d` keeps the cursor's column on the line that
+                // takes the place of the deleted ones.
+                let keep_column = action.action.as_any().is::<VisualDeleteLine>();
+                let column = vim
+                    .update_active_editor(cx, |_, editor, cx| {
+                        let column = editor.selections.newest::<Point>(cx).head().column;
+                        editor.change_selections(None, cx, |s| {
+                            let end = Point::new(range.end.0, s.buffer().line_len(range.end));
+                            s.select_ranges([end..Point::new(range.start.0, 0)]);
+                        });
+                        column
                     })
-                });
+                    .filter(|_| keep_column)
+                    .unwrap_or(0);
+                if let Some(register) = action.register {
+                    vim.update_state(|state| state.selected_register = Some(register));
+                }
+                if let Some(count) = action.count {
+                    vim.update_state(|state| state.pre_count = Some(count));
+                }
                 cx.dispatch_action(action.action.boxed_clone());
                 cx.defer(move |cx| {
                     Vim::update(cx, |vim, cx| {
                         vim.update_active_editor(cx, |_, editor, cx| {
                             editor.change_selections(None, cx, |s| {
-                                s.select_ranges([
-                                    Point::new(range.start.0, 0)..Point::new(range.start.0, 0)
-                                ]);
+                                s.move_with(|map, selection| {
+                                    // The range may have run to the end of the
+                                    // buffer, leaving the last line in its place.
+                                    let row =
+                                        range.start.0.min(map.buffer_snapshot.max_point().row);
+                                    let cursor = map.clip_point(
+                                        Point::new(row, column).to_display_point(map),
+                                        Bias::Left,
+                                    );
+                                    selection.collapse_to(cursor, SelectionGoal::None)
+                                })
                             })
                         });
                     })
@@ -138,6 +365,987 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             .notify_err(workspace, cx);
         }
     });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &NormalCommand, cx| {
+        let Some(line_starts) = action.line_starts(cx).notify_err(workspace, cx) else {
+            return;
+        };
+        let keystrokes = keystrokes_for_text(&action.keys);
+        let noremap = action.noremap;
+        cx.window_context()
+            .spawn(|mut cx| async move {
+                run_keystrokes(line_starts, keystrokes, noremap, &mut cx).await
+            })
+            .detach_and_log_err(cx);
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &GlobalCommand, cx| {
+        // With no command (or `:p`) the matching lines are only visited, and
+        // shown as `:p` would print them.
+        let print = "print".starts_with(action.command.as_str());
+        let command = if print {
+            None
+        } else if let Some(result) = command_interceptor(&action.command, cx) {
+            Some(result.action)
+        } else {
+            workspace.show_error(&anyhow!("Not an editor command: {}", action.command), cx);
+            return;
+        };
+        let Some(lines) = action.matching_lines(cx).notify_err(workspace, cx) else {
+            return;
+        };
+        if lines.is_empty() {
+            let message = if action.invert {
+                "Pattern found in every line"
+            } else {
+                "Pattern not found"
+            };
+            workspace.show_error(&anyhow!("{}: {}", message, action.pattern), cx);
+            return;
+        }
+        let matches = lines.len();
+        let (lines, text): (Vec<_>, Vec<_>) = lines.into_iter().unzip();
+        if print {
+            workspace.show_toast(
+                Toast::new(NotificationId::unique::<GlobalCommand>(), text.join("\n")),
+                cx,
+            );
+        }
+
+        cx.window_context()
+            .spawn(|mut cx| async move {
+                for line in lines {
+                    // As with vim's marks, a line that the command deleted
+                    // from an earlier line isn't visited.
+                    if !cx.update(|cx| line_exists(&line, cx))? {
+                        continue;
+                    }
+                    cx.update(|cx| move_to_line_start(line.start, cx))?;
+                    let Some(command) = command.as_ref() else {
+                        continue;
+                    };
+                    if let Some(normal) = command.as_any().downcast_ref::<NormalCommand>() {
+                        let line_starts = cx.update(|cx| normal.line_starts(cx))??;
+                        let keystrokes = keystrokes_for_text(&normal.keys);
+                        run_keystrokes(line_starts, keystrokes, normal.noremap, &mut cx).await?;
+                    } else {
+                        cx.update(|cx| cx.dispatch_action(command.boxed_clone()))?;
+                    }
+                }
+                cx.update(|cx| {
+                    Vim::update(cx, |vim, _| {
+                        let plural = if matches == 1 { "" } else { "es" };
+                        vim.set_status_message(format!("{matches} match{plural}"))
+                    })
+                })?;
+                anyhow::Ok(())
+            })
+            .detach_and_notify_err(cx);
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &WriteToCommand, cx| {
+        let result = Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let range = match action.range.as_ref() {
+                    Some(range) => range.buffer_range(vim, editor, cx)?,
+                    None => MultiBufferRow(0)..snapshot.max_buffer_row(),
+                };
+                let end = Point::new(range.end.0, snapshot.line_len(range.end));
+                let mut text = snapshot
+                    .text_for_range(Point::new(range.start.0, 0)..end)
+                    .collect::<String>();
+                text.push('\n');
+                anyhow::Ok(text)
+            })
+        });
+        let Some(input) = result.transpose().notify_err(workspace, cx).flatten() else {
+            return;
+        };
+
+        let command = action.command.clone();
+        let output = cx
+            .background_executor()
+            .spawn(async move { run_shell_command(&command, &input) });
+        cx.spawn(|workspace, mut cx| async move {
+            let output = output.await;
+            workspace.update(&mut cx, |workspace, cx| match output {
+                Ok(output) => workspace.show_toast(
+                    Toast::new(NotificationId::unique::<WriteToCommand>(), output),
+                    cx,
+                ),
+                Err(err) => workspace.show_error(&err, cx),
+            })
+        })
+        .detach_and_log_err(cx);
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &FilterCommand, cx| {
+        let result = Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let range = action.range.buffer_range(vim, editor, cx)?;
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let start = Point::new(range.start.0, 0);
+                let end = Point::new(range.end.0, snapshot.line_len(range.end));
+                let mut input = snapshot.text_for_range(start..end).collect::<String>();
+                input.push('\n');
+                let lines = range.end.0 - range.start.0 + 1;
+                let range = snapshot.anchor_before(start)..snapshot.anchor_after(end);
+                anyhow::Ok((cx.view().downgrade(), range, lines, input))
+            })
+        });
+        let Some((editor, range, lines, input)) =
+            result.transpose().notify_err(workspace, cx).flatten()
+        else {
+            return;
+        };
+
+        let command = action.command.clone();
+        let output = cx
+            .background_executor()
+            .spawn(async move { run_filter_command(&command, &input) });
+        cx.window_context()
+            .spawn(|mut cx| async move {
+                // The lines are left as they were if the command fails.
+                let output = output.await?;
+                editor.update(&mut cx, |editor, cx| {
+                    editor.transact(cx, |editor, cx| {
+                        editor.edit([(range.clone(), output)], cx);
+                        let snapshot = editor.buffer().read(cx).snapshot(cx);
+                        let row = range.start.to_point(&snapshot).row;
+                        let indent = snapshot.indent_size_for_line(MultiBufferRow(row));
+                        let cursor = Point::new(row, indent.len);
+                        editor.change_selections(None, cx, |s| s.select_ranges([cursor..cursor]));
+                    });
+                    Vim::update(cx, |vim, cx| {
+                        vim.report(lines, format!("{} lines filtered", lines), cx)
+                    });
+                })
+            })
+            .detach_and_notify_err(cx);
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &SortCommand, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            let regex = action
+                .pattern
+                .as_ref()
+                .map(|pattern| pattern_regex(vim, pattern))
+                .transpose()?;
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let range = match action.range.as_ref() {
+                    Some(range) => range.buffer_range(vim, editor, cx)?,
+                    None => MultiBufferRow(0)..snapshot.max_buffer_row(),
+                };
+                let start = Point::new(range.start.0, 0);
+                let end = Point::new(range.end.0, snapshot.line_len(range.end));
+                let text = snapshot.text_for_range(start..end).collect::<String>();
+                let lines = action.sort(text.split('\n').collect(), regex.as_ref());
+                let indent = lines.first().map_or(0, |line| {
+                    line.len() - line.trim_start_matches([' ', '\t']).len()
+                });
+                let cursor = Point::new(range.start.0, indent as u32);
+                editor.transact(cx, |editor, cx| {
+                    editor.edit([(start..end, lines.join("\n"))], cx);
+                    editor.change_selections(None, cx, |s| s.select_ranges([cursor..cursor]));
+                });
+                anyhow::Ok(())
+            })
+            .unwrap_or(Ok(()))
+        })
+        .notify_err(workspace, cx);
+    });
+
+    workspace.register_action(
+        |workspace: &mut Workspace, action: &TrimWhitespaceCommand, cx| {
+            Vim::update(cx, |vim, cx| {
+                vim.switch_mode(Mode::Normal, false, cx);
+                vim.update_active_editor(cx, |vim, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let range = match action.range.as_ref() {
+                        Some(range) => range.buffer_range(vim, editor, cx)?,
+                        None => MultiBufferRow(0)..snapshot.max_buffer_row(),
+                    };
+                    let edits = (range.start.0..=range.end.0)
+                        .filter_map(|row| {
+                            let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                            let trailing = snapshot
+                                .reversed_chars_at(end)
+                                .take_while(|c| matches!(c, ' ' | '\t'))
+                                .count() as u32;
+                            let start = Point::new(row, end.column - trailing);
+                            let keep = trailing == 0
+                                || (!action.in_strings
+                                    && in_multiline_string(
+                                        &snapshot,
+                                        snapshot.point_to_offset(start),
+                                    ));
+                            (!keep).then_some((start..end, ""))
+                        })
+                        .collect::<Vec<_>>();
+                    editor.transact(cx, |editor, cx| editor.edit(edits, cx));
+                    anyhow::Ok(())
+                })
+                .unwrap_or(Ok(()))
+            })
+            .notify_err(workspace, cx);
+        },
+    );
+
+    workspace.register_action(|workspace: &mut Workspace, action: &PutCommand, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let row = match action.range.as_ref() {
+                    Some(range) => range.head().buffer_row(vim, editor, cx)?.0,
+                    None => editor.selections.newest::<Point>(cx).head().row,
+                };
+                let before = action.before
+                    || action.range.as_ref().is_some_and(|range| {
+                        matches!(range.head(), Position::Line { row: 0, offset: 0 })
+                    });
+                let text = if let Some(expression) = action.expression.as_ref() {
+                    let lines = evaluate(expression)?.to_lines();
+                    // An empty list puts no lines at all, rather than a blank one.
+                    if lines.is_empty() {
+                        return Ok(());
+                    }
+                    lines.join("\n")
+                } else {
+                    let Some(register) = vim
+                        .read_register(action.register, Some(editor), cx)
+                        .filter(|register| !register.text.is_empty())
+                    else {
+                        return Err(anyhow!(
+                            "Nothing in register {}",
+                            action.register.unwrap_or('"')
+                        ));
+                    };
+                    register
+                        .text
+                        .strip_suffix('\n')
+                        .unwrap_or(register.text.as_ref())
+                        .to_string()
+                };
+                let line_count = text.split('\n').count() as u32;
+
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let (point, text, last_row) = if before {
+                    (Point::new(row, 0), text + "\n", row + line_count - 1)
+                } else {
+                    let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                    (end, "\n".to_string() + &text, row + line_count)
+                };
+                editor.transact(cx, |editor, cx| {
+                    editor.edit([(point..point, text)], cx);
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let indent = snapshot.indent_size_for_line(MultiBufferRow(last_row));
+                    let cursor = Point::new(last_row, indent.len);
+                    editor.change_selections(None, cx, |s| s.select_ranges([cursor..cursor]));
+                });
+                anyhow::Ok(())
+            })
+            .unwrap_or(Ok(()))
+        })
+        .notify_err(workspace, cx);
+    });
+
+    workspace.register_action(|workspace: &mut Workspace, action: &MoveLinesCommand, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let range = match action.range.as_ref() {
+                    Some(range) => range.buffer_range(vim, editor, cx)?,
+                    None => {
+                        let row = editor.selections.newest::<Point>(cx).head().row;
+                        MultiBufferRow(row)..MultiBufferRow(row)
+                    }
+                };
+                // The row the lines end up in front of, before they're removed.
+                let insert_row = match action.address.head() {
+                    Position::Line { row: 0, offset: 0 } => 0,
+                    address => address.buffer_row(vim, editor, cx)?.0 + 1,
+                };
+                let (start, end) = (range.start.0, range.end.0);
+                if !action.copy && insert_row > start && insert_row <= end {
+                    return Err(anyhow!("E134: Cannot move a range of lines into itself"));
+                }
+
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let max_row = snapshot.max_buffer_row().0;
+                let line_count = end - start + 1;
+                let text = snapshot
+                    .text_for_range(
+                        Point::new(start, 0)..Point::new(end, snapshot.line_len(range.end)),
+                    )
+                    .collect::<String>();
+
+                let mut edits = Vec::new();
+                let last_row = if !action.copy && (insert_row == start || insert_row == end + 1) {
+                    end
+                } else {
+                    if insert_row <= max_row {
+                        let point = Point::new(insert_row, 0);
+                        edits.push((point..point, text + "\n"));
+                    } else {
+                        let point = Point::new(max_row, snapshot.line_len(MultiBufferRow(max_row)));
+                        edits.push((point..point, "\n".to_string() + &text));
+                    }
+                    if action.copy {
+                        insert_row + line_count - 1
+                    } else {
+                        let deleted = if end < max_row {
+                            Point::new(start, 0)..Point::new(end + 1, 0)
+                        } else {
+                            let above = start - 1;
+                            Point::new(above, snapshot.line_len(MultiBufferRow(above)))
+                                ..Point::new(end, snapshot.line_len(range.end))
+                        };
+                        edits.push((deleted, String::new()));
+                        if insert_row > end {
+                            insert_row - 1
+                        } else {
+                            insert_row + line_count - 1
+                        }
+                    }
+                };
+
+                editor.transact(cx, |editor, cx| {
+                    editor.edit(edits, cx);
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let indent = snapshot.indent_size_for_line(MultiBufferRow(last_row));
+                    let cursor = Point::new(last_row, indent.len);
+                    editor.change_selections(None, cx, |s| s.select_ranges([cursor..cursor]));
+                });
+                anyhow::Ok(())
+            })
+            .unwrap_or(Ok(()))
+        })
+        .notify_err(workspace, cx);
+    });
+}
+
+/// Runs `command` in the system shell with `input` as its standard input,
+/// returning everything it printed.
+pub(crate) fn run_shell_command(command: &str, input: &str) -> Result<String> {
+    let output = shell_output(command, input)?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() && text.trim().is_empty() {
+        return Err(anyhow!("shell returned {}", output.status));
+    }
+    Ok(text.trim_end().to_string())
+}
+
+/// Runs `command` as a filter of `input`, returning what it printed without
+/// its final newline, or an error if it failed.
+fn run_filter_command(command: &str, input: &str) -> Result<String> {
+    let output = shell_output(command, input)?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    if !output.status.success() {
+        return Err(match text.trim() {
+            "" => anyhow!("shell returned {}", output.status),
+            text => anyhow!("shell returned {}: {}", output.status, text),
+        });
+    }
+    Ok(text.strip_suffix('\n').unwrap_or(&text).to_string())
+}
+
+fn shell_output(command: &str, input: &str) -> Result<std::process::Output> {
+    let (shell, flag) = if cfg!(windows) {
+        ("cmd", "/C")
+    } else {
+        ("sh", "-c")
+    };
+    let mut child = Command::new(shell)
+        .arg(flag)
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    // Write from another thread so that a command producing a lot of output
+    // can't deadlock against us while its input is still being written.
+    let mut stdin = child
+        .stdin
+        .take()
+        .ok_or_else(|| anyhow!("failed to open stdin of {}", command))?;
+    let input = input.to_string();
+    let writer = std::thread::spawn(move || stdin.write_all(input.as_bytes()));
+    let output = child.wait_with_output()?;
+    // The command is free to exit without reading all of its input.
+    writer.join().ok();
+    Ok(output)
+}
+
+/// Parses `g[lobal][!]/{pattern}/[cmd]` or `v[global]/{pattern}/[cmd]`. As with
+/// `:s`, most non-alphanumeric characters may be used instead of `/`.
+fn parse_global_command(query: &str, range: Option<CommandRange>) -> Option<GlobalCommand> {
+    let name_len = query
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let (name, rest) = query.split_at(name_len);
+    let mut invert = if !name.is_empty() && "global".starts_with(name) {
+        false
+    } else if !name.is_empty() && "vglobal".starts_with(name) {
+        true
+    } else {
+        return None;
+    };
+
+    let mut chars = rest.chars();
+    let mut delimiter = chars.next()?;
+    if delimiter == '!' && !invert {
+        invert = true;
+        delimiter = chars.next()?;
+    }
+    if delimiter.is_alphanumeric() || matches!(delimiter, '"' | '|' | '\\' | ' ') {
+        return None;
+    }
+
+    let pattern = parse_delimited(&mut chars, delimiter);
+    Some(GlobalCommand {
+        range,
+        pattern,
+        command: chars.as_str().trim().to_string(),
+        invert,
+    })
+}
+
+/// Consumes `chars` up to and including the next unescaped `delimiter`,
+/// returning the text before it with any escaped delimiters unescaped.
+fn parse_delimited(chars: &mut impl Iterator<Item = char>, delimiter: char) -> String {
+    let mut text = String::new();
+    let mut escaped = false;
+    for c in chars {
+        if escaped {
+            escaped = false;
+            if c != delimiter {
+                text.push('\\');
+            }
+            text.push(c);
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            break;
+        } else {
+            text.push(c);
+        }
+    }
+    text
+}
+
+/// Parses `y[ank] {x}` and `d[elete] {x}`, which yank or delete lines into
+/// register `x`. A number after the command is a count rather than a
+/// register, and isn't handled here.
+fn parse_register_command(query: &str) -> Option<(Box<dyn Action>, char)> {
+    let (name, register) = query.split_once(' ')?;
+    let mut chars = register.trim().chars();
+    let register = chars.next().filter(|c| !c.is_ascii_digit())?;
+    if chars.next().is_some() {
+        return None;
+    }
+    let action = if !name.is_empty() && "yank".starts_with(name) {
+        VisualYankLine.boxed_clone()
+    } else if !name.is_empty() && "delete".starts_with(name) {
+        VisualDeleteLine.boxed_clone()
+    } else {
+        return None;
+    };
+    Some((action, register))
+}
+
+/// Parses `reg[isters] clear`, which empties the unnamed, numbered and named
+/// registers.
+fn parse_registers_command(query: &str) -> Option<ClearRegisters> {
+    let (name, argument) = query.split_once(' ')?;
+    (name.len() >= 3 && "registers".starts_with(name) && argument.trim() == "clear")
+        .then_some(ClearRegisters)
+}
+
+/// Parses `>>` or `<<` (or any longer run), which shift the lines once for
+/// each character. A single `>` or `<` is one of the [`commands`].
+fn parse_shift_command(query: &str) -> Option<(Box<dyn Action>, usize)> {
+    let count = query.chars().count();
+    if count < 2 {
+        None
+    } else if query.chars().all(|c| c == '>') {
+        Some((Indent.boxed_clone(), count))
+    } else if query.chars().all(|c| c == '<') {
+        Some((Outdent.boxed_clone(), count))
+    } else {
+        None
+    }
+}
+
+/// Parses `pu[t][!] [x]` or `pu[t][!] ={expr}`.
+fn parse_put_command(query: &str, range: Option<CommandRange>) -> Option<PutCommand> {
+    let name_len = query
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let (name, rest) = query.split_at(name_len);
+    if name.len() < 2 || !"put".starts_with(name) {
+        return None;
+    }
+    let (before, rest) = match rest.strip_prefix('!') {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    if let Some(expression) = rest.trim_start().strip_prefix('=') {
+        return Some(PutCommand {
+            range,
+            register: Some('='),
+            expression: Some(expression.trim().to_string()),
+            before,
+        });
+    }
+    if !rest.is_empty() && !rest.starts_with(' ') {
+        return None;
+    }
+    let mut chars = rest.trim().chars();
+    let register = chars.next();
+    if chars.next().is_some() {
+        return None;
+    }
+    Some(PutCommand {
+        range,
+        register,
+        expression: None,
+        before,
+    })
+}
+
+/// Parses `sor[t][!] [i][n][u] [/{pattern}/]`, where `!` reverses the order,
+/// `i` ignores case, `n` sorts by the first number on each line and `u` keeps
+/// only the first of each run of equal lines.
+fn parse_sort_command(query: &str, range: Option<CommandRange>) -> Option<SortCommand> {
+    let name_len = query
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let (name, rest) = query.split_at(name_len);
+    if name.len() < 3 || !"sort".starts_with(name) {
+        return None;
+    }
+
+    let mut sort = SortCommand {
+        range,
+        reverse: false,
+        numeric: false,
+        unique: false,
+        ignore_case: false,
+        pattern: None,
+    };
+    let mut chars = rest.chars().peekable();
+    if chars.next_if_eq(&'!').is_some() {
+        sort.reverse = true;
+    }
+    while let Some(c) = chars.next() {
+        match c {
+            ' ' | '\t' => {}
+            'i' => sort.ignore_case = true,
+            'n' => sort.numeric = true,
+            'u' => sort.unique = true,
+            c if sort.pattern.is_none()
+                && !c.is_alphanumeric()
+                && !matches!(c, '"' | '|' | '\\') =>
+            {
+                sort.pattern = Some(parse_delimited(&mut chars, c));
+            }
+            _ => return None,
+        }
+    }
+    Some(sort)
+}
+
+/// Parses `Trim[TrailingWhitespace][!]`, where `!` also trims whitespace
+/// inside multi-line strings.
+fn parse_trim_command(query: &str, range: Option<CommandRange>) -> Option<TrimWhitespaceCommand> {
+    let (name, in_strings) = match query.strip_suffix('!') {
+        Some(name) => (name, true),
+        None => (query, false),
+    };
+    if name.len() < 4 || !"TrimTrailingWhitespace".starts_with(name) {
+        return None;
+    }
+    Some(TrimWhitespaceCommand { range, in_strings })
+}
+
+/// Parses `m[ove] {address}`, `co[py] {address}` or `t {address}`.
+fn parse_move_command(query: &str, range: Option<CommandRange>) -> Option<MoveLinesCommand> {
+    let name_len = query
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .count();
+    let (name, rest) = query.split_at(name_len);
+    let copy = match name {
+        "t" | "co" | "cop" | "copy" => true,
+        "m" | "mo" | "mov" | "move" => false,
+        _ => return None,
+    };
+    let (address, rest) = VimCommand::parse_range(rest.trim_start());
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    Some(MoveLinesCommand {
+        range,
+        address: address?,
+        copy,
+    })
+}
+
+/// Whether `offset` is inside a string that continues onto the next line,
+/// where trailing whitespace is part of the string's contents.
+fn in_multiline_string(snapshot: &MultiBufferSnapshot, offset: usize) -> bool {
+    let Some(excerpt) = snapshot.excerpt_containing(offset..offset) else {
+        return false;
+    };
+    let buffer = excerpt.buffer();
+    let offset = excerpt.map_offset_to_buffer(offset);
+    let Some(layer) = buffer.syntax_layer_at(offset) else {
+        return false;
+    };
+
+    let row = buffer.offset_to_point(offset).row;
+    let mut cursor = layer.node().walk();
+    while cursor.goto_first_child_for_byte(offset).is_some() {}
+    let mut node = Some(cursor.node());
+    while let Some(current) = node {
+        // The cursor may have gone into a node that starts after `offset`.
+        let contains_offset = current.start_byte() <= offset;
+        if contains_offset
+            && current.kind().contains("string")
+            && current.end_position().row > row as usize
+        {
+            return true;
+        }
+        node = current.parent();
+    }
+    false
+}
+
+/// Compiles `pattern`, or the last search if it is empty (as in `:g//`).
+fn pattern_regex(vim: &Vim, pattern: &str) -> Result<Regex> {
+    let pattern = if pattern.is_empty() {
+        vim.workspace_state
+            .registers
+            .get(&'/')
+            .map(|register| register.text.to_string())
+            .ok_or_else(|| anyhow!("No previous regular expression"))?
+    } else {
+        pattern.to_string()
+    };
+    Ok(Regex::new(&vim_pattern_to_regex(&pattern))?)
+}
+
+/// Converts the common parts of a vim pattern to the regex syntax used by zed:
+/// `\(` and `\)` become groups (and bare parentheses are literals), and `\<`
+/// and `\>` become word boundaries.
+fn vim_pattern_to_regex(pattern: &str) -> String {
+    let mut regex = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('(' | ')')) => regex.push(c),
+                Some('<' | '>') => regex.push_str("\\b"),
+                Some(c) => {
+                    regex.push('\\');
+                    regex.push(c);
+                }
+                None => regex.push_str("\\\\"),
+            },
+            '(' | ')' => {
+                regex.push('\\');
+                regex.push(c);
+            }
+            c => regex.push(c),
+        }
+    }
+    regex
+}
+
+/// Parses `w[rite] !{cmd}`, returning `cmd`. Note that `:w!` (with no space)
+/// is a forced write, and `:w file` or `:w >>file` write to a file instead.
+fn parse_write_to_command(query: &str) -> Option<String> {
+    let (command, rest) = query.split_once(' ')?;
+    if command.is_empty() || !"write".starts_with(command) {
+        return None;
+    }
+    let shell_command = rest.trim_start().strip_prefix('!')?;
+    Some(shell_command.trim().to_string())
+}
+
+impl NormalCommand {
+    /// Returns the start of each line in the range, or `None` if the keys
+    /// should be run once from the cursor.
+    fn line_starts(&self, cx: &mut WindowContext) -> Result<Option<Vec<Anchor>>> {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            let Some(range) = self.range.as_ref() else {
+                return Ok(None);
+            };
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let range = range.buffer_range(vim, editor, cx)?;
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                anyhow::Ok(
+                    (range.start.0..=range.end.0)
+                        .map(|row| snapshot.anchor_before(Point::new(row, 0)))
+                        .collect::<Vec<_>>(),
+                )
+            })
+            .transpose()
+        })
+    }
+}
+
+impl GlobalCommand {
+    /// Returns the extent (including its newline) and text of each line in
+    /// the range that should be visited. These are collected up front so that
+    /// commands which delete lines don't change which lines are processed.
+    fn matching_lines(&self, cx: &mut WindowContext) -> Result<Vec<(Range<Anchor>, String)>> {
+        Vim::update(cx, |vim, cx| {
+            vim.switch_mode(Mode::Normal, false, cx);
+            let regex = pattern_regex(vim, &self.pattern)?;
+
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let range = match self.range.as_ref() {
+                    Some(range) => range.buffer_range(vim, editor, cx)?,
+                    None => MultiBufferRow(0)..snapshot.max_buffer_row(),
+                };
+                let mut lines = Vec::new();
+                for row in range.start.0..=range.end.0 {
+                    let start = Point::new(row, 0);
+                    let end = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+                    let line = snapshot.text_for_range(start..end).collect::<String>();
+                    if regex.is_match(&line) != self.invert {
+                        let next_line = if row < snapshot.max_point().row {
+                            snapshot.anchor_before(Point::new(row + 1, 0))
+                        } else {
+                            snapshot.anchor_after(snapshot.len())
+                        };
+                        lines.push((snapshot.anchor_before(start)..next_line, line));
+                    }
+                }
+                anyhow::Ok(lines)
+            })
+            .unwrap_or_else(|| Ok(Vec::new()))
+        })
+    }
+}
+
+impl SortCommand {
+    /// Sorts `lines`, keeping lines that compare equal in their original
+    /// order, even when reversed. Lines that don't match `regex` are sorted as
+    /// if they were empty, and with `n`, lines without a number come first.
+    fn sort(&self, mut lines: Vec<&str>, regex: Option<&Regex>) -> Vec<String> {
+        fn key<'a>(line: &'a str, regex: Option<&Regex>) -> &'a str {
+            match regex {
+                Some(regex) => regex.find(line).map_or("", |m| &line[m.end()..]),
+                None => line,
+            }
+        }
+        let compare = |a: &str, b: &str| -> Ordering {
+            let (a, b) = (key(a, regex), key(b, regex));
+            if self.numeric {
+                first_number(a).cmp(&first_number(b))
+            } else if self.ignore_case {
+                a.to_lowercase().cmp(&b.to_lowercase())
+            } else {
+                a.cmp(b)
+            }
+        };
+
+        if self.reverse {
+            lines.sort_by(|a, b| compare(b, a));
+        } else {
+            lines.sort_by(|a, b| compare(a, b));
+        }
+        if self.unique {
+            lines.dedup_by(|a, b| compare(a, b) == Ordering::Equal);
+        }
+        lines.into_iter().map(ToString::to_string).collect()
+    }
+}
+
+/// Returns the first (optionally negative) decimal number in `text`, if
+/// there is one.
+fn first_number(text: &str) -> Option<i64> {
+    let start = text.find(|c: char| c.is_ascii_digit())?;
+    let len = text[start..]
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or(text.len() - start);
+    let number = text[start..start + len].parse::<i64>().unwrap_or(i64::MAX);
+    if text[..start].ends_with('-') {
+        Some(-number)
+    } else {
+        Some(number)
+    }
+}
+
+/// Whether `line`, from the start of a line to the start of the next (or the
+/// end of the buffer), hasn't been deleted. The empty line after a trailing
+/// newline, which vim doesn't count as a line, never exists.
+fn line_exists(line: &Range<Anchor>, cx: &mut WindowContext) -> bool {
+    Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            line.start.to_offset(&snapshot) < line.end.to_offset(&snapshot)
+        })
+    })
+    .unwrap_or(false)
+}
+
+fn move_to_line_start(line_start: Anchor, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.clear_operator(cx);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.change_selections(None, cx, |s| {
+                s.select_anchor_ranges([line_start..line_start])
+            })
+        });
+    })
+}
+
+/// Dispatches `keystrokes` as though they were typed, once from the start of
+/// each line in `line_starts`, or once from the cursor if there are none.
+/// With `noremap`, mappings aren't applied to them.
+async fn run_keystrokes(
+    line_starts: Option<Vec<Anchor>>,
+    keystrokes: Vec<Keystroke>,
+    noremap: bool,
+    cx: &mut AsyncWindowContext,
+) -> Result<()> {
+    let line_starts = match line_starts {
+        Some(line_starts) => line_starts.into_iter().map(Some).collect(),
+        None => vec![None],
+    };
+    for line_start in line_starts {
+        cx.update(|cx| match line_start {
+            Some(line_start) => move_to_line_start(line_start, cx),
+            None => Vim::update(cx, |vim, cx| vim.clear_operator(cx)),
+        })?;
+        // Each keystroke is dispatched in its own update so that any
+        // effects it schedules are flushed before the next one.
+        for keystroke in keystrokes.iter().cloned() {
+            cx.update(|cx| {
+                Vim::update(cx, |vim, _| vim.workspace_state.noremap_typeahead = noremap);
+                cx.dispatch_keystroke(keystroke);
+                Vim::update(cx, |vim, _| vim.workspace_state.noremap_typeahead = false);
+            })?;
+        }
+        // An incomplete command at the end of the keys is abandoned,
+        // and insert mode is left as if by <Esc>.
+        cx.update(|cx| cx.dispatch_keystroke(Keystroke::parse("escape").unwrap()))?;
+    }
+    Ok(())
+}
+
+/// Converts the literal text given to `:normal` into the keystrokes that would
+/// type it.
+pub(crate) fn keystrokes_for_text(text: &str) -> Vec<Keystroke> {
+    text.chars()
+        .map(|c| {
+            let key = match c {
+                ' ' => "space".to_string(),
+                '\t' => "tab".to_string(),
+                '\n' => "enter".to_string(),
+                c => c.to_lowercase().to_string(),
+            };
+            Keystroke {
+                modifiers: Modifiers {
+                    shift: c.is_uppercase(),
+                    ..Default::default()
+                },
+                key,
+                ime_key: None,
+            }
+        })
+        .collect()
+}
+
+/// The commands that add and remove mappings, by the modes they apply in,
+/// with the shortest abbreviation vim accepts for each.
+const MAP_COMMANDS: [(&[MapMode], [(&str, usize); 3]); 6] = [
+    (
+        &[MapMode::Normal, MapMode::Visual, MapMode::OperatorPending],
+        [("map", 3), ("noremap", 2), ("unmap", 3)],
+    ),
+    (
+        &[MapMode::Normal],
+        [("nmap", 2), ("nnoremap", 2), ("nunmap", 3)],
+    ),
+    (
+        &[MapMode::Visual],
+        [("vmap", 2), ("vnoremap", 2), ("vunmap", 2)],
+    ),
+    (
+        &[MapMode::Visual],
+        [("xmap", 2), ("xnoremap", 2), ("xunmap", 2)],
+    ),
+    (
+        &[MapMode::OperatorPending],
+        [("omap", 2), ("onoremap", 3), ("ounmap", 2)],
+    ),
+    (
+        &[MapMode::Insert],
+        [("imap", 2), ("inoremap", 3), ("iunmap", 2)],
+    ),
+];
+
+/// Parses `[nvxoi]map {lhs} {rhs}`, `[nvxoi]noremap {lhs} {rhs}` and
+/// `[nvxoi]unmap {lhs}`.
+fn parse_map_command(query: &str) -> Option<Box<dyn Action>> {
+    let (name, args) = query.split_once(' ')?;
+    let args = args.trim_start();
+    for (modes, [map, noremap, unmap]) in MAP_COMMANDS {
+        let is = |(command, len): (&str, usize)| name.len() >= len && command.starts_with(name);
+        if is(unmap) {
+            let lhs = args.trim_end();
+            return (!lhs.is_empty()).then(|| Unmap::new(modes, lhs).boxed_clone());
+        }
+        let recursive = is(map);
+        if recursive || is(noremap) {
+            let (lhs, rhs) = args.split_once(char::is_whitespace)?;
+            return Some(Map::new(modes, lhs, rhs.trim_start(), recursive).boxed_clone());
+        }
+    }
+    None
+}
+
+/// Parses `ab[breviate] {lhs} {rhs}` and `iab[brev] {lhs} {rhs}`. Zed has no
+/// abbreviations on the command line, so the two are the same.
+fn parse_abbreviate_command(query: &str) -> Option<Abbreviate> {
+    let (name, rest) = query.split_once(' ')?;
+    if !(name.len() >= 2 && "abbreviate".starts_with(name)
+        || name.len() >= 3 && "iabbrev".starts_with(name))
+    {
+        return None;
+    }
+    let (trigger, expansion) = rest.trim_start().split_once(char::is_whitespace)?;
+    Abbreviate::new(trigger, expansion.trim_start())
+}
+
+/// Parses the `normal[!] {keys}` command, returning `keys` and whether the
+/// `!` was given, which stops mappings from applying to them.
+fn parse_normal_command(query: &str) -> Option<(String, bool)> {
+    let (command, keys) = query.split_once(' ')?;
+    let (command, noremap) = match command.strip_suffix('!') {
+        Some(command) => (command, true),
+        None => (command, false),
+    };
+    if command.len() < 4 || !"normal".starts_with(command) {
+        return None;
+    }
+    Some((keys.trim_start().to_string(), noremap))
 }
 
 #[derive(Debug, Default)]
@@ -209,7 +1417,6 @@ impl VimCommand {
         }
     }
 
-    // TODO: ranges with search queries
     fn parse_range(query: &str) -> (Option<CommandRange>, String) {
         let mut chars = query.chars().peekable();
 
@@ -295,6 +1502,31 @@ impl VimCommand {
                     offset: Self::parse_offset(chars),
                 })
             }
+            '/' | '?' => {
+                let delimiter = chars.next()?;
+                let mut pattern = String::new();
+                let mut escaped = false;
+                for c in chars.by_ref() {
+                    if escaped {
+                        escaped = false;
+                        if c != delimiter {
+                            pattern.push('\\');
+                        }
+                        pattern.push(c);
+                    } else if c == '\\' {
+                        escaped = true;
+                    } else if c == delimiter {
+                        break;
+                    } else {
+                        pattern.push(c);
+                    }
+                }
+                Some(Position::Search {
+                    pattern,
+                    backwards: delimiter == '?',
+                    offset: Self::parse_offset(chars),
+                })
+            }
             _ => None,
         }
     }
@@ -326,10 +1558,25 @@ impl VimCommand {
 
 #[derive(Debug, Clone, PartialEq, Deserialize)]
 enum Position {
-    Line { row: u32, offset: i32 },
-    Mark { name: char, offset: i32 },
-    LastLine { offset: i32 },
-    CurrentLine { offset: i32 },
+    Line {
+        row: u32,
+        offset: i32,
+    },
+    Mark {
+        name: char,
+        offset: i32,
+    },
+    LastLine {
+        offset: i32,
+    },
+    CurrentLine {
+        offset: i32,
+    },
+    Search {
+        pattern: String,
+        backwards: bool,
+        offset: i32,
+    },
 }
 
 impl Position {
@@ -365,6 +1612,50 @@ impl Position {
                 .to_point(&snapshot.buffer_snapshot)
                 .row
                 .saturating_add_signed(*offset),
+            Position::Search {
+                pattern,
+                backwards,
+                offset,
+            } => {
+                let pattern = if pattern.is_empty() {
+                    vim.workspace_state
+                        .registers
+                        .get(&'/')
+                        .map(|register| register.text.to_string())
+                        .ok_or_else(|| anyhow!("No previous regular expression"))?
+                } else {
+                    pattern.clone()
+                };
+                let regex = Regex::new(&vim_pattern_to_regex(&pattern))?;
+                let buffer = &snapshot.buffer_snapshot;
+                let current_row = editor
+                    .selections
+                    .newest_anchor()
+                    .head()
+                    .to_point(buffer)
+                    .row;
+                let row_count = buffer.max_buffer_row().0 + 1;
+
+                // Like vim, the search starts on the line after (or before) the
+                // cursor and wraps around the end of the buffer.
+                let row = (1..=row_count)
+                    .map(|distance| {
+                        if *backwards {
+                            (current_row + row_count - distance) % row_count
+                        } else {
+                            (current_row + distance) % row_count
+                        }
+                    })
+                    .find(|row| {
+                        let line_len = buffer.line_len(MultiBufferRow(*row));
+                        let line = buffer
+                            .text_for_range(Point::new(*row, 0)..Point::new(*row, line_len))
+                            .collect::<String>();
+                        regex.is_match(&line)
+                    })
+                    .ok_or_else(|| anyhow!("Pattern not found: {}", pattern))?;
+                row.saturating_add_signed(*offset)
+            }
         };
 
         Ok(MultiBufferRow(target).min(snapshot.max_buffer_row()))
@@ -378,6 +1669,20 @@ pub(crate) struct CommandRange {
 }
 
 impl CommandRange {
+    pub(crate) fn current_line() -> Self {
+        CommandRange {
+            start: Position::CurrentLine { offset: 0 },
+            end: None,
+        }
+    }
+
+    pub(crate) fn whole_file() -> Self {
+        CommandRange {
+            start: Position::Line { row: 1, offset: 0 },
+            end: Some(Position::LastLine { offset: 0 }),
+        }
+    }
+
     fn head(&self) -> &Position {
         self.end.as_ref().unwrap_or(&self.start)
     }
@@ -585,10 +1890,13 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
         VimCommand::new(("cN", "ext"), editor::actions::GoToPrevDiagnostic).count(),
         VimCommand::new(("lp", "revious"), editor::actions::GoToPrevDiagnostic).count(),
         VimCommand::new(("lN", "ext"), editor::actions::GoToPrevDiagnostic).count(),
-        VimCommand::new(("j", "oin"), JoinLines).range(),
+        VimCommand::new(("j", "oin"), JoinLines)
+            .bang(JoinLinesNoWhitespace)
+            .range(),
         VimCommand::new(("d", "elete"), VisualDeleteLine).range(),
-        VimCommand::new(("sor", "t"), SortLinesCaseSensitive).range(),
-        VimCommand::new(("sort i", ""), SortLinesCaseInsensitive).range(),
+        VimCommand::new(("y", "ank"), VisualYankLine).range(),
+        VimCommand::new((">", ""), Indent).range(),
+        VimCommand::new(("<", ""), Outdent).range(),
         VimCommand::str(("E", "xplore"), "project_panel::ToggleFocus"),
         VimCommand::str(("H", "explore"), "project_panel::ToggleFocus"),
         VimCommand::str(("L", "explore"), "project_panel::ToggleFocus"),
@@ -600,6 +1908,11 @@ fn generate_commands(_: &AppContext) -> Vec<VimCommand> {
         VimCommand::str(("Ch", "at"), "chat_panel::ToggleFocus"),
         VimCommand::str(("No", "tifications"), "notification_panel::ToggleFocus"),
         VimCommand::str(("A", "I"), "assistant::ToggleFocus"),
+        VimCommand::new(("vi", "sual"), ExitExMode),
+        VimCommand::new(("dig", "raphs"), ShowDigraphs),
+        VimCommand::new(("abc", "lear"), ClearAbbreviations),
+        VimCommand::new(("iabc", "lear"), ClearAbbreviations),
+        VimCommand::new(("as", "cii"), ShowCharacterInfo),
         VimCommand::new(("$", ""), EndOfDocument),
         VimCommand::new(("%", ""), EndOfDocument),
         VimCommand::new(("0", ""), StartOfDocument),
@@ -619,6 +1932,62 @@ fn commands(cx: &AppContext) -> &Vec<VimCommand> {
         .0
 }
 
+/// Intercepts commands typed on the command line, remembering them for `q:`
+/// once they run.
+pub fn history_command_interceptor(input: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
+    let mut result = command_interceptor(input, cx)?;
+    result.action = Box::new(HistoryCommand::new(input, result.action));
+    Some(result)
+}
+
+/// How many commands and searches are remembered, as with vim's default
+/// `'history'`.
+const HISTORY_SIZE: usize = 50;
+
+/// Adds `entry` to the end of `history`, dropping any earlier copy of it, as
+/// vim's command line and search histories do.
+pub(crate) fn push_history(history: &mut Vec<String>, entry: &str) {
+    if entry.is_empty() {
+        return;
+    }
+    history.retain(|existing| existing != entry);
+    history.push(entry.to_string());
+    if history.len() > HISTORY_SIZE {
+        history.drain(..history.len() - HISTORY_SIZE);
+    }
+}
+
+/// Enters vim's Ex mode, where the command line is shown again after each
+/// command, until `:visual` (or closing it without a command) leaves.
+pub(crate) fn enter_ex_mode(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| {
+        vim.take_count(cx);
+        vim.workspace_state.ex_mode = true;
+    });
+    prompt_ex_command(workspace, cx);
+}
+
+fn prompt_ex_command(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
+    command_palette::CommandPalette::toggle(workspace, "", cx);
+    let Some(palette) = workspace.active_modal::<command_palette::CommandPalette>(cx) else {
+        Vim::update(cx, |vim, _| vim.workspace_state.ex_mode = false);
+        return;
+    };
+    cx.subscribe(&palette, |_, _, _: &DismissEvent, cx| {
+        // The palette closes before the command picked from it is
+        // dispatched, so wait for that to tell whether one was picked.
+        cx.defer(|_, cx| {
+            Vim::update(cx, |vim, _| {
+                let state = &mut vim.workspace_state;
+                if !std::mem::take(&mut state.ex_command_ran) {
+                    state.ex_mode = false;
+                }
+            })
+        });
+    })
+    .detach();
+}
+
 pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandInterceptResult> {
     // NOTE: We also need to support passing arguments to commands like :w
     // (ideally with filename autocompletion).
@@ -630,18 +1999,75 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
     let range_prefix = input[0..(input.len() - query.len())].to_string();
     let query = query.as_str();
 
-    let action = if range.is_some() && query == "" {
+    let action = if let Some(CommandRange {
+        start:
+            Position::Search {
+                pattern,
+                backwards,
+                offset: 0,
+            },
+        end: None,
+    }) = range.as_ref().filter(|_| query == "")
+    {
+        Some(
+            FindCommand {
+                query: pattern.clone(),
+                backwards: *backwards,
+            }
+            .boxed_clone(),
+        )
+    } else if range.is_some() && query == "" {
         Some(
             GoToLine {
                 range: range.clone().unwrap(),
             }
             .boxed_clone(),
         )
-    } else if query.starts_with('/') || query.starts_with('?') {
+    } else if let Some((action, register)) = parse_register_command(query) {
         Some(
-            FindCommand {
-                query: query[1..].to_string(),
-                backwards: query.starts_with('?'),
+            WithRange {
+                is_count: false,
+                range: range.clone().unwrap_or(CommandRange {
+                    start: Position::CurrentLine { offset: 0 },
+                    end: None,
+                }),
+                action,
+                register: Some(register),
+                count: None,
+            }
+            .boxed_clone(),
+        )
+    } else if let Some((action, count)) = parse_shift_command(query) {
+        Some(
+            WithRange {
+                is_count: false,
+                range: range.clone().unwrap_or(CommandRange {
+                    start: Position::CurrentLine { offset: 0 },
+                    end: None,
+                }),
+                action,
+                register: None,
+                count: Some(count),
+            }
+            .boxed_clone(),
+        )
+    } else if let Some(clear) = parse_registers_command(query) {
+        Some(clear.boxed_clone())
+    } else if let Some(put) = parse_put_command(query, range.clone()) {
+        Some(put.boxed_clone())
+    } else if let Some(sort) = parse_sort_command(query, range.clone()) {
+        Some(sort.boxed_clone())
+    } else if let Some(trim) = parse_trim_command(query, range.clone()) {
+        Some(trim.boxed_clone())
+    } else if let Some(move_lines) = parse_move_command(query, range.clone()) {
+        Some(move_lines.boxed_clone())
+    } else if query == "&" || query == "&&" {
+        Some(
+            RepeatSubstitute {
+                range: range.clone(),
+                whole_file: false,
+                keep_flags: query == "&&",
+                last_search: false,
             }
             .boxed_clone(),
         )
@@ -666,6 +2092,41 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
         } else {
             None
         }
+    } else if let Some(global) = parse_global_command(query, range.clone()) {
+        Some(global.boxed_clone())
+    } else if let Some((range, command)) = range.as_ref().zip(
+        query
+            .strip_prefix('!')
+            .filter(|command| !command.trim().is_empty()),
+    ) {
+        Some(
+            FilterCommand {
+                range: range.clone(),
+                command: command.trim().to_string(),
+            }
+            .boxed_clone(),
+        )
+    } else if let Some(command) = parse_write_to_command(query) {
+        Some(
+            WriteToCommand {
+                range: range.clone(),
+                command,
+            }
+            .boxed_clone(),
+        )
+    } else if let Some(abbreviate) = parse_abbreviate_command(query) {
+        Some(abbreviate.boxed_clone())
+    } else if let Some((keys, noremap)) = parse_normal_command(query) {
+        Some(
+            NormalCommand {
+                range: range.clone(),
+                keys,
+                noremap,
+            }
+            .boxed_clone(),
+        )
+    } else if let Some(map) = parse_map_command(query) {
+        Some(map)
     } else {
         None
     };
@@ -691,6 +2152,8 @@ pub fn command_interceptor(mut input: &str, cx: &AppContext) -> Option<CommandIn
                             is_count: command.has_count,
                             range: range.clone(),
                             action,
+                            register: None,
+                            count: None,
                         }),
                         string,
                         positions,
@@ -736,9 +2199,17 @@ fn generate_positions(string: &str, query: &str) -> Vec<usize> {
 mod test {
     use std::path::Path;
 
-    use crate::test::{NeovimBackedTestContext, VimTestContext};
+    use crate::{
+        command::GlobalCommand,
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+        Vim,
+    };
+    use command_palette::CommandPalette;
+    use editor::Editor;
     use gpui::TestAppContext;
     use indoc::indoc;
+    use workspace::notifications::NotificationId;
 
     #[gpui::test]
     async fn test_command_basics(cx: &mut TestAppContext) {
@@ -760,6 +2231,33 @@ mod test {
         });
     }
 
+    #[gpui::test]
+    async fn test_command_delete(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        let text = indoc! {"
+            oˇne
+            two
+            three
+            four
+            five"};
+        cx.simulate(": 3 d enter", text).await.assert_matches();
+        cx.simulate(": 2 , 5 d enter", text).await.assert_matches();
+        cx.simulate(": $ d enter", text).await.assert_matches();
+        cx.simulate(": % d enter", text).await.assert_matches();
+        cx.simulate(
+            ": 1 d enter",
+            indoc! {"
+            one
+            two
+            threˇe
+            four
+            five"},
+        )
+        .await
+        .assert_matches();
+    }
+
     #[gpui::test]
     async fn test_command_goto(cx: &mut TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -798,6 +2296,48 @@ mod test {
             ˇcc"});
     }
 
+    #[gpui::test]
+    async fn test_command_replace_delimiters(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            xx x
+            /usr/bin/zed
+            a#b"})
+            .await;
+        cx.simulate_shared_keystrokes(": 1 s # a # b # enter").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇb
+            xx x
+            /usr/bin/zed
+            a#b"});
+        cx.simulate_shared_keystrokes("j : s , x , y , g enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            ˇyy y
+            /usr/bin/zed
+            a#b"});
+        cx.simulate_shared_keystrokes(
+            "j : s # / u s r / b i n # / u s r / l o c a l / b i n # enter",
+        )
+        .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            yy y
+            ˇ/usr/local/bin/zed
+            a#b"});
+        // the delimiter can still be matched by escaping it
+        cx.simulate_shared_keystrokes("j : s # a \\ # # c enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            b
+            yy y
+            /usr/local/bin/zed
+            ˇcb"});
+    }
+
     #[gpui::test]
     async fn test_command_search(cx: &mut TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -849,6 +2389,52 @@ mod test {
         assert_eq!(fs.load(&path).await.unwrap(), "@@\n");
     }
 
+    // the commands run are unix ones
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_command_write_to_command(cx: &mut TestAppContext) {
+        use crate::command::WriteToCommand;
+        use workspace::notifications::simple_message_notification::MessageNotification;
+
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": w space ! w c space - l enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇa\nb\nc", Mode::Normal);
+        let output = cx.workspace(|workspace, cx| {
+            workspace
+                .notification::<MessageNotification>(&NotificationId::unique::<WriteToCommand>())
+                .unwrap()
+                .read(cx)
+                .message()
+                .to_string()
+        });
+        // macOS pads the count
+        assert_eq!(output.trim(), "3");
+    }
+
+    // the commands run are unix ones
+    #[cfg(unix)]
+    #[gpui::test]
+    async fn test_command_filter(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 1 , 3 ! s o r t space - r enter");
+        cx.assert_state("ˇc\nb\na\nd", Mode::Normal);
+
+        // a failing command leaves the lines as they were
+        cx.simulate_keystrokes(": 1 , 3 ! e x i t space 1 enter");
+        cx.assert_state("ˇc\nb\na\nd", Mode::Normal);
+
+        // `!` fills in the range of a motion or of the visual selection
+        cx.simulate_keystrokes("! j s o r t enter");
+        cx.assert_state("ˇb\nc\na\nd", Mode::Normal);
+        cx.simulate_keystrokes("shift-v j j ! s o r t enter");
+        cx.assert_state("ˇa\nb\nc\nd", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_command_quit(cx: &mut TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -907,6 +2493,364 @@ mod test {
         cx.shared_state().await.assert_eq("1\nˇ2 3 4\n1");
     }
 
+    #[gpui::test]
+    async fn test_normal_command(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇa
+            b
+            c"})
+            .await;
+        cx.simulate_shared_keystrokes(": n o r m a l space shift-a ; enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            aˇ;
+            b
+            c"});
+
+        cx.simulate_shared_keystrokes(": % n o r m a l space shift-i h i enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            hia;
+            hib
+            hˇic"});
+
+        // an incomplete command is discarded
+        cx.simulate_shared_keystrokes(": n o r m space d enter")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            hia;
+            hib
+            hˇic"});
+    }
+
+    #[gpui::test]
+    async fn test_normal_command_mappings(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("nnoremap x dd");
+        cx.simulate_keystrokes("enter");
+
+        cx.set_state("ˇab\ncd", Mode::Normal);
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("normal x");
+        cx.simulate_keystrokes("enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇcd", Mode::Normal);
+
+        // with ! the built-in `x` runs instead
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("normal! x");
+        cx.simulate_keystrokes("enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_global(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇax\nb\ncx\nd").await;
+        cx.simulate_shared_keystrokes(": g / x / d enter").await;
+        cx.shared_state().await.assert_eq("b\nˇd");
+
+        cx.set_shared_state("ˇkeep 1\nx\nkeep 2\ny\nkeep 3").await;
+        cx.simulate_shared_keystrokes(": v / k e e p / d enter")
+            .await;
+        cx.shared_state().await.assert_eq("keep 1\nkeep 2\nˇkeep 3");
+
+        cx.set_shared_state("ˇa\nb\na").await;
+        cx.simulate_shared_keystrokes(": g ! / b / n o r m a l space shift-a x enter")
+            .await;
+        cx.shared_state().await.assert_eq("ax\nb\naˇx");
+    }
+
+    #[gpui::test]
+    async fn test_command_global_report(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let status_message = |cx: &mut VimTestContext| {
+            cx.update(|cx| Vim::read(cx).workspace_state.status_message.clone())
+        };
+
+        cx.set_state("ˇax\nb\ncx\nd\nex", Mode::Normal);
+        cx.simulate_keystrokes(": g / x / enter");
+        cx.assert_state("ax\nb\ncx\nd\nˇex", Mode::Normal);
+        assert_eq!(status_message(&mut cx), Some("3 matches".into()));
+
+        cx.simulate_keystrokes(": g / x / p enter");
+        assert_eq!(status_message(&mut cx), Some("3 matches".into()));
+        cx.workspace(|workspace, _| {
+            assert!(workspace
+                .notification_ids()
+                .contains(&NotificationId::unique::<GlobalCommand>()))
+        });
+
+        cx.set_state("ˇax\nb\ncx", Mode::Normal);
+        cx.simulate_keystrokes(": v / x / d enter");
+        cx.assert_state("ax\nˇcx", Mode::Normal);
+        assert_eq!(status_message(&mut cx), Some("1 match".into()));
+    }
+
+    #[gpui::test]
+    async fn test_command_global_deleted_lines(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // lines deleted along with an earlier one are skipped, rather than
+        // running the command on whatever line takes their place
+        cx.set_state("ˇx 1\nx 2\ny\nx 3\nz", Mode::Normal);
+        cx.simulate_keystrokes(": g / x / . , + 1 d enter");
+        cx.assert_state("ˇy", Mode::Normal);
+
+        cx.set_state("ˇx 1\nx 2\nkeep\nx 3\nz", Mode::Normal);
+        cx.simulate_keystrokes(": v / k e e p / . , + 1 d enter");
+        cx.assert_state("ˇkeep", Mode::Normal);
+
+        // including the last line
+        cx.set_state("ˇx 1\nx 2", Mode::Normal);
+        cx.simulate_keystrokes(": g / x / . , + 1 d enter");
+        cx.assert_state("ˇ", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_sort(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇc\na\nb").await;
+        cx.simulate_shared_keystrokes(": s o r t enter").await;
+        cx.shared_state().await.assert_eq("ˇa\nb\nc");
+
+        cx.set_shared_state("ˇ9\n10").await;
+        cx.simulate_shared_keystrokes(": s o r t enter").await;
+        cx.shared_state().await.assert_eq("ˇ10\n9");
+
+        cx.set_shared_state("ˇ9\n10\nx\n2").await;
+        cx.simulate_shared_keystrokes(": s o r t space n enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇx\n2\n9\n10");
+
+        cx.set_shared_state("ˇb\na\nb\na").await;
+        cx.simulate_shared_keystrokes(": s o r t space u enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇa\nb");
+
+        cx.set_shared_state("ˇx\na\nc\nb").await;
+        cx.simulate_shared_keystrokes(": 2 , 4 s o r t ! enter")
+            .await;
+        cx.shared_state().await.assert_eq("x\nˇc\nb\na");
+
+        cx.set_shared_state("ˇa3\nb1\nc2").await;
+        cx.simulate_shared_keystrokes(": s o r t space / . / enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇb1\nc2\na3");
+
+        // lines without a number come before negative numbers
+        cx.set_shared_state("ˇ3\nx\n-1").await;
+        cx.simulate_shared_keystrokes(": s o r t space n enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇx\n-1\n3");
+
+        // and the first of the equal lines is kept, even when reversed
+        cx.set_shared_state("ˇb\nB\na").await;
+        cx.simulate_shared_keystrokes(": s o r t space i u enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇa\nb");
+        cx.set_shared_state("ˇb\nB\na").await;
+        cx.simulate_shared_keystrokes(": s o r t ! space i u enter")
+            .await;
+        cx.shared_state().await.assert_eq("ˇb\na");
+    }
+
+    #[gpui::test]
+    async fn test_command_trim_trailing_whitespace(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa  \n  \nb\t \nc", Mode::Normal);
+        cx.simulate_keystrokes(
+            ": % shift-t r i m shift-t r a i l i n g shift-w h i t e s p a c e enter",
+        );
+        cx.assert_state("ˇa\n\nb\nc", Mode::Normal);
+
+        cx.set_state("ˇa  \nb  \nc\t\nd  ", Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 3 shift-t r i m enter");
+        cx.assert_state("ˇa  \nb\nc\nd  ", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_trim_trailing_whitespace_in_strings(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new_typescript(cx).await;
+
+        cx.set_state("ˇconst a = `one  \ntwo  `;  \nconst b = 1;\t", Mode::Normal);
+        cx.run_until_parked();
+        cx.simulate_keystrokes(": shift-t r i m enter");
+        cx.assert_state("ˇconst a = `one  \ntwo  `;\nconst b = 1;", Mode::Normal);
+
+        // with ! the string's whitespace goes too
+        cx.simulate_keystrokes(": shift-t r i m ! enter");
+        cx.assert_state("ˇconst a = `one\ntwo  `;\nconst b = 1;", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_move_and_copy(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 1 , 2 t $ enter");
+        cx.assert_state("a\nb\nc\nd\na\nˇb", Mode::Normal);
+        cx.simulate_keystrokes("u");
+        cx.assert_state("ˇa\nb\nc\nd", Mode::Normal);
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 3 m 0 enter");
+        cx.assert_state("ˇc\na\nb\nd", Mode::Normal);
+        cx.simulate_keystrokes("u");
+        cx.assert_state("ˇa\nb\nc\nd", Mode::Normal);
+
+        cx.set_state("a\n  ˇb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": t . enter");
+        cx.assert_state("a\n  b\n  ˇb\nc", Mode::Normal);
+
+        // moving down, to the end, and to a mark
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 1 , 2 m 3 enter");
+        cx.assert_state("c\na\nˇb\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 3 , 4 m o v e space 0 enter");
+        cx.assert_state("b\nˇd\nc\na", Mode::Normal);
+        cx.simulate_keystrokes("g g m x G : 2 c o p y space ' x enter");
+        cx.assert_state("b\nˇd\nd\nc\na", Mode::Normal);
+        cx.simulate_keystrokes(": 2 m $ enter");
+        cx.assert_state("b\nd\nc\na\nˇd", Mode::Normal);
+
+        // a range can't be moved into itself
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 1 , 3 m 2 enter");
+        cx.assert_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 3 m 1 enter");
+        cx.assert_state("a\nb\nˇc\nd", Mode::Normal);
+
+        cx.simulate_keystrokes(": 2 , 3 d enter");
+        cx.assert_state("a\nˇd", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_registers(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇa\nb\nc\nd").await;
+        cx.simulate_shared_keystrokes(": 1 , 3 y a n k space a enter")
+            .await;
+        cx.simulate_shared_keystrokes(": $ p u t space a enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\nb\nc\nd\na\nb\nˇc");
+        cx.simulate_shared_keystrokes(": 0 p u t enter").await;
+        cx.shared_state()
+            .await
+            .assert_eq("a\nb\nˇc\na\nb\nc\nd\na\nb\nc");
+
+        cx.set_shared_state("ˇa\nb\nc").await;
+        cx.simulate_shared_keystrokes(": 2 d e l e t e space x enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\nˇc");
+        cx.simulate_shared_keystrokes(": p u t space x enter").await;
+        cx.shared_state().await.assert_eq("a\nc\nˇb");
+    }
+
+    #[gpui::test]
+    async fn test_command_registers_clear(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let register = |cx: &mut VimTestContext, name: char| {
+            cx.update(|cx| {
+                Vim::read(cx)
+                    .workspace_state
+                    .registers
+                    .get(&name)
+                    .map(|register| register.text.to_string())
+            })
+        };
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("\" a y y j d d");
+        assert_eq!(register(&mut cx, 'a'), Some("one\n".into()));
+        assert_eq!(register(&mut cx, '1'), Some("two\n".into()));
+
+        cx.simulate_keystrokes(": r e g i s t e r s space c l e a r enter");
+        assert_eq!(register(&mut cx, 'a'), None);
+        assert_eq!(register(&mut cx, '1'), None);
+        assert_eq!(register(&mut cx, '"'), None);
+        cx.assert_state("one\nˇthree", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_put_expression(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇa\nb").await;
+        cx.simulate_shared_keystrokes(": p u t space = 1 + 1 enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\nˇ2\nb");
+        cx.simulate_shared_keystrokes(": $ p u t space = [ 1 , 2 , 3 ] enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\n2\nb\n1\n2\nˇ3");
+        cx.simulate_shared_keystrokes(": p u t space = [ ] enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\n2\nb\n1\n2\nˇ3");
+    }
+
+    #[gpui::test]
+    async fn test_command_ranges_with_marks_and_searches(cx: &mut TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇa\nb\nc\nd\ne").await;
+        cx.simulate_shared_keystrokes("j m a j j m b g g").await;
+        cx.simulate_shared_keystrokes(": ' a , ' b y enter").await;
+        cx.shared_register('"').await.assert_eq("b\nc\nd\n");
+
+        cx.set_shared_state("a\nˇb\nc\nend\nd").await;
+        cx.simulate_shared_keystrokes(": . , / e n d / d enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\nˇd");
+
+        cx.set_shared_state("a\nb\nc\nd\nˇe").await;
+        cx.simulate_shared_keystrokes(": ? b ? + 1 , $ - 1 d enter")
+            .await;
+        cx.shared_state().await.assert_eq("a\nb\nˇe");
+    }
+
+    #[gpui::test]
+    async fn test_command_shift_range(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": % > enter");
+        cx.assert_state("ˇ    a\n    b\n    c", Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 3 < enter");
+        cx.assert_state("    a\nˇb\nc", Mode::Normal);
+
+        cx.set_state("ˇa\nb\nc\nd\ne", Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 4 > > enter");
+        cx.assert_state("a\nˇ        b\n        c\n        d\ne", Mode::Normal);
+        cx.simulate_keystrokes(": 3 < < < enter");
+        cx.assert_state("a\n        b\nˇc\n        d\ne", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_command_join_range(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\n  b\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 1 , 3 j o i n enter");
+        cx.assert_state("ˇa b c\nd", Mode::Normal);
+
+        cx.set_state("a\nˇb\n  c\nd", Mode::Normal);
+        cx.simulate_keystrokes(": . , + 1 j ! enter");
+        cx.assert_state("a\nˇb  c\nd", Mode::Normal);
+
+        // a single line is joined with the next
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": 2 j enter");
+        cx.assert_state("a\nˇb c", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_command_visual_replace(cx: &mut TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -917,4 +2861,70 @@ mod test {
             .await;
         cx.shared_state().await.assert_eq("k\nk\nˇk\n4\n4\n3\n2\n1");
     }
+
+    #[gpui::test]
+    async fn test_command_window(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc\nd", Mode::Normal);
+        cx.simulate_keystrokes(": 2 enter");
+        cx.simulate_keystrokes(": 4 enter");
+        cx.assert_state("a\nb\nc\nˇd", Mode::Normal);
+
+        cx.simulate_keystrokes("q :");
+        cx.run_until_parked();
+        let history = cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            editor.read(cx).text(cx)
+        });
+        assert_eq!(history, "2\n4\n");
+        // `q` isn't left waiting for a register, or recording one
+        assert_eq!(cx.active_operator(), None);
+        let recording = cx.update(|cx| Vim::read(cx).workspace_state.recording_register);
+        assert_eq!(recording, None);
+
+        cx.simulate_keystrokes("k k enter");
+        cx.run_until_parked();
+        cx.assert_state("a\nˇb\nc\nd", Mode::Normal);
+        let active = cx.workspace(|workspace, cx| {
+            let editor = workspace.active_item_as::<Editor>(cx).unwrap();
+            editor.read(cx).text(cx)
+        });
+        assert_eq!(active, "a\nb\nc\nd");
+        let history = cx.update(|cx| Vim::read(cx).workspace_state.command_history.clone());
+        assert_eq!(history, vec!["4".to_string(), "2".to_string()]);
+    }
+
+    #[gpui::test]
+    async fn test_command_history(cx: &mut TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa\nb\nc", Mode::Normal);
+        cx.simulate_keystrokes(": s / a / x enter");
+        cx.simulate_keystrokes(": 3 enter");
+        cx.simulate_keystrokes(": s / c / z enter");
+        cx.assert_state("x\nb\nˇz", Mode::Normal);
+
+        let query = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| {
+                workspace
+                    .active_modal::<CommandPalette>(cx)
+                    .unwrap()
+                    .read(cx)
+                    .query(cx)
+            })
+        };
+        cx.simulate_keystrokes(": up");
+        assert_eq!(query(&mut cx), "s/c/z");
+        cx.simulate_keystrokes("up");
+        assert_eq!(query(&mut cx), "3");
+        cx.simulate_keystrokes("escape");
+
+        cx.simulate_keystrokes(": s up");
+        assert_eq!(query(&mut cx), "s/c/z");
+        cx.simulate_keystrokes("up");
+        assert_eq!(query(&mut cx), "s/a/x");
+        cx.simulate_keystrokes("down down");
+        assert_eq!(query(&mut cx), "s");
+    }
 }
@@ -0,0 +1,148 @@
+//! `q:`, `q/` and `q?`, which open the command or search history in a small
+//! window below the editor. The history can be browsed and edited like any
+//! other text, and `enter` runs the line under the cursor.
+
+use editor::{scroll::Autoscroll, Editor};
+use gpui::{actions, View, ViewContext};
+use language::{Buffer, Point};
+use multi_buffer::MultiBufferRow;
+use workspace::{SaveIntent, SplitDirection, Workspace};
+
+use crate::{
+    command::{command_interceptor, push_history, HistoryCommand},
+    normal::search::FindCommand,
+    state::{CommandWindow, CommandWindowKind},
+    Vim,
+};
+
+actions!(
+    vim,
+    [
+        OpenCommandWindow,
+        OpenSearchWindow,
+        OpenSearchBackwardWindow,
+        ConfirmCommandWindow,
+        CloseCommandWindow
+    ]
+);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace, _: &OpenCommandWindow, cx| {
+        open_command_window(workspace, CommandWindowKind::Command, cx);
+    });
+    workspace.register_action(|workspace, _: &OpenSearchWindow, cx| {
+        open_command_window(
+            workspace,
+            CommandWindowKind::Search { backwards: false },
+            cx,
+        );
+    });
+    workspace.register_action(|workspace, _: &OpenSearchBackwardWindow, cx| {
+        open_command_window(workspace, CommandWindowKind::Search { backwards: true }, cx);
+    });
+    workspace.register_action(|workspace, _: &ConfirmCommandWindow, cx| {
+        let Some((window, editor)) = take_command_window(cx) else {
+            return;
+        };
+        let line = editor.update(cx, |editor, cx| {
+            let row = editor.selections.newest::<Point>(cx).head().row;
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            snapshot
+                .text_for_range(
+                    Point::new(row, 0)..Point::new(row, snapshot.line_len(MultiBufferRow(row))),
+                )
+                .collect::<String>()
+        });
+        close_command_window(workspace, &window, &editor, cx);
+        let line = line.trim().to_string();
+        if line.is_empty() {
+            return;
+        }
+
+        match window.kind {
+            CommandWindowKind::Command => {
+                let Some(result) = command_interceptor(&line, cx) else {
+                    workspace.show_error(&anyhow::anyhow!("Not an editor command: {}", line), cx);
+                    return;
+                };
+                let action = HistoryCommand::new(&line, result.action);
+                cx.defer(move |_, cx| cx.dispatch_action(Box::new(action)));
+            }
+            CommandWindowKind::Search { backwards } => {
+                Vim::update(cx, |vim, _| {
+                    push_history(&mut vim.workspace_state.search_history, &line)
+                });
+                let action = FindCommand {
+                    query: line,
+                    backwards,
+                };
+                cx.defer(move |_, cx| cx.dispatch_action(Box::new(action)));
+            }
+        }
+    });
+    workspace.register_action(|workspace, _: &CloseCommandWindow, cx| {
+        if let Some((window, editor)) = take_command_window(cx) {
+            close_command_window(workspace, &window, &editor, cx);
+        }
+    });
+}
+
+/// Opens the history as one entry per line, oldest first, with the cursor on
+/// the empty line after them. The `q` that came first is waiting for a
+/// register to record into, which this cancels.
+fn open_command_window(
+    workspace: &mut Workspace,
+    kind: CommandWindowKind,
+    cx: &mut ViewContext<Workspace>,
+) {
+    Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+    let state = &Vim::read(cx).workspace_state;
+    let history = match kind {
+        CommandWindowKind::Command => &state.command_history,
+        CommandWindowKind::Search { .. } => &state.search_history,
+    };
+    let mut text = history.join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+
+    let buffer = cx.new_model(|cx| Buffer::local(text, cx));
+    let editor: View<Editor> = cx.new_view(|cx| Editor::for_buffer(buffer, None, cx));
+    let origin = workspace.active_pane().downgrade();
+    Vim::update(cx, |vim, _| {
+        vim.workspace_state.command_window = Some(CommandWindow {
+            editor: editor.downgrade(),
+            origin,
+            kind,
+        })
+    });
+    workspace.split_item(SplitDirection::Down, Box::new(editor.clone()), cx);
+    editor.update(cx, |editor, cx| {
+        let end = editor.buffer().read(cx).len(cx);
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| s.select_ranges([end..end]));
+    });
+}
+
+fn take_command_window(cx: &mut ViewContext<Workspace>) -> Option<(CommandWindow, View<Editor>)> {
+    let window = Vim::update(cx, |vim, _| vim.workspace_state.command_window.take())?;
+    let editor = window.editor.upgrade()?;
+    Some((window, editor))
+}
+
+/// Returns focus to the pane the window was opened from, then closes it.
+fn close_command_window(
+    workspace: &mut Workspace,
+    window: &CommandWindow,
+    editor: &View<Editor>,
+    cx: &mut ViewContext<Workspace>,
+) {
+    if let Some(origin) = window.origin.upgrade() {
+        cx.focus_view(&origin);
+    }
+    if let Some(pane) = workspace.pane_for(editor) {
+        pane.update(cx, |pane, cx| {
+            pane.close_item_by_id(editor.entity_id(), SaveIntent::Skip, cx)
+                .detach_and_log_err(cx)
+        });
+    }
+}
@@ -1,15 +1,18 @@
 use std::sync::Arc;
 
 use collections::HashMap;
-use gpui::AppContext;
+use gpui::{actions, AppContext, ViewContext};
 use lazy_static::lazy_static;
 use settings::Settings;
 use ui::WindowContext;
+use workspace::Workspace;
 
-use crate::{Vim, VimSettings};
+use crate::{state::Operator, Vim, VimSettings};
 
 mod default;
 
+actions!(vim, [ShowDigraphs]);
+
 lazy_static! {
     static ref DEFAULT_DIGRAPHS_MAP: HashMap<String, Arc<str>> = {
         let mut map = HashMap::default();
@@ -22,7 +25,18 @@ lazy_static! {
     };
 }
 
-fn lookup_digraph(a: char, b: char, cx: &AppContext) -> Arc<str> {
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &ShowDigraphs, cx| {
+        let text = digraphs_listing(cx);
+        cx.emit(workspace::Event::OpenBundledFile {
+            text: text.into(),
+            title: "Digraphs",
+            language: "Plain Text",
+        });
+    });
+}
+
+fn lookup_digraph(a: char, b: char, cx: &AppContext) -> Option<Arc<str>> {
     let custom_digraphs = &VimSettings::get_global(cx).custom_digraphs;
     let input = format!("{a}{b}");
     let reversed = format!("{b}{a}");
@@ -33,11 +47,14 @@ fn lookup_digraph(a: char, b: char, cx: &AppContext) -> Arc<str> {
         .or_else(|| custom_digraphs.get(&reversed))
         .or_else(|| DEFAULT_DIGRAPHS_MAP.get(&reversed))
         .cloned()
-        .unwrap_or_else(|| b.to_string().into())
 }
 
 pub fn insert_digraph(first_char: char, second_char: char, cx: &mut WindowContext) {
-    let text = lookup_digraph(first_char, second_char, &cx);
+    // Unknown digraphs insert nothing, and cancel whatever was waiting for them.
+    let Some(text) = lookup_digraph(first_char, second_char, &cx) else {
+        Vim::update(cx, |vim, cx| vim.clear_operator(cx));
+        return;
+    };
 
     Vim::update(cx, |vim, cx| vim.pop_operator(cx));
     if Vim::read(cx).state().editor_input_enabled() {
@@ -49,11 +66,114 @@ pub fn insert_digraph(first_char: char, second_char: char, cx: &mut WindowContex
     }
 }
 
+/// Handles a key typed after `ctrl-v`, which inserts it literally or starts
+/// a code point: up to three decimal digits, `o` and three octal digits, `x`
+/// and two hex digits, `u` and four, or `U` and eight. A key that can't be part
+/// of the code point ends it early and is inserted after it.
+pub fn insert_literal(prefix: Option<String>, text: &str, cx: &mut WindowContext) {
+    let mut prefix = prefix;
+    let mut output = String::new();
+    for ch in text.chars() {
+        let Some(mut typed) = prefix.take() else {
+            if matches!(ch, 'u' | 'U' | 'x' | 'X' | 'o' | 'O') || ch.is_ascii_digit() {
+                prefix = Some(ch.to_string());
+            } else {
+                output.push(ch);
+                break;
+            }
+            continue;
+        };
+
+        let (radix, max_digits, digits_start) = match typed.chars().next() {
+            Some('u') => (16, 4, 1),
+            Some('U') => (16, 8, 1),
+            Some('x' | 'X') => (16, 2, 1),
+            Some('o' | 'O') => (8, 3, 1),
+            _ => (10, 3, 0),
+        };
+        typed.push(ch);
+        let digits = &typed[digits_start..];
+        let fits = ch.is_digit(radix)
+            && (radix != 10 || digits.parse::<u32>().is_ok_and(|value| value <= 255));
+        if !fits {
+            typed.pop();
+            output.push_str(&literal_code_point(&typed, digits_start, radix));
+            output.push(ch);
+            break;
+        }
+        if digits.len() == max_digits {
+            output.push_str(&literal_code_point(&typed, digits_start, radix));
+            break;
+        }
+        prefix = Some(typed);
+    }
+
+    Vim::update(cx, |vim, cx| {
+        vim.pop_operator(cx);
+        if prefix.is_some() {
+            vim.push_operator(Operator::Literal { prefix }, cx);
+        }
+    });
+    if output.is_empty() {
+        return;
+    }
+    if Vim::read(cx).state().editor_input_enabled() {
+        Vim::update(cx, |vim, cx| {
+            vim.update_active_editor(cx, |_, editor, cx| editor.insert(&output, cx));
+        });
+    } else {
+        Vim::active_editor_input_ignored(output.into(), cx);
+    }
+}
+
+/// The character for the digits typed after `ctrl-v`, or the letter that
+/// introduced them if none were typed.
+fn literal_code_point(typed: &str, digits_start: usize, radix: u32) -> String {
+    let digits = &typed[digits_start..];
+    if digits.is_empty() {
+        return typed.to_string();
+    }
+    u32::from_str_radix(digits, radix)
+        .ok()
+        .and_then(char::from_u32)
+        .map(|ch| ch.to_string())
+        .unwrap_or_default()
+}
+
+/// Lists the custom digraphs and then the default ones, one per line, as the
+/// two characters, the text they produce and its code point in decimal.
+fn digraphs_listing(cx: &AppContext) -> String {
+    let mut custom_digraphs = VimSettings::get_global(cx)
+        .custom_digraphs
+        .iter()
+        .collect::<Vec<_>>();
+    custom_digraphs.sort();
+
+    let mut text = String::new();
+    for (input, output) in custom_digraphs {
+        let code = output.chars().next().map(|c| c as u32).unwrap_or_default();
+        text.push_str(&format!("{input} {output} {code}\n"));
+    }
+    for &(a, b, c) in default::DEFAULT_DIGRAPHS {
+        let output = char::from_u32(c).unwrap();
+        let output = match c {
+            // Show control characters in caret notation, as vim does.
+            0x00..=0x1f => format!("^{}", char::from_u32(c + 0x40).unwrap()),
+            0x7f => "^?".to_string(),
+            _ if output.is_control() => format!("<{c:x}>"),
+            _ => output.to_string(),
+        };
+        text.push_str(&format!("{a}{b} {output} {c}\n"));
+    }
+    text
+}
+
 #[cfg(test)]
 mod test {
     use collections::HashMap;
     use settings::SettingsStore;
 
+    use super::digraphs_listing;
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
@@ -151,4 +271,69 @@ mod test {
         cx.simulate_shared_keystrokes("a ctrl-k s , escape").await;
         cx.shared_state().await.assert_eq("Helloˇş");
     }
+
+    #[gpui::test]
+    async fn test_digraph_common(cx: &mut gpui::TestAppContext) {
+        let mut cx: NeovimBackedTestContext = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇ").await;
+        cx.simulate_shared_keystrokes(
+            "a ctrl-k a : ctrl-k e ' ctrl-k E u ctrl-k - > ctrl-k > - ctrl-k = > escape",
+        )
+        .await;
+        cx.shared_state().await.assert_eq("äé€→→ˇ⇒");
+    }
+
+    #[gpui::test]
+    async fn test_literal_code_points(cx: &mut gpui::TestAppContext) {
+        let mut cx: NeovimBackedTestContext = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇ").await;
+        cx.simulate_shared_keystrokes("a ctrl-v u 0 0 e 9 ctrl-v U 0 0 0 1 f 6 0 0 escape")
+            .await;
+        cx.shared_state().await.assert_eq("éˇ😀");
+
+        cx.set_shared_state("ˇ").await;
+        cx.simulate_shared_keystrokes("a ctrl-v x 4 1 ctrl-v 0 6 5 escape")
+            .await;
+        cx.shared_state().await.assert_eq("AˇA");
+
+        cx.set_shared_state("ˇHello").await;
+        cx.simulate_shared_keystrokes("r ctrl-v u 0 0 e 9").await;
+        cx.shared_state().await.assert_eq("ˇéello");
+    }
+
+    #[gpui::test]
+    async fn test_literal_ended_early(cx: &mut gpui::TestAppContext) {
+        let mut cx: NeovimBackedTestContext = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇ").await;
+        cx.simulate_shared_keystrokes("a ctrl-v u e 9 z ctrl-v 6 5 y escape")
+            .await;
+        cx.shared_state().await.assert_eq("ézAˇy");
+    }
+
+    #[gpui::test]
+    async fn test_digraph_unknown(cx: &mut gpui::TestAppContext) {
+        let mut cx: VimTestContext = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes("a ctrl-k q q x");
+        cx.assert_state("xˇ", Mode::Insert);
+
+        cx.set_state("ˇHello", Mode::Normal);
+        cx.simulate_keystrokes("r ctrl-k q q");
+        cx.assert_state("ˇHello", Mode::Normal);
+        assert_eq!(cx.active_operator(), None);
+    }
+
+    #[gpui::test]
+    async fn test_digraphs_listing(cx: &mut gpui::TestAppContext) {
+        let mut cx: VimTestContext = VimTestContext::new(cx, true).await;
+
+        let listing = cx.update(|cx| digraphs_listing(cx));
+        assert!(listing.starts_with("NU ^@ 0\n"));
+        assert!(listing.contains("\na: ä 228\n"));
+        assert!(listing.contains("\n-> → 8594\n"));
+    }
 }
@@ -1,10 +1,12 @@
-use crate::{insert::NormalBefore, Vim, VimModeSetting};
+use crate::{insert::NormalBefore, prompt, Vim, VimModeSetting};
 use editor::{Editor, EditorEvent};
 use gpui::{Action, AppContext, Entity, EntityId, UpdateGlobal, View, ViewContext, WindowContext};
 use settings::{Settings, SettingsStore};
 
 pub fn init(cx: &mut AppContext) {
-    cx.observe_new_views(|_, cx: &mut ViewContext<Editor>| {
+    cx.observe_new_views(|editor, cx: &mut ViewContext<Editor>| {
+        prompt::register(editor, cx);
+
         let editor = cx.view().clone();
         cx.subscribe(&editor, |_, editor, event: &EditorEvent, cx| match event {
             EditorEvent::Focused => cx.window_context().defer(|cx| focused(editor, cx)),
@@ -21,6 +23,10 @@ pub fn init(cx: &mut AppContext) {
                     Vim::unhook_vim_settings(editor, cx);
                 }
             }
+            // The editor has just reset its scroll margins from its own settings.
+            if enabled && editor.use_modal_editing() {
+                Vim::apply_scroll_off(editor, cx);
+            }
         })
         .detach();
 
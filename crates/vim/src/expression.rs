@@ -0,0 +1,479 @@
+use std::{fmt, iter::Peekable, str::Chars};
+
+use anyhow::{anyhow, Result};
+
+/// The most items a list, or bytes a string, that `range()` and `repeat()`
+/// may build, so that a mistyped count doesn't exhaust memory.
+const MAX_VALUE_LEN: usize = 1 << 20;
+
+/// A value produced by evaluating a vim expression.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Value {
+    Number(i64),
+    String(String),
+    List(Vec<Value>),
+}
+
+impl Value {
+    /// Returns the lines that putting this value inserts: one per item for a
+    /// list, otherwise the value's text split on newlines.
+    pub(crate) fn to_lines(&self) -> Vec<String> {
+        match self {
+            Value::List(items) => items.iter().map(ToString::to_string).collect(),
+            value => value
+                .to_string()
+                .split('\n')
+                .map(ToString::to_string)
+                .collect(),
+        }
+    }
+
+    fn to_number(&self) -> Result<i64> {
+        match self {
+            Value::Number(number) => Ok(*number),
+            // As in vim, a string is converted using its leading digits.
+            Value::String(string) => {
+                let string = string.trim_start();
+                let (sign, digits) = match string.strip_prefix('-') {
+                    Some(digits) => (-1, digits),
+                    None => (1, string),
+                };
+                let len = digits
+                    .find(|c: char| !c.is_ascii_digit())
+                    .unwrap_or(digits.len());
+                if len == 0 {
+                    return Ok(0);
+                }
+                let number = digits[..len]
+                    .parse::<i64>()
+                    .map_err(|_| anyhow!("Number too large: {}", &digits[..len]))?;
+                Ok(sign * number)
+            }
+            Value::List(_) => Err(anyhow!("Using a List as a Number")),
+        }
+    }
+
+    fn to_list(self) -> Result<Vec<Value>> {
+        match self {
+            Value::List(items) => Ok(items),
+            _ => Err(anyhow!("List required")),
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Number(number) => write!(f, "{}", number),
+            Value::String(string) => write!(f, "{}", string),
+            Value::List(items) => {
+                write!(f, "[")?;
+                for (ix, item) in items.iter().enumerate() {
+                    if ix > 0 {
+                        write!(f, ", ")?;
+                    }
+                    match item {
+                        Value::String(string) => write!(f, "'{}'", string.replace('\'', "''"))?,
+                        item => write!(f, "{}", item)?,
+                    }
+                }
+                write!(f, "]")
+            }
+        }
+    }
+}
+
+/// Returns the result of an arithmetic operation, which is an error rather than
+/// wrapping around when it doesn't fit in a number.
+fn checked(result: Option<i64>) -> Result<i64> {
+    result.ok_or_else(|| anyhow!("Number overflow"))
+}
+
+/// Evaluates `expr`, which may use numbers, strings, lists, arithmetic,
+/// string concatenation (`.` or `..`) and a few builtin functions such as
+/// `range()`.
+pub(crate) fn evaluate(expr: &str) -> Result<Value> {
+    evaluate_with_submatches(expr, &[])
+}
+
+/// Evaluates `expr` like [`evaluate`], with `submatch(n)` returning
+/// `submatches[n]` as it does in a `:s` replacement.
+pub(crate) fn evaluate_with_submatches(expr: &str, submatches: &[&str]) -> Result<Value> {
+    let mut parser = Parser {
+        chars: expr.chars().peekable(),
+        submatches,
+    };
+    let value = parser.expr()?;
+    parser.skip_whitespace();
+    if parser.chars.peek().is_some() {
+        return Err(anyhow!("Invalid expression: {}", expr));
+    }
+    Ok(value)
+}
+
+struct Parser<'a> {
+    chars: Peekable<Chars<'a>>,
+    submatches: &'a [&'a str],
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.chars.next_if(|c| c.is_whitespace()).is_some() {}
+    }
+
+    fn expect(&mut self, expected: char) -> Result<()> {
+        self.skip_whitespace();
+        match self.chars.next() {
+            Some(c) if c == expected => Ok(()),
+            _ => Err(anyhow!("Missing '{}'", expected)),
+        }
+    }
+
+    /// Parses additions, subtractions and concatenations.
+    fn expr(&mut self) -> Result<Value> {
+        let mut value = self.term()?;
+        loop {
+            self.skip_whitespace();
+            match self.chars.peek() {
+                Some('+') => {
+                    self.chars.next();
+                    let rhs = self.term()?;
+                    value = match (value, rhs) {
+                        (Value::List(mut lhs), Value::List(rhs)) => {
+                            lhs.extend(rhs);
+                            Value::List(lhs)
+                        }
+                        (lhs, rhs) => {
+                            Value::Number(checked(lhs.to_number()?.checked_add(rhs.to_number()?))?)
+                        }
+                    };
+                }
+                Some('-') => {
+                    self.chars.next();
+                    let rhs = self.term()?;
+                    value =
+                        Value::Number(checked(value.to_number()?.checked_sub(rhs.to_number()?))?);
+                }
+                Some('.') => {
+                    self.chars.next();
+                    self.chars.next_if_eq(&'.');
+                    let rhs = self.term()?;
+                    if matches!(value, Value::List(_)) || matches!(rhs, Value::List(_)) {
+                        return Err(anyhow!("Using a List as a String"));
+                    }
+                    value = Value::String(format!("{}{}", value, rhs));
+                }
+                _ => return Ok(value),
+            }
+        }
+    }
+
+    /// Parses multiplications, divisions and remainders.
+    fn term(&mut self) -> Result<Value> {
+        let mut value = self.unary()?;
+        loop {
+            self.skip_whitespace();
+            let Some(op) = self.chars.next_if(|c| matches!(c, '*' | '/' | '%')) else {
+                return Ok(value);
+            };
+            let lhs = value.to_number()?;
+            let rhs = self.unary()?.to_number()?;
+            if rhs == 0 && op != '*' {
+                return Err(anyhow!("Division by zero"));
+            }
+            value = Value::Number(checked(match op {
+                '*' => lhs.checked_mul(rhs),
+                '/' => lhs.checked_div(rhs),
+                _ => lhs.checked_rem(rhs),
+            })?);
+        }
+    }
+
+    fn unary(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.chars.peek() {
+            Some('-') => {
+                self.chars.next();
+                Ok(Value::Number(checked(
+                    self.unary()?.to_number()?.checked_neg(),
+                )?))
+            }
+            Some('+') => {
+                self.chars.next();
+                Ok(Value::Number(self.unary()?.to_number()?))
+            }
+            Some('!') => {
+                self.chars.next();
+                Ok(Value::Number((self.unary()?.to_number()? == 0) as i64))
+            }
+            _ => self.primary(),
+        }
+    }
+
+    fn primary(&mut self) -> Result<Value> {
+        self.skip_whitespace();
+        match self.chars.peek().copied() {
+            Some(c) if c.is_ascii_digit() => {
+                let mut digits = String::new();
+                while let Some(c) = self.chars.next_if(|c| c.is_ascii_alphanumeric()) {
+                    digits.push(c);
+                }
+                let number = match digits.strip_prefix("0x").or(digits.strip_prefix("0X")) {
+                    Some(hex) => i64::from_str_radix(hex, 16),
+                    None => digits.parse(),
+                };
+                number
+                    .map(Value::Number)
+                    .map_err(|_| anyhow!("Invalid number: {}", digits))
+            }
+            Some('\'') => {
+                self.chars.next();
+                let mut string = String::new();
+                loop {
+                    match self.chars.next() {
+                        // A doubled quote is a literal quote.
+                        Some('\'') if self.chars.next_if_eq(&'\'').is_some() => string.push('\''),
+                        Some('\'') => return Ok(Value::String(string)),
+                        Some(c) => string.push(c),
+                        None => return Err(anyhow!("Missing quote: '{}", string)),
+                    }
+                }
+            }
+            Some('"') => {
+                self.chars.next();
+                let mut string = String::new();
+                loop {
+                    match self.chars.next() {
+                        Some('\\') => match self.chars.next() {
+                            Some('n') => string.push('\n'),
+                            Some('t') => string.push('\t'),
+                            Some('r') => string.push('\r'),
+                            Some(c) => string.push(c),
+                            None => return Err(anyhow!("Missing quote: \"{}", string)),
+                        },
+                        Some('"') => return Ok(Value::String(string)),
+                        Some(c) => string.push(c),
+                        None => return Err(anyhow!("Missing quote: \"{}", string)),
+                    }
+                }
+            }
+            Some('[') => {
+                self.chars.next();
+                Ok(Value::List(self.arguments(']')?))
+            }
+            Some('(') => {
+                self.chars.next();
+                let value = self.expr()?;
+                self.expect(')')?;
+                Ok(value)
+            }
+            Some(c) if c.is_ascii_alphabetic() || c == '_' => {
+                let mut name = String::new();
+                while let Some(c) = self
+                    .chars
+                    .next_if(|c| c.is_ascii_alphanumeric() || *c == '_')
+                {
+                    name.push(c);
+                }
+                self.expect('(')
+                    .map_err(|_| anyhow!("Undefined variable: {}", name))?;
+                let arguments = self.arguments(')')?;
+                call_function(&name, arguments, self.submatches)
+            }
+            _ => Err(anyhow!("Invalid expression")),
+        }
+    }
+
+    /// Parses comma separated expressions up to `close`, allowing a trailing
+    /// comma as vim does for lists.
+    fn arguments(&mut self, close: char) -> Result<Vec<Value>> {
+        let mut values = Vec::new();
+        loop {
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&close).is_some() {
+                return Ok(values);
+            }
+            values.push(self.expr()?);
+            self.skip_whitespace();
+            if self.chars.next_if_eq(&',').is_none() {
+                self.expect(close)?;
+                return Ok(values);
+            }
+        }
+    }
+}
+
+fn call_function(name: &str, arguments: Vec<Value>, submatches: &[&str]) -> Result<Value> {
+    let numbers = || {
+        arguments
+            .iter()
+            .map(Value::to_number)
+            .collect::<Result<Vec<_>>>()
+    };
+    let string = |ix: usize| arguments.get(ix).map(ToString::to_string);
+
+    match (name, arguments.len()) {
+        ("range", 1..=3) => {
+            let numbers = numbers()?;
+            let (start, end, stride) = match numbers[..] {
+                [end] => (0, checked(end.checked_sub(1))?, 1),
+                [start, end] => (start, end, 1),
+                [start, end, stride] => (start, end, stride),
+                _ => unreachable!(),
+            };
+            if stride == 0 {
+                return Err(anyhow!("Stride is zero"));
+            }
+            let mut items = Vec::new();
+            let mut number = start;
+            while (stride > 0 && number <= end) || (stride < 0 && number >= end) {
+                if items.len() == MAX_VALUE_LEN {
+                    return Err(anyhow!("List too long"));
+                }
+                items.push(Value::Number(number));
+                // Past the largest or smallest number, the end is passed too.
+                let Some(next) = number.checked_add(stride) else {
+                    break;
+                };
+                number = next;
+            }
+            Ok(Value::List(items))
+        }
+        ("len", 1) => Ok(Value::Number(match &arguments[0] {
+            Value::List(items) => items.len() as i64,
+            value => value.to_string().len() as i64,
+        })),
+        ("toupper", 1) => Ok(Value::String(string(0).unwrap().to_uppercase())),
+        ("tolower", 1) => Ok(Value::String(string(0).unwrap().to_lowercase())),
+        ("repeat", 2) => {
+            let count = usize::try_from(arguments[1].to_number()?).unwrap_or(0);
+            let too_long = |len: usize| {
+                len.checked_mul(count)
+                    .filter(|len| *len <= MAX_VALUE_LEN)
+                    .is_none()
+            };
+            Ok(match &arguments[0] {
+                Value::List(items) => {
+                    if too_long(items.len()) {
+                        return Err(anyhow!("List too long"));
+                    }
+                    Value::List(
+                        items
+                            .iter()
+                            .cycle()
+                            .take(items.len() * count)
+                            .cloned()
+                            .collect(),
+                    )
+                }
+                value => {
+                    let string = value.to_string();
+                    if too_long(string.len()) {
+                        return Err(anyhow!("String too long"));
+                    }
+                    Value::String(string.repeat(count))
+                }
+            })
+        }
+        ("join", 1..=2) => {
+            let separator = string(1).unwrap_or_else(|| " ".to_string());
+            let mut arguments = arguments;
+            let items = arguments.swap_remove(0).to_list()?;
+            Ok(Value::String(
+                items
+                    .iter()
+                    .map(ToString::to_string)
+                    .collect::<Vec<_>>()
+                    .join(&separator),
+            ))
+        }
+        ("string", 1) => Ok(Value::String(match &arguments[0] {
+            Value::String(string) => format!("'{}'", string.replace('\'', "''")),
+            value => value.to_string(),
+        })),
+        ("submatch", 1) => {
+            let ix = arguments[0].to_number()?;
+            Ok(Value::String(
+                usize::try_from(ix)
+                    .ok()
+                    .and_then(|ix| submatches.get(ix))
+                    .map_or_else(String::new, ToString::to_string),
+            ))
+        }
+        (
+            "range" | "len" | "toupper" | "tolower" | "repeat" | "join" | "string" | "submatch",
+            _,
+        ) => Err(anyhow!("Wrong number of arguments for function: {}", name)),
+        _ => Err(anyhow!("Unknown function: {}", name)),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{evaluate, evaluate_with_submatches, Value};
+
+    #[test]
+    fn test_evaluate() {
+        assert_eq!(evaluate("1 + 2 * 3").unwrap(), Value::Number(7));
+        assert_eq!(evaluate("(1 + 2) * 3 - -1").unwrap(), Value::Number(10));
+        assert_eq!(evaluate("7 / 2 . 'x'").unwrap().to_string(), "3x");
+        assert_eq!(evaluate("\"a\\nb\"").unwrap().to_lines(), ["a", "b"]);
+        assert_eq!(evaluate("'it''s'").unwrap().to_string(), "it's");
+        assert_eq!(
+            evaluate("range(1, 3) + [4]").unwrap().to_lines(),
+            ["1", "2", "3", "4"]
+        );
+        assert_eq!(evaluate("range(3)").unwrap().to_string(), "[0, 1, 2]");
+        assert_eq!(
+            evaluate("join(['a', 'b'], '-')").unwrap().to_string(),
+            "a-b"
+        );
+        assert_eq!(
+            evaluate_with_submatches("submatch(0) * 2 . submatch(1) . submatch(5)", &["21", "x"])
+                .unwrap()
+                .to_string(),
+            "42x"
+        );
+        assert!(evaluate("1 +").is_err());
+        assert!(evaluate("1 / 0").is_err());
+        assert!(evaluate("nope()").is_err());
+    }
+
+    #[test]
+    fn test_evaluate_overflow() {
+        assert_eq!(
+            evaluate("9223372036854775807").unwrap(),
+            Value::Number(i64::MAX)
+        );
+        assert!(evaluate("9223372036854775808").is_err());
+        assert!(evaluate("9999999999 * 9999999999").is_err());
+        assert!(evaluate("9223372036854775807 + 1").is_err());
+        assert!(evaluate("-9223372036854775807 - 2").is_err());
+        assert!(evaluate("-(-9223372036854775807 - 1)").is_err());
+        assert!(evaluate("(-9223372036854775807 - 1) / -1").is_err());
+        assert!(evaluate("(-9223372036854775807 - 1) % -1").is_err());
+
+        // text from the buffer is checked too
+        assert!(evaluate_with_submatches("submatch(0) * 2", &["99999999999999999999"]).is_err());
+        assert!(evaluate_with_submatches("submatch(0) * 2", &["4611686018427387904"]).is_err());
+        assert_eq!(
+            evaluate_with_submatches("submatch(0) + 1", &["abc"]).unwrap(),
+            Value::Number(1)
+        );
+    }
+
+    #[test]
+    fn test_evaluate_limits() {
+        assert!(evaluate("range(1, 10000000000)").is_err());
+        assert!(evaluate("repeat('ab', 10000000000)").is_err());
+        assert!(evaluate("repeat([1, 2], 9223372036854775807)").is_err());
+        assert_eq!(evaluate("repeat('ab', -1)").unwrap().to_string(), "");
+        assert_eq!(
+            evaluate("range(9223372036854775806, 9223372036854775807)")
+                .unwrap()
+                .to_lines(),
+            ["9223372036854775806", "9223372036854775807"]
+        );
+        assert_eq!(evaluate("len(range(1000))").unwrap(), Value::Number(1000));
+    }
+}
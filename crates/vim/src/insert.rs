@@ -1,17 +1,193 @@
 use crate::{
+    motion::{first_non_whitespace, previous_word_start, start_of_line},
     normal::{mark::create_mark, repeat},
-    state::Mode,
+    state::{Mode, Register},
     Vim,
 };
-use editor::{scroll::Autoscroll, Bias};
-use gpui::{actions, Action, ViewContext};
+use editor::{
+    display_map::{DisplaySnapshot, ToDisplayPoint},
+    scroll::Autoscroll,
+    Bias, DisplayPoint, ToOffset,
+};
+use gpui::{actions, impl_actions, Action, ViewContext, WindowContext};
 use language::SelectionGoal;
+use serde::Deserialize;
 use workspace::Workspace;
 
-actions!(vim, [NormalBefore]);
+actions!(
+    vim,
+    [
+        NormalBefore,
+        TemporaryNormal,
+        DeleteWordBeforeCursor,
+        DeleteLineBeforeCursor,
+        PasteVerbatim,
+    ]
+);
+
+/// Inserts the text from the previous insertion again, as `ctrl-a` does, and
+/// then (as `ctrl-@` does) optionally leaves insert mode.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct InsertLastInserted {
+    #[serde(default)]
+    stop_insert: bool,
+}
+
+impl_actions!(vim, [InsertLastInserted]);
 
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(normal_before);
+    workspace.register_action(insert_last_inserted);
+    workspace.register_action(paste_verbatim);
+    workspace.register_action(|_: &mut Workspace, _: &TemporaryNormal, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.update_state(|state| state.temp_mode = true);
+            vim.switch_mode(Mode::Normal, true, cx);
+        })
+    });
+    workspace.register_action(|_: &mut Workspace, _: &DeleteWordBeforeCursor, cx| {
+        delete_before_cursor(cx, |map, head| previous_word_start(map, head, false, 1))
+    });
+    workspace.register_action(|_: &mut Workspace, _: &DeleteLineBeforeCursor, cx| {
+        delete_before_cursor(cx, |map, head| {
+            let first_non_blank = first_non_whitespace(map, false, head);
+            if head > first_non_blank {
+                first_non_blank
+            } else {
+                start_of_line(map, false, head)
+            }
+        })
+    });
+}
+
+/// Remembers the text between where insertion started and the newest cursor,
+/// which is what was typed in the end, whatever was deleted along the way.
+fn store_last_inserted_text(vim: &mut Vim, cx: &mut WindowContext) {
+    let Some(insert_start) = vim.state().insert_start.clone() else {
+        return;
+    };
+    let text = vim.update_active_editor(cx, |_, editor, cx| {
+        let buffer = editor.buffer().read(cx).snapshot(cx);
+        let head = editor.selections.newest::<usize>(cx).head();
+        let start = insert_start
+            .iter()
+            .map(|anchor| anchor.to_offset(&buffer))
+            .filter(|start| *start <= head)
+            .max()
+            .unwrap_or(head);
+        buffer.text_for_range(start..head).collect::<String>()
+    });
+    if let Some(text) = text {
+        vim.workspace_state.last_inserted_text = Some(text.into());
+    }
+}
+
+/// After `ctrl-o`, goes back to insert mode once the normal-mode command has
+/// finished, wherever it left the cursor. Commands that wait for more input,
+/// like an operator or a count, keep normal mode until they are complete.
+pub(crate) fn resume_insert(action: &dyn Action, cx: &mut WindowContext) {
+    if TemporaryNormal.partial_eq(action) {
+        return;
+    }
+    Vim::update(cx, |vim, cx| {
+        let state = vim.state();
+        if !state.temp_mode {
+            return;
+        }
+        match state.mode {
+            Mode::Normal
+                if state.operator_stack.is_empty()
+                    && state.pre_count.is_none()
+                    && state.post_count.is_none()
+                    && state.selected_register.is_none() =>
+            {
+                vim.update_state(|state| state.temp_mode = false);
+                vim.switch_mode(Mode::Insert, true, cx);
+                vim.record_insert_start(cx);
+            }
+            Mode::Insert | Mode::Replace => vim.update_state(|state| state.temp_mode = false),
+            _ => {}
+        }
+    })
+}
+
+fn insert_last_inserted(
+    workspace: &mut Workspace,
+    action: &InsertLastInserted,
+    cx: &mut ViewContext<Workspace>,
+) {
+    Vim::update(cx, |vim, cx| {
+        let Some(text) = vim.workspace_state.last_inserted_text.clone() else {
+            return;
+        };
+        vim.update_active_editor(cx, |_, editor, cx| editor.insert(&text, cx));
+    });
+    if action.stop_insert {
+        normal_before(workspace, &NormalBefore, cx);
+    }
+}
+
+/// Pastes the clipboard exactly as it was copied, like a terminal's bracketed
+/// paste, so that it isn't reindented and no brackets are paired. As with the
+/// editor's paste, text copied from several cursors is shared out between
+/// them again.
+fn paste_verbatim(_: &mut Workspace, _: &PasteVerbatim, cx: &mut ViewContext<Workspace>) {
+    Vim::update(cx, |vim, cx| {
+        // a terminal pastes from the system clipboard, whichever provider
+        // the registers use
+        let Some(item) = cx.read_from_clipboard() else {
+            return;
+        };
+        let register = Register::from(item);
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.do_paste(
+                &register.text.to_string(),
+                register.clipboard_selections,
+                true,
+                false,
+                cx,
+            )
+        });
+    });
+}
+
+/// Deletes back from each cursor to `target`, as `ctrl-w` and `ctrl-u` do.
+/// As in vim, the deletion stops where insertion started if it would go past
+/// it, but only once, so doing it again carries on from there.
+fn delete_before_cursor(
+    cx: &mut WindowContext,
+    target: impl Fn(&DisplaySnapshot, DisplayPoint) -> DisplayPoint,
+) {
+    Vim::update(cx, |vim, cx| {
+        let insert_start = vim.state().insert_start.clone().unwrap_or_default();
+        let mut stopped_at_insert_start = false;
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.transact(cx, |editor, cx| {
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.move_with(|map, selection| {
+                        let head = selection.head();
+                        let mut start = target(map, head);
+                        for point in insert_start
+                            .iter()
+                            .map(|anchor| anchor.to_display_point(map))
+                        {
+                            if start < point && point < head {
+                                start = point;
+                                stopped_at_insert_start = true;
+                            }
+                        }
+                        selection.collapse_to(head, SelectionGoal::None);
+                        selection.set_head(start, SelectionGoal::None);
+                    });
+                });
+                editor.insert("", cx);
+            });
+        });
+        if stopped_at_insert_start {
+            vim.update_state(|state| state.insert_start.take());
+        }
+    });
 }
 
 fn normal_before(_: &mut Workspace, action: &NormalBefore, cx: &mut ViewContext<Workspace>) {
@@ -22,6 +198,7 @@ fn normal_before(_: &mut Workspace, action: &NormalBefore, cx: &mut ViewContext<
             return false;
         }
         let count = vim.take_count(cx).unwrap_or(1);
+        store_last_inserted_text(vim, cx);
         vim.stop_recording_immediately(action.boxed_clone());
         if count <= 1 || vim.workspace_state.dot_replaying {
             create_mark(vim, "^".into(), false, cx);
@@ -52,6 +229,8 @@ mod test {
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
     };
+    use editor::ClipboardSelection;
+    use gpui::ClipboardItem;
 
     #[gpui::test]
     async fn test_enter_and_exit_insert_mode(cx: &mut gpui::TestAppContext) {
@@ -92,6 +271,26 @@ mod test {
         cx.shared_state().await.assert_eq("oi\noi\noˇi\nhello\n");
     }
 
+    #[gpui::test]
+    async fn test_insert_with_counts_at_previous(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇab", Mode::Normal);
+        cx.simulate_keystrokes("3 a x escape");
+        cx.assert_state("axxˇxb", Mode::Normal);
+
+        cx.set_state("ˇhello", Mode::Normal);
+        cx.simulate_keystrokes("2 o l i n e escape");
+        cx.assert_state("hello\nline\nlinˇe", Mode::Normal);
+
+        // `gi` carries on from where the last insert stopped
+        cx.set_state("ˇhello world", Mode::Normal);
+        cx.simulate_keystrokes("e a ! escape w");
+        cx.assert_state("hello! ˇworld", Mode::Normal);
+        cx.simulate_keystrokes("2 g i ? escape");
+        cx.assert_state("hello!?ˇ? world", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_insert_with_repeat(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -129,4 +328,138 @@ mod test {
             .await;
         cx.shared_state().await.assert_eq("hehello\nˇllo\n");
     }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_w(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ", Mode::Normal);
+        cx.simulate_keystrokes("i o n e space t w o space t h r e e ctrl-w");
+        cx.assert_state("one two ˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-w");
+        cx.assert_state("one ˇ", Mode::Insert);
+
+        // It stops once where insertion started.
+        cx.set_state("foˇo", Mode::Normal);
+        cx.simulate_keystrokes("a b a r ctrl-w");
+        cx.assert_state("fooˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-w");
+        cx.assert_state("ˇ", Mode::Insert);
+
+        // And it is repeated by `.`.
+        cx.set_state("ˇ\n", Mode::Normal);
+        cx.simulate_keystrokes("i a b space c d ctrl-w escape");
+        cx.assert_state("abˇ \n", Mode::Normal);
+        cx.simulate_keystrokes(".");
+        cx.assert_state("ababˇ  \n", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_u(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("    aˇbc", Mode::Normal);
+        cx.simulate_keystrokes("shift-a space d e f ctrl-u");
+        cx.assert_state("    abcˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-u");
+        cx.assert_state("    ˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-u");
+        cx.assert_state("ˇ", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_t_ctrl_d(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("aˇbc\ndef", Mode::Normal);
+        cx.simulate_keystrokes("i x ctrl-t");
+        cx.assert_state("    axˇbc\ndef", Mode::Insert);
+        cx.simulate_keystrokes("escape j .");
+        cx.assert_state("    axbc\n    deˇxf", Mode::Normal);
+        cx.simulate_keystrokes("i ctrl-d");
+        cx.assert_state("    axbc\ndeˇxf", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_insert_last_inserted(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇ\n", Mode::Normal);
+        cx.simulate_keystrokes("i h e l x backspace l o escape");
+        cx.assert_state("hellˇo\n", Mode::Normal);
+
+        cx.simulate_keystrokes("o ctrl-a");
+        cx.assert_state("hello\nhelloˇ\n", Mode::Insert);
+
+        cx.simulate_keystrokes("escape o ctrl-@");
+        cx.assert_state("hello\nhello\nhellˇo\n", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_o(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("hello ˇworld", Mode::Normal);
+        cx.simulate_keystrokes("i a b ctrl-o");
+        cx.assert_state("hello abˇworld", Mode::Normal);
+        cx.simulate_keystrokes("0");
+        cx.assert_state("ˇhello abworld", Mode::Insert);
+        cx.simulate_keystrokes("x");
+        cx.assert_state("xˇhello abworld", Mode::Insert);
+
+        // the cursor can be left past the end of the line
+        cx.simulate_keystrokes("ctrl-o $");
+        cx.assert_state("xhello abworldˇ", Mode::Insert);
+
+        // an operator waits for its motion
+        cx.set_state("one\ntwˇo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("i ctrl-o d");
+        assert_eq!(cx.mode(), Mode::Normal);
+        cx.simulate_keystrokes("d");
+        cx.assert_state("one\nˇthree", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_insert_ctrl_o_repeat(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇab\ncd", Mode::Normal);
+        cx.simulate_keystrokes("shift-a x ctrl-o 0 y escape");
+        cx.assert_state("ˇyabx\ncd", Mode::Normal);
+        cx.simulate_keystrokes("j .");
+        cx.assert_state("yabx\nˇycdx", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_paste_verbatim(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_typescript(cx).await;
+
+        // not reindented to the surrounding block, and `O` is just text
+        cx.write_to_clipboard(ClipboardItem::new_string("if (x) {\n    O\n}".into()));
+        cx.set_state("function a() {\n    ˇ\n}", Mode::Insert);
+        cx.run_until_parked();
+        cx.simulate_keystrokes("ctrl-shift-v");
+        cx.run_until_parked();
+        cx.assert_state("function a() {\n    if (x) {\n    O\n}ˇ\n}", Mode::Insert);
+
+        // no closing bracket is added
+        cx.write_to_clipboard(ClipboardItem::new_string("f(".into()));
+        cx.set_state("ˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-shift-v");
+        cx.assert_state("f(ˇ", Mode::Insert);
+
+        // text copied from several cursors goes back to one each
+        let selection = ClipboardSelection {
+            len: 3,
+            is_entire_line: false,
+            first_line_indent: 0,
+        };
+        cx.write_to_clipboard(ClipboardItem::new_string_with_json_metadata(
+            "one\ntwo".into(),
+            vec![selection.clone(), selection],
+        ));
+        cx.set_state("ˇ\n    ˇ", Mode::Insert);
+        cx.simulate_keystrokes("ctrl-shift-v");
+        cx.assert_state("oneˇ\n    twoˇ", Mode::Insert);
+    }
 }
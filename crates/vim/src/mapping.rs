@@ -0,0 +1,409 @@
+//! Mappings defined while Zed is running, with `:map` and its variants. Each
+//! mapping is bound in the keymap to [`RunMapping`], which queues the keys it
+//! maps to so that they're dispatched as though they were typed, much like
+//! vim's typeahead. Bindings can't be removed again, so an unmapped binding
+//! lets the built-in binding for its keys run instead.
+
+use anyhow::{anyhow, Result};
+use gpui::{impl_actions, KeyBinding, Keystroke, Modifiers, ViewContext, WindowContext};
+use serde::Deserialize;
+use settings::Settings;
+use util::ResultExt;
+use workspace::Workspace;
+
+use crate::{command::keystrokes_for_text, Vim, VimSettings};
+
+/// How many mappings may expand before the keys they've queued run out,
+/// before they're taken to be recursive (vim's `maxmapdepth`).
+const MAX_MAPPING_DEPTH: usize = 1000;
+
+/// The modes a mapping can apply in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+pub enum MapMode {
+    Normal,
+    Visual,
+    OperatorPending,
+    Insert,
+}
+
+impl MapMode {
+    fn context(self) -> &'static str {
+        match self {
+            Self::Normal => "vim_mode == normal",
+            Self::Visual => "vim_mode == visual",
+            Self::OperatorPending => "vim_mode == operator",
+            Self::Insert => "vim_mode == insert",
+        }
+    }
+}
+
+/// The keys a mapping expands to, and whether they may be mapped in turn.
+#[derive(Clone, Debug)]
+pub struct Mapping {
+    keys: Vec<Keystroke>,
+    recursive: bool,
+}
+
+/// Maps `lhs` to `rhs` in each of `modes` (`:map`, `:nnoremap` and so on).
+/// Both are written in vim's key notation, such as `<C-x>` or `<leader>w`.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Map {
+    modes: Vec<MapMode>,
+    lhs: String,
+    rhs: String,
+    recursive: bool,
+}
+
+/// Removes the mapping for `lhs` in each of `modes` (`:unmap` and so on).
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Unmap {
+    modes: Vec<MapMode>,
+    lhs: String,
+}
+
+/// Bound to the keys of the mapping for `keys` in `mode`, to run it.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct RunMapping {
+    mode: MapMode,
+    keys: String,
+}
+
+impl_actions!(vim, [Map, Unmap, RunMapping]);
+
+impl Map {
+    pub(crate) fn new(modes: &[MapMode], lhs: &str, rhs: &str, recursive: bool) -> Self {
+        Self {
+            modes: modes.to_vec(),
+            lhs: lhs.to_string(),
+            rhs: rhs.to_string(),
+            recursive,
+        }
+    }
+}
+
+impl Unmap {
+    pub(crate) fn new(modes: &[MapMode], lhs: &str) -> Self {
+        Self {
+            modes: modes.to_vec(),
+            lhs: lhs.to_string(),
+        }
+    }
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_, action: &Map, cx| {
+        let leader = VimSettings::get_global(cx).leader.clone();
+        let lhs = keys_source(&parse_key_notation(&action.lhs, &leader));
+        if lhs.is_empty() {
+            return;
+        }
+        let mapping = Mapping {
+            keys: parse_key_notation(&action.rhs, &leader),
+            recursive: action.recursive,
+        };
+        let bindings = Vim::update(cx, |vim, _| vim.add_mapping(&action.modes, lhs, mapping));
+        cx.bind_keys(bindings);
+    });
+
+    workspace.register_action(|workspace, action: &Unmap, cx| {
+        let leader = VimSettings::get_global(cx).leader.clone();
+        let lhs = keys_source(&parse_key_notation(&action.lhs, &leader));
+        let removed = Vim::update(cx, |vim, _| {
+            let mappings = &mut vim.workspace_state.mappings;
+            action.modes.iter().fold(false, |removed, &mode| {
+                mappings.remove(&(mode, lhs.clone())).is_some() || removed
+            })
+        });
+        if !removed {
+            workspace.show_error(&anyhow!("No such mapping: {}", action.lhs), cx);
+        }
+    });
+
+    workspace.register_action(|workspace, action: &RunMapping, cx| {
+        match Vim::update(cx, |vim, _| vim.queue_mapping(action)) {
+            Some(Ok(())) => run_typeahead(cx),
+            Some(Err(error)) => workspace.show_error(&error, cx),
+            None => cx.propagate(),
+        }
+    });
+}
+
+impl Vim {
+    /// Maps `lhs` in each of `modes`, returning the bindings that it needs.
+    fn add_mapping(&mut self, modes: &[MapMode], lhs: String, mapping: Mapping) -> Vec<KeyBinding> {
+        let mappings = &mut self.workspace_state.mappings;
+        let mut bindings = Vec::new();
+        for &mode in modes {
+            if mappings
+                .insert((mode, lhs.clone()), mapping.clone())
+                .is_some()
+            {
+                continue;
+            }
+            // Later bindings take precedence, so longer mappings starting
+            // with these keys are bound again to keep waiting for the rest.
+            let prefix = format!("{lhs} ");
+            let longer = mappings
+                .keys()
+                .filter(|(other_mode, keys)| *other_mode == mode && keys.starts_with(&prefix));
+            bindings.extend(
+                std::iter::once(&(mode, lhs.clone()))
+                    .chain(longer)
+                    .filter_map(|(mode, keys)| {
+                        let action = RunMapping {
+                            mode: *mode,
+                            keys: keys.clone(),
+                        };
+                        KeyBinding::load(keys, Box::new(action), Some(mode.context())).log_err()
+                    }),
+            );
+        }
+        bindings
+    }
+
+    /// Queues the keys that `action`'s mapping expands to ahead of any that
+    /// are already queued. Returns `None` if there is no mapping to run,
+    /// because it was unmapped or its keys came from a `noremap` mapping.
+    fn queue_mapping(&mut self, action: &RunMapping) -> Option<Result<()>> {
+        let state = &mut self.workspace_state;
+        if state.noremap_typeahead {
+            return None;
+        }
+        let mapping = state
+            .mappings
+            .get(&(action.mode, action.keys.clone()))?
+            .clone();
+
+        state.mapping_depth += 1;
+        if state.mapping_depth > MAX_MAPPING_DEPTH {
+            state.typeahead.clear();
+            return Some(Err(anyhow!("Recursive mapping: {}", action.keys)));
+        }
+        // As in vim, keys that start with the mapping's own aren't mapped
+        // again, so that `:map j jzz` doesn't recurse.
+        let lhs_len = action.keys.split(' ').count();
+        let starts_with_lhs = mapping
+            .keys
+            .get(..lhs_len)
+            .is_some_and(|keys| keys_source(keys) == action.keys);
+        for (ix, keystroke) in mapping.keys.into_iter().enumerate().rev() {
+            let remap = mapping.recursive && !(starts_with_lhs && ix < lhs_len);
+            state.typeahead.push_front((keystroke, remap));
+        }
+        Some(Ok(()))
+    }
+}
+
+/// Dispatches the queued keys one at a time. Mappings among them queue their
+/// own keys at the front, so this keeps going until they have all run.
+fn run_typeahead(cx: &mut WindowContext) {
+    cx.spawn(|mut cx| async move {
+        while let Some((keystroke, remap)) = cx.update(|cx| {
+            Vim::update(cx, |vim, _| {
+                let next = vim.workspace_state.typeahead.pop_front();
+                if next.is_none() {
+                    vim.workspace_state.mapping_depth = 0;
+                }
+                next
+            })
+        })? {
+            cx.update(|cx| {
+                Vim::update(cx, |vim, _| vim.workspace_state.noremap_typeahead = !remap);
+                cx.dispatch_keystroke(keystroke);
+                Vim::update(cx, |vim, _| vim.workspace_state.noremap_typeahead = false);
+            })?;
+        }
+        anyhow::Ok(())
+    })
+    .detach_and_log_err(cx);
+}
+
+/// Converts `keys`, in vim's key notation, into keystrokes. `<leader>` is
+/// replaced by `leader`, and anything in angle brackets that isn't the name
+/// of a key stands for itself, as in vim.
+pub(crate) fn parse_key_notation(keys: &str, leader: &str) -> Vec<Keystroke> {
+    let mut keystrokes = Vec::new();
+    let mut rest = keys;
+    while let Some(c) = rest.chars().next() {
+        if let Some((name, after)) = rest.strip_prefix('<').and_then(|rest| rest.split_once('>')) {
+            if let Some(special) = parse_special_key(name, leader) {
+                keystrokes.extend(special);
+                rest = after;
+                continue;
+            }
+        }
+        keystrokes.extend(keystrokes_for_text(&c.to_string()));
+        rest = &rest[c.len_utf8()..];
+    }
+    keystrokes
+}
+
+/// Parses the name of a key written in angle brackets, such as `CR` or
+/// `C-x`, with any of the `C-`, `S-`, `A-` (or `M-`) and `D-` modifiers.
+fn parse_special_key(name: &str, leader: &str) -> Option<Vec<Keystroke>> {
+    if name.eq_ignore_ascii_case("leader") {
+        return Some(keystrokes_for_text(leader));
+    }
+    if name.eq_ignore_ascii_case("nop") {
+        return Some(Vec::new());
+    }
+
+    let mut modifiers = Modifiers::default();
+    let mut name = name;
+    while let Some((modifier, rest)) = name
+        .split_once('-')
+        .filter(|(modifier, rest)| modifier.len() == 1 && !rest.is_empty())
+    {
+        match modifier.to_ascii_lowercase().as_str() {
+            "c" => modifiers.control = true,
+            "s" => modifiers.shift = true,
+            "a" | "m" => modifiers.alt = true,
+            "d" => modifiers.platform = true,
+            _ => return None,
+        }
+        name = rest;
+    }
+
+    let lowercase = name.to_ascii_lowercase();
+    let key: &str = match lowercase.as_str() {
+        "cr" | "enter" | "return" => "enter",
+        "esc" => "escape",
+        "bs" => "backspace",
+        "del" => "delete",
+        "lt" => "<",
+        "bar" => "|",
+        "bslash" => "\\",
+        "space" | "tab" | "backspace" | "delete" | "up" | "down" | "left" | "right" | "home"
+        | "end" | "pageup" | "pagedown" | "insert" => lowercase.as_str(),
+        key if key
+            .strip_prefix('f')
+            .and_then(|n| n.parse::<u8>().ok())
+            .is_some_and(|n| (1..=12).contains(&n)) =>
+        {
+            key
+        }
+        _ => {
+            let mut chars = name.chars();
+            let (Some(c), None) = (chars.next(), chars.next()) else {
+                return None;
+            };
+            if modifiers == Modifiers::default() {
+                return None;
+            }
+            // As in vim, `<C-X>` is the same as `<C-x>`.
+            modifiers.shift |= c.is_uppercase() && !modifiers.control;
+            return Some(vec![Keystroke {
+                modifiers,
+                key: c.to_lowercase().to_string(),
+                ime_key: None,
+            }]);
+        }
+    };
+    Some(vec![Keystroke {
+        modifiers,
+        key: key.to_string(),
+        ime_key: None,
+    }])
+}
+
+/// Writes `keystrokes` as they would be written in the keymap, which is how
+/// mappings are keyed.
+fn keys_source(keystrokes: &[Keystroke]) -> String {
+    keystrokes
+        .iter()
+        .map(|keystroke| {
+            let modifiers = &keystroke.modifiers;
+            let mut source = String::new();
+            for (held, name) in [
+                (modifiers.control, "ctrl-"),
+                (modifiers.alt, "alt-"),
+                (modifiers.shift, "shift-"),
+                (modifiers.platform, "cmd-"),
+                (modifiers.function, "fn-"),
+            ] {
+                if held {
+                    source.push_str(name);
+                }
+            }
+            source.push_str(&keystroke.key);
+            source
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod test {
+    use gpui::Keystroke;
+
+    use super::{keys_source, parse_key_notation};
+    use crate::{state::Mode, test::VimTestContext};
+
+    #[test]
+    fn test_key_notation() {
+        let keys = |notation| keys_source(&parse_key_notation(notation, "\\"));
+        assert_eq!(keys(":q<CR>"), ": q enter");
+        assert_eq!(keys("<leader>dd"), "\\ d d");
+        assert_eq!(keys("<C-x><c-W>"), "ctrl-x ctrl-w");
+        assert_eq!(keys("<S-Tab><A-j>"), "shift-tab alt-j");
+        assert_eq!(keys("Q<lt><Space>"), "shift-q < space");
+        assert_eq!(keys("<nope>"), "< n o p e >");
+        assert_eq!(keys("<Nop>"), "");
+        assert_eq!(
+            parse_key_notation("<leader>", " "),
+            vec![Keystroke::parse("space").unwrap()]
+        );
+    }
+
+    #[gpui::test]
+    async fn test_map_command(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("nmap Q :q<CR>");
+        cx.simulate_keystrokes("enter");
+        cx.simulate_keystrokes(": n e w enter");
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 2));
+        cx.simulate_keystrokes("shift-q");
+        cx.workspace(|workspace, cx| assert_eq!(workspace.items(cx).count(), 1));
+
+        cx.set_state("ˇa\nb", Mode::Normal);
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("nmap <leader>d dd");
+        cx.simulate_keystrokes("enter");
+        cx.simulate_keystrokes("\\ d");
+        cx.assert_state("ˇb", Mode::Normal);
+
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("inoremap jk <Esc>");
+        cx.simulate_keystrokes("enter");
+        cx.simulate_keystrokes("i x j k");
+        cx.assert_state("ˇxb", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_noremap(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        for command in ["nmap Y l", "nmap Z Yx", "nnoremap W Yx"] {
+            cx.simulate_keystrokes(":");
+            cx.simulate_input(command);
+            cx.simulate_keystrokes("enter");
+        }
+
+        // `Z` uses the mapping of `Y`, but `W` runs the built-in `Y`
+        cx.set_state("ˇabc", Mode::Normal);
+        cx.simulate_keystrokes("shift-z");
+        cx.assert_state("aˇc", Mode::Normal);
+        cx.set_state("ˇabc", Mode::Normal);
+        cx.simulate_keystrokes("shift-w");
+        cx.assert_state("ˇbc", Mode::Normal);
+
+        cx.simulate_keystrokes(":");
+        cx.simulate_input("unmap Y");
+        cx.simulate_keystrokes("enter");
+        cx.set_state("ˇabc", Mode::Normal);
+        cx.simulate_keystrokes("shift-z");
+        cx.assert_state("ˇbc", Mode::Normal);
+    }
+}
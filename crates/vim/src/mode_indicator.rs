@@ -1,14 +1,16 @@
-use gpui::{div, Element, Render, Subscription, ViewContext};
+use gpui::{div, AppContext, Element, Render, Subscription, ViewContext};
 use itertools::Itertools;
+use settings::{Settings, SettingsStore};
 use workspace::{item::ItemHandle, ui::prelude::*, StatusItemView};
 
-use crate::{state::Mode, Vim};
+use crate::{state::Mode, Vim, VimSettings};
 
 /// The ModeIndicator displays the current mode in the status bar.
 pub struct ModeIndicator {
     pub(crate) mode: Option<Mode>,
     pub(crate) operators: String,
     pending_keys: Option<String>,
+    status_message: Option<SharedString>,
     _subscriptions: Vec<Subscription>,
 }
 
@@ -17,6 +19,7 @@ impl ModeIndicator {
     pub fn new(cx: &mut ViewContext<Self>) -> Self {
         let _subscriptions = vec![
             cx.observe_global::<Vim>(|this, cx| this.update_mode(cx)),
+            cx.observe_global::<SettingsStore>(|_, cx| cx.notify()),
             cx.observe_pending_input(|this, cx| {
                 this.update_pending_keys(cx);
                 cx.notify();
@@ -27,6 +30,7 @@ impl ModeIndicator {
             mode: None,
             operators: "".to_string(),
             pending_keys: None,
+            status_message: None,
             _subscriptions,
         };
         this.update_mode(cx);
@@ -37,8 +41,10 @@ impl ModeIndicator {
         if let Some(vim) = self.vim(cx) {
             self.mode = Some(vim.state().mode);
             self.operators = self.current_operators_description(&vim);
+            self.status_message = vim.workspace_state.status_message.clone();
         } else {
             self.mode = None;
+            self.status_message = None;
         }
     }
 
@@ -55,6 +61,28 @@ impl ModeIndicator {
         }
     }
 
+    /// The text shown in the status bar. As with vim's `showmode`, the mode
+    /// is only named outside of normal mode.
+    pub(crate) fn label(&self, cx: &AppContext) -> String {
+        let mut parts = Vec::new();
+        if let Some(message) = self.status_message.as_ref() {
+            parts.push(message.to_string());
+        }
+        if let Some(mode) = self.mode.filter(|_| VimSettings::get_global(cx).showmode) {
+            parts.push(
+                self.pending_keys
+                    .as_ref()
+                    .unwrap_or(&self.operators)
+                    .clone(),
+            );
+            if mode != Mode::Normal {
+                parts.push(format!("-- {} --", mode));
+            }
+        }
+        parts.retain(|part| !part.is_empty());
+        parts.join(" ")
+    }
+
     fn vim<'a>(&self, cx: &'a mut ViewContext<Self>) -> Option<&'a Vim> {
         // In some tests Vim isn't enabled, so we use try_global.
         cx.try_global::<Vim>().filter(|vim| vim.enabled)
@@ -80,14 +108,12 @@ impl ModeIndicator {
 }
 
 impl Render for ModeIndicator {
-    fn render(&mut self, _: &mut ViewContext<Self>) -> impl IntoElement {
-        let Some(mode) = self.mode.as_ref() else {
+    fn render(&mut self, cx: &mut ViewContext<Self>) -> impl IntoElement {
+        if self.mode.is_none() {
             return div().into_any();
-        };
-
-        let pending = self.pending_keys.as_ref().unwrap_or(&self.operators);
+        }
 
-        Label::new(format!("{} -- {} --", pending, mode))
+        Label::new(self.label(cx))
             .size(LabelSize::Small)
             .line_height_style(LineHeightStyle::UiLabel)
             .into_any_element()
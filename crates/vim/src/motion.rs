@@ -1185,7 +1185,7 @@ pub(crate) fn next_word_end(
     point
 }
 
-fn previous_word_start(
+pub(crate) fn previous_word_start(
     map: &DisplaySnapshot,
     mut point: DisplayPoint,
     ignore_punctuation: bool,
@@ -1701,17 +1701,19 @@ fn window_top(
 
     if let Some(visible_rows) = text_layout_details.visible_rows {
         let bottom_row = first_visible_line.row().0 + visible_rows as u32;
-        let new_row = (first_visible_line.row().0 + (times as u32))
-            .min(bottom_row)
-            .min(map.max_point().row().0);
-        let new_col = point.column().min(map.line_len(first_visible_line.row()));
+        let new_row = DisplayRow(
+            (first_visible_line.row().0 + (times as u32))
+                .min(bottom_row)
+                .min(map.max_point().row().0),
+        );
+        let new_col = point.column().min(map.line_len(new_row));
 
-        let new_point = DisplayPoint::new(DisplayRow(new_row), new_col);
+        let new_point = DisplayPoint::new(new_row, new_col);
         (map.clip_point(new_point, Bias::Left), SelectionGoal::None)
     } else {
         let new_row =
             DisplayRow((first_visible_line.row().0 + (times as u32)).min(map.max_point().row().0));
-        let new_col = point.column().min(map.line_len(first_visible_line.row()));
+        let new_col = point.column().min(map.line_len(new_row));
 
         let new_point = DisplayPoint::new(new_row, new_col);
         (map.clip_point(new_point, Bias::Left), SelectionGoal::None)
@@ -1787,7 +1789,11 @@ pub fn coerce_punctuation(kind: CharKind, treat_punctuation_as_word: bool) -> Ch
 #[cfg(test)]
 mod test {
 
-    use crate::test::NeovimBackedTestContext;
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+    };
+    use gpui::point;
     use indoc::indoc;
 
     #[gpui::test]
@@ -1943,6 +1949,31 @@ mod test {
         cx.shared_state().await.assert_eq("one two thˇree four");
     }
 
+    #[gpui::test]
+    async fn test_comma_semicolon_counts(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇxa ya za wa").await;
+        cx.simulate_shared_keystrokes("f a").await;
+        cx.shared_state().await.assert_eq("xˇa ya za wa");
+        cx.simulate_shared_keystrokes("2 ;").await;
+        cx.shared_state().await.assert_eq("xa ya zˇa wa");
+        cx.simulate_shared_keystrokes(",").await;
+        cx.shared_state().await.assert_eq("xa yˇa za wa");
+
+        // `;` after `t` moves on from the character it stopped before
+        cx.set_shared_state("ˇxa ya za wa").await;
+        cx.simulate_shared_keystrokes("t a").await;
+        cx.shared_state().await.assert_eq("ˇxa ya za wa");
+        cx.simulate_shared_keystrokes(";").await;
+        cx.shared_state().await.assert_eq("xa ˇya za wa");
+        // but with a count the adjacent character is the first one found
+        cx.simulate_shared_keystrokes("2 ;").await;
+        cx.shared_state().await.assert_eq("xa ya ˇza wa");
+        cx.simulate_shared_keystrokes(",").await;
+        cx.shared_state().await.assert_eq("xa yaˇ za wa");
+    }
+
     #[gpui::test]
     async fn test_next_word_end_newline_last_char(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -2198,6 +2229,67 @@ mod test {
           "});
     }
 
+    fn scroll_to(cx: &mut VimTestContext, row: f32, keystrokes: &str) {
+        cx.update_editor(|editor, cx| editor.set_scroll_position(point(0., row), cx));
+        cx.simulate_keystrokes(keystrokes);
+    }
+
+    #[gpui::test]
+    async fn test_window_motions_in_viewport(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // 15 whole lines are visible, with the default scroll margin of 3
+        cx.set_scroll_height(15);
+
+        let lines = |cursor: u32, deleted: std::ops::Range<u32>| {
+            (0..40)
+                .filter(|row| !deleted.contains(row))
+                .map(|row| {
+                    if row == cursor {
+                        format!("liˇne {row}\n")
+                    } else {
+                        format!("line {row}\n")
+                    }
+                })
+                .collect::<String>()
+        };
+
+        // the scroll margin applies, unless a count is further from the edge
+        cx.set_state(&lines(15, 0..0), Mode::Normal);
+        cx.run_until_parked();
+        scroll_to(&mut cx, 10., "shift-h");
+        cx.assert_state(&lines(13, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "2 shift-h");
+        cx.assert_state(&lines(13, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "5 shift-h");
+        cx.assert_state(&lines(14, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "shift-m");
+        cx.assert_state(&lines(17, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "shift-l");
+        cx.assert_state(&lines(21, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "5 shift-l");
+        cx.assert_state(&lines(20, 0..0), Mode::Normal);
+
+        // as operator targets they're linewise
+        scroll_to(&mut cx, 10., "d shift-h");
+        cx.assert_state(&lines(21, 13..21), Mode::Normal);
+        cx.set_state(&lines(13, 0..0), Mode::Normal);
+        cx.run_until_parked();
+        scroll_to(&mut cx, 10., "d shift-l");
+        cx.assert_state(&lines(22, 13..22), Mode::Normal);
+
+        // a closed fold is one screen line
+        cx.set_state(&lines(11, 0..0), Mode::Normal);
+        cx.simulate_keystrokes("z f j j");
+        cx.run_until_parked();
+        scroll_to(&mut cx, 10., "shift-h");
+        cx.assert_state(&lines(15, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "shift-m");
+        cx.assert_state(&lines(19, 0..0), Mode::Normal);
+        scroll_to(&mut cx, 10., "shift-l");
+        cx.assert_state(&lines(23, 0..0), Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_previous_word_end(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
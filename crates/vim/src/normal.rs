@@ -1,9 +1,14 @@
 mod case;
 mod change;
-mod delete;
+pub(crate) mod character_info;
+pub(crate) mod delete;
+mod filter;
+mod fold;
+mod format;
 mod increment;
 mod indent;
 pub(crate) mod mark;
+pub(crate) mod operator_func;
 mod paste;
 pub(crate) mod repeat;
 mod scroll;
@@ -28,7 +33,10 @@ use editor::scroll::Autoscroll;
 use editor::Anchor;
 use editor::Bias;
 use editor::Editor;
-use editor::{display_map::ToDisplayPoint, movement};
+use editor::{
+    display_map::{DisplayRow, ToDisplayPoint},
+    movement, DisplayPoint,
+};
 use gpui::{actions, ViewContext, WindowContext};
 use language::{Point, SelectionGoal};
 use log::error;
@@ -39,7 +47,12 @@ use self::{
     case::{change_case, convert_to_lower_case, convert_to_upper_case},
     change::{change_motion, change_object},
     delete::{delete_motion, delete_object},
+    filter::{filter_motion, filter_object},
+    fold::{fold_motion, fold_object},
+    format::{format_motion, format_object},
     indent::{indent_motion, indent_object, IndentDirection},
+    operator_func::{operator_func_motion, operator_func_object},
+    repeat::block_to_end_of_line,
     toggle_comments::{toggle_comments_motion, toggle_comments_object},
     yank::{yank_motion, yank_object},
 };
@@ -65,8 +78,10 @@ actions!(
         ConvertToUpperCase,
         ConvertToLowerCase,
         JoinLines,
+        JoinLinesNoWhitespace,
         Indent,
         Outdent,
+        AutoIndent,
         ToggleComments,
         Undo,
         Redo,
@@ -131,32 +146,18 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
         })
     });
     workspace.register_action(|_: &mut Workspace, _: &JoinLines, cx| {
-        Vim::update(cx, |vim, cx| {
-            vim.record_current_action(cx);
-            let mut times = vim.take_count(cx).unwrap_or(1);
-            if vim.state().mode.is_visual() {
-                times = 1;
-            } else if times > 1 {
-                // 2J joins two lines together (same as J or 1J)
-                times -= 1;
-            }
-
-            vim.update_active_editor(cx, |_, editor, cx| {
-                editor.transact(cx, |editor, cx| {
-                    for _ in 0..times {
-                        editor.join_lines(&Default::default(), cx)
-                    }
-                })
-            });
-            if vim.state().mode.is_visual() {
-                vim.switch_mode(Mode::Normal, false, cx)
-            }
-        });
+        join_lines(true, cx);
+    });
+    workspace.register_action(|_: &mut Workspace, _: &JoinLinesNoWhitespace, cx| {
+        join_lines(false, cx);
     });
 
     workspace.register_action(|_: &mut Workspace, _: &Indent, cx| {
         Vim::update(cx, |vim, cx| {
-            vim.record_current_action(cx);
+            // In insert mode (as `ctrl-t`) this is part of the insertion's recording.
+            if vim.state().mode != Mode::Insert {
+                vim.record_current_action(cx);
+            }
             let count = vim.take_count(cx).unwrap_or(1);
             vim.update_active_editor(cx, |_, editor, cx| {
                 editor.transact(cx, |editor, cx| {
@@ -175,7 +176,10 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
 
     workspace.register_action(|_: &mut Workspace, _: &Outdent, cx| {
         Vim::update(cx, |vim, cx| {
-            vim.record_current_action(cx);
+            // In insert mode (as `ctrl-d`) this is part of the insertion's recording.
+            if vim.state().mode != Mode::Insert {
+                vim.record_current_action(cx);
+            }
             let count = vim.take_count(cx).unwrap_or(1);
             vim.update_active_editor(cx, |_, editor, cx| {
                 editor.transact(cx, |editor, cx| {
@@ -213,12 +217,33 @@ pub(crate) fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace
         })
     });
 
+    workspace.register_action(|_: &mut Workspace, _: &AutoIndent, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.record_current_action(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                editor.transact(cx, |editor, cx| {
+                    let mut original_positions = save_selection_starts(editor, cx);
+                    editor.autoindent(&Default::default(), cx);
+                    restore_selection_cursors(editor, cx, &mut original_positions);
+                });
+            });
+            if vim.state().mode.is_visual() {
+                vim.switch_mode(Mode::Normal, false, cx)
+            }
+        });
+    });
+
     paste::register(workspace, cx);
     repeat::register(workspace, cx);
     scroll::register(workspace, cx);
     search::register(workspace, cx);
     substitute::register(workspace, cx);
     increment::register(workspace, cx);
+    character_info::register(workspace, cx);
+    format::register(workspace, cx);
+    filter::register(workspace, cx);
+    fold::register(workspace, cx);
+    operator_func::register(workspace, cx);
 }
 
 pub fn normal_motion(
@@ -228,6 +253,12 @@ pub fn normal_motion(
     cx: &mut WindowContext,
 ) {
     Vim::update(cx, |vim, cx| {
+        if let Some(operator) = operator.as_ref() {
+            if !motion_moves(vim, &motion, operator, times, cx) {
+                vim.stop_recording();
+                return;
+            }
+        }
         match operator {
             None => move_cursor(vim, motion, times, cx),
             Some(Operator::Change) => change_motion(vim, motion, times, cx),
@@ -236,6 +267,9 @@ pub fn normal_motion(
             Some(Operator::AddSurrounds { target: None }) => {}
             Some(Operator::Indent) => indent_motion(vim, motion, times, IndentDirection::In, cx),
             Some(Operator::Outdent) => indent_motion(vim, motion, times, IndentDirection::Out, cx),
+            Some(Operator::AutoIndent) => {
+                indent_motion(vim, motion, times, IndentDirection::Auto, cx)
+            }
             Some(Operator::Lowercase) => {
                 change_case_motion(vim, motion, times, CaseTarget::Lowercase, cx)
             }
@@ -246,6 +280,12 @@ pub fn normal_motion(
                 change_case_motion(vim, motion, times, CaseTarget::OppositeCase, cx)
             }
             Some(Operator::ToggleComments) => toggle_comments_motion(vim, motion, times, cx),
+            Some(Operator::Format { keep_cursor }) => {
+                format_motion(vim, motion, times, keep_cursor, cx)
+            }
+            Some(Operator::Filter) => filter_motion(vim, motion, times, cx),
+            Some(Operator::CreateFold) => fold_motion(vim, motion, times, cx),
+            Some(Operator::OperatorFunc) => operator_func_motion(vim, motion, times, cx),
             Some(operator) => {
                 // Can't do anything for text objects, Ignoring
                 error!("Unexpected normal mode motion operator: {:?}", operator)
@@ -254,35 +294,91 @@ pub fn normal_motion(
     });
 }
 
+/// Whether `motion` moves any of the cursors. As in vim, an operator does
+/// nothing, leaving the registers alone, if its motion can't move. The
+/// exception is `c` with a motion within the line, which enters insert mode
+/// even where there is nothing to change, as `cl` does on an empty line.
+fn motion_moves(
+    vim: &mut Vim,
+    motion: &Motion,
+    operator: &Operator,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) -> bool {
+    if operator == &Operator::Change
+        && matches!(
+            motion,
+            Motion::Left
+                | Motion::Right
+                | Motion::EndOfLine { .. }
+                | Motion::Backspace
+                | Motion::StartOfLine { .. }
+                | Motion::NextWordStart { .. }
+                | Motion::NextSubwordStart { .. }
+        )
+    {
+        return true;
+    }
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        // Operators can reach past the last character of the line, so that
+        // `dl` deletes it.
+        editor.set_clip_at_line_ends(false, cx);
+        let (map, selections) = editor.selections.all_adjusted_display(cx);
+        let moves = selections.into_iter().any(|selection| {
+            motion
+                .range(&map, selection, times, false, &text_layout_details)
+                .is_some()
+        });
+        editor.set_clip_at_line_ends(vim.state().clip_at_line_ends(), cx);
+        moves
+    })
+    .unwrap_or(true)
+}
+
 pub fn normal_object(object: Object, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         let mut waiting_operator: Option<Operator> = None;
+        let times = vim.take_count(cx);
         match vim.maybe_pop_operator() {
             Some(Operator::Object { around }) => match vim.maybe_pop_operator() {
-                Some(Operator::Change) => change_object(vim, object, around, cx),
-                Some(Operator::Delete) => delete_object(vim, object, around, cx),
-                Some(Operator::Yank) => yank_object(vim, object, around, cx),
+                Some(Operator::Change) => change_object(vim, object, around, times, cx),
+                Some(Operator::Delete) => delete_object(vim, object, around, times, cx),
+                Some(Operator::Yank) => yank_object(vim, object, around, times, cx),
                 Some(Operator::Indent) => {
-                    indent_object(vim, object, around, IndentDirection::In, cx)
+                    indent_object(vim, object, around, times, IndentDirection::In, cx)
                 }
                 Some(Operator::Outdent) => {
-                    indent_object(vim, object, around, IndentDirection::Out, cx)
+                    indent_object(vim, object, around, times, IndentDirection::Out, cx)
+                }
+                Some(Operator::AutoIndent) => {
+                    indent_object(vim, object, around, times, IndentDirection::Auto, cx)
                 }
                 Some(Operator::Lowercase) => {
-                    change_case_object(vim, object, around, CaseTarget::Lowercase, cx)
+                    change_case_object(vim, object, around, times, CaseTarget::Lowercase, cx)
                 }
                 Some(Operator::Uppercase) => {
-                    change_case_object(vim, object, around, CaseTarget::Uppercase, cx)
+                    change_case_object(vim, object, around, times, CaseTarget::Uppercase, cx)
                 }
                 Some(Operator::OppositeCase) => {
-                    change_case_object(vim, object, around, CaseTarget::OppositeCase, cx)
+                    change_case_object(vim, object, around, times, CaseTarget::OppositeCase, cx)
                 }
                 Some(Operator::AddSurrounds { target: None }) => {
                     waiting_operator = Some(Operator::AddSurrounds {
                         target: Some(SurroundsType::Object(object)),
                     });
                 }
-                Some(Operator::ToggleComments) => toggle_comments_object(vim, object, around, cx),
+                Some(Operator::ToggleComments) => {
+                    toggle_comments_object(vim, object, around, times, cx)
+                }
+                Some(Operator::Format { keep_cursor }) => {
+                    format_object(vim, object, around, times, keep_cursor, cx)
+                }
+                Some(Operator::Filter) => filter_object(vim, object, around, times, cx),
+                Some(Operator::CreateFold) => fold_object(vim, object, around, times, cx),
+                Some(Operator::OperatorFunc) => {
+                    operator_func_object(vim, object, around, times, cx)
+                }
                 _ => {
                     // Can't do anything for namespace operators. Ignoring
                 }
@@ -329,6 +425,10 @@ pub(crate) fn move_cursor(
 fn insert_after(_: &mut Workspace, _: &InsertAfter, cx: &mut ViewContext<Workspace>) {
     Vim::update(cx, |vim, cx| {
         vim.start_recording(cx);
+        if vim.state().mode == Mode::VisualBlock {
+            append_to_block(vim, cx);
+            return;
+        }
         vim.switch_mode(Mode::Insert, false, cx);
         vim.update_active_editor(cx, |_, editor, cx| {
             editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
@@ -341,10 +441,81 @@ fn insert_after(_: &mut Workspace, _: &InsertAfter, cx: &mut ViewContext<Workspa
 fn insert_before(_: &mut Workspace, _: &InsertBefore, cx: &mut ViewContext<Workspace>) {
     Vim::update(cx, |vim, cx| {
         vim.start_recording(cx);
+        // Each row of the block gets a cursor at its left edge. As in vim,
+        // lines that end before the block or right at its edge are left alone,
+        // apart from the first.
+        if vim.state().mode == Mode::VisualBlock {
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let (map, selections) = editor.selections.all_display(cx);
+                let cursors = selections
+                    .iter()
+                    .enumerate()
+                    .filter(|(ix, selection)| {
+                        *ix == 0 || selection.start.column() < map.line_len(selection.start.row())
+                    })
+                    .map(|(_, selection)| {
+                        let point = selection.start.to_point(&map);
+                        point..point
+                    })
+                    .collect::<Vec<_>>();
+                editor.change_selections(None, cx, |s| s.select_ranges(cursors));
+            });
+        }
         vim.switch_mode(Mode::Insert, false, cx);
     });
 }
 
+/// `A` in visual block mode puts a cursor after the block on every line it
+/// spans. Lines too short to reach the right edge of the block are padded out
+/// to it with spaces, unless the block was extended with `$`, in which case
+/// each line is appended to at its own end.
+fn append_to_block(vim: &mut Vim, cx: &mut WindowContext) {
+    let Some((rows, right_edge)) = vim
+        .update_active_editor(cx, |_, editor, cx| {
+            let to_end_of_line = block_to_end_of_line(editor);
+            let (_, selections) = editor.selections.all_display(cx);
+            let first_row = selections.iter().map(|s| s.start.row()).min()?;
+            let last_row = selections.iter().map(|s| s.end.row()).max()?;
+            let right_edge = selections.iter().map(|s| s.end.column()).max()?;
+            Some((
+                first_row.0..=last_row.0,
+                (!to_end_of_line).then_some(right_edge),
+            ))
+        })
+        .flatten()
+    else {
+        return;
+    };
+
+    vim.switch_mode(Mode::Insert, false, cx);
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let (map, _) = editor.selections.all_display(cx);
+        let mut edits = Vec::new();
+        let mut cursors = Vec::new();
+        for row in rows {
+            let row = DisplayRow(row);
+            let line_len = map.line_len(row);
+            let column = right_edge.unwrap_or(line_len);
+            let point = if line_len < column {
+                let line_end = DisplayPoint::new(row, line_len).to_point(&map);
+                edits.push((line_end..line_end, " ".repeat((column - line_len) as usize)));
+                line_end
+            } else {
+                map.clip_point(DisplayPoint::new(row, column), Bias::Left)
+                    .to_point(&map)
+            };
+            let anchor = map.buffer_snapshot.anchor_after(point);
+            cursors.push(anchor..anchor);
+        }
+        editor.transact(cx, |editor, cx| {
+            editor.edit(edits, cx);
+            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                s.select_anchor_ranges(cursors)
+            });
+        });
+    });
+}
+
 fn insert_first_non_whitespace(
     _: &mut Workspace,
     _: &InsertFirstNonWhitespace,
@@ -505,6 +676,71 @@ fn toggle_comments(_: &mut Workspace, _: &ToggleComments, cx: &mut ViewContext<W
     });
 }
 
+/// `J`, or `gJ` which joins the lines as they are without adding or removing
+/// any whitespace.
+fn join_lines(insert_whitespace: bool, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        vim.record_current_action(cx);
+        let mut times = vim.take_count(cx).unwrap_or(1);
+        if vim.state().mode.is_visual() {
+            times = 1;
+        } else if times > 1 {
+            // 2J joins two lines together (same as J or 1J)
+            times -= 1;
+        }
+
+        vim.update_active_editor(cx, |_, editor, cx| {
+            editor.transact(cx, |editor, cx| {
+                for _ in 0..times {
+                    if insert_whitespace {
+                        editor.join_lines(&Default::default(), cx)
+                    } else {
+                        join_lines_literally(editor, cx)
+                    }
+                }
+            })
+        });
+        if vim.state().mode.is_visual() {
+            vim.switch_mode(Mode::Normal, false, cx)
+        }
+    });
+}
+
+/// Removes the line breaks after each line of the selections (or just the
+/// current line), leaving the cursor where the last two lines were joined.
+fn join_lines_literally(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let max_row = snapshot.max_point().row;
+    let mut edits = Vec::new();
+    let mut cursor_positions = Vec::new();
+    for selection in editor.selections.all::<Point>(cx) {
+        let end_row = if selection.start.row == selection.end.row {
+            selection.start.row + 1
+        } else {
+            selection.end.row
+        };
+        let end_row = end_row.min(max_row);
+        if end_row == selection.start.row {
+            continue;
+        }
+        for row in selection.start.row..end_row {
+            let end_of_line = Point::new(row, snapshot.line_len(MultiBufferRow(row)));
+            edits.push((end_of_line..Point::new(row + 1, 0), ""));
+        }
+        let last_join = Point::new(end_row - 1, snapshot.line_len(MultiBufferRow(end_row - 1)));
+        let anchor = snapshot.anchor_before(last_join);
+        cursor_positions.push(anchor..anchor);
+    }
+    if edits.is_empty() {
+        return;
+    }
+
+    editor.edit(edits, cx);
+    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+        s.select_anchor_ranges(cursor_positions)
+    });
+}
+
 fn save_selection_starts(editor: &Editor, cx: &mut ViewContext<Editor>) -> HashMap<usize, Anchor> {
     let (map, selections) = editor.selections.all_display(cx);
     selections
@@ -1494,4 +1730,33 @@ mod test {
         cx.simulate_shared_keystrokes("2 0 r - ").await;
         cx.shared_state().await.assert_eq("ˇhello world\n");
     }
+
+    #[gpui::test]
+    async fn test_operator_with_motion_that_cant_move(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the operators do nothing, and the register keeps what was yanked
+        cx.set_state("abc\nˇdef", Mode::Normal);
+        cx.simulate_keystrokes("y w");
+        for keystrokes in ["d j", "c j", "d h", "y j", "d f z"] {
+            cx.simulate_keystrokes(keystrokes);
+            cx.assert_state("abc\nˇdef", Mode::Normal);
+        }
+        cx.simulate_keystrokes("shift-p");
+        cx.assert_state("abc\ndeˇfdef", Mode::Normal);
+
+        cx.set_state("abc\nˇ", Mode::Normal);
+        cx.simulate_keystrokes("d w");
+        cx.assert_state("abc\nˇ", Mode::Normal);
+
+        // `l` reaches the last character of the line
+        cx.set_state("abˇc", Mode::Normal);
+        cx.simulate_keystrokes("d l");
+        cx.assert_state("aˇb", Mode::Normal);
+
+        // `c` with a motion in the line still starts inserting
+        cx.set_state("abc\nˇ\ndef", Mode::Normal);
+        cx.simulate_keystrokes("c l");
+        cx.assert_state("abc\nˇ\ndef", Mode::Insert);
+    }
 }
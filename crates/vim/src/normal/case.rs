@@ -60,20 +60,23 @@ pub fn change_case_object(
     vim: &mut Vim,
     object: Object,
     around: bool,
+    times: Option<usize>,
     mode: CaseTarget,
     cx: &mut WindowContext,
 ) {
     vim.stop_recording();
-    vim.update_active_editor(cx, |_, editor, cx| {
+    vim.update_active_editor(cx, |vim, editor, cx| {
         editor.transact(cx, |editor, cx| {
             let mut original_positions: HashMap<_, _> = Default::default();
-            editor.change_selections(None, cx, |s| {
-                s.move_with(|map, selection| {
-                    object.expand_selection(map, selection, around);
-                    original_positions.insert(
-                        selection.id,
-                        map.display_point_to_anchor(selection.start, Bias::Left),
-                    );
+            vim.with_object_cache(|cache| {
+                editor.change_selections(None, cx, |s| {
+                    s.move_with(|map, selection| {
+                        object.expand_selection(map, selection, around, times, cache);
+                        original_positions.insert(
+                            selection.id,
+                            map.display_point_to_anchor(selection.start, Bias::Left),
+                        );
+                    });
                 });
             });
             match mode {
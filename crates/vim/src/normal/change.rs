@@ -12,7 +12,8 @@ use editor::{
     Bias, DisplayPoint,
 };
 use gpui::WindowContext;
-use language::{char_kind, CharKind, Selection};
+use language::{char_kind, CharKind, Point, Selection};
+use multi_buffer::MultiBufferRow;
 
 pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
     // Some motions ignore failure when switching to normal mode
@@ -81,15 +82,29 @@ pub fn change_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
     }
 }
 
-pub fn change_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+pub fn change_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
     let mut objects_found = false;
     vim.update_active_editor(cx, |vim, editor, cx| {
         // We are swapping to insert mode anyway. Just set the line end clipping behavior now
         editor.set_clip_at_line_ends(false, cx);
         editor.transact(cx, |editor, cx| {
-            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                s.move_with(|map, selection| {
-                    objects_found |= object.expand_selection(map, selection, around);
+            vim.with_object_cache(|cache| {
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.move_with(|map, selection| {
+                        if !object.expand_selection(map, selection, around, times, cache) {
+                            return;
+                        }
+                        objects_found = true;
+                        if object.covers_whole_lines(map, selection, around) {
+                            keep_inner_line(map, selection);
+                        }
+                    });
                 });
             });
             if objects_found {
@@ -106,6 +121,21 @@ pub fn change_object(vim: &mut Vim, object: Object, around: bool, cx: &mut Windo
     }
 }
 
+/// A multi-line inner bracket object covers whole lines, which `di{` deletes
+/// entirely. `ci{` leaves one line behind to type on instead, keeping the
+/// indentation of the first line that was in the block.
+fn keep_inner_line(map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>) {
+    let start = selection.start.to_point(map);
+    let end = selection.end.to_point(map);
+    let indent = map
+        .buffer_snapshot
+        .indent_size_for_line(MultiBufferRow(start.row));
+    let last_row = MultiBufferRow(end.row - 1);
+    selection.start = Point::new(start.row, indent.len).to_display_point(map);
+    selection.end =
+        Point::new(last_row.0, map.buffer_snapshot.line_len(last_row)).to_display_point(map);
+}
+
 // From the docs https://vimdoc.sourceforge.net/htmldoc/motion.html
 // Special case: "cw" and "cW" are treated like "ce" and "cE" if the cursor is
 // on a non-blank.  This is because "cw" is interpreted as change-word, and a
@@ -0,0 +1,128 @@
+use gpui::{actions, ViewContext, WindowContext};
+use itertools::Itertools;
+use language::Point;
+use multi_buffer::MultiBufferRow;
+use unicode_segmentation::UnicodeSegmentation;
+use workspace::Workspace;
+
+use crate::Vim;
+
+actions!(vim, [ShowCharacterInfo, ShowUtf8Bytes]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &ShowCharacterInfo, cx| {
+        show_character(cx, character_info)
+    });
+    workspace
+        .register_action(|_: &mut Workspace, _: &ShowUtf8Bytes, cx| show_character(cx, utf8_bytes));
+}
+
+/// Shows a description of the character under the cursor (including any
+/// combining characters that follow it) in the status line, or `NUL` on an
+/// empty line as vim does.
+fn show_character(cx: &mut WindowContext, describe: fn(&str) -> String) {
+    Vim::update(cx, |vim, cx| {
+        vim.take_count(cx);
+        let grapheme = vim.update_active_editor(cx, |_, editor, cx| {
+            let snapshot = editor.buffer().read(cx).snapshot(cx);
+            let head = editor.selections.newest::<Point>(cx).head();
+            let line_end = Point::new(head.row, snapshot.line_len(MultiBufferRow(head.row)));
+            let text = snapshot.text_for_range(head..line_end).collect::<String>();
+            text.graphemes(true).next().map(str::to_string)
+        });
+        let Some(grapheme) = grapheme else {
+            return;
+        };
+        let message = grapheme.map_or("NUL".to_string(), |grapheme| describe(&grapheme));
+        vim.set_status_message(message);
+    })
+}
+
+/// The code point of each character as `ga` shows it, like
+/// `<é> 233, Hex 00e9, Octal 351`.
+fn character_info(grapheme: &str) -> String {
+    grapheme
+        .chars()
+        .enumerate()
+        .map(|(ix, ch)| {
+            let code = ch as u32;
+            let shown = match code {
+                0x00..=0x1f => format!("^{}", char::from_u32(code + 0x40).unwrap()),
+                0x7f => "^?".to_string(),
+                // Combining characters are shown after a space to give them
+                // something to combine with.
+                _ if ix > 0 => format!(" {ch}"),
+                _ => ch.to_string(),
+            };
+            let (hex, octal) = match code {
+                0x00..=0x7f => (format!("{code:02x}"), format!("{code:03o}")),
+                0x80..=0xffff => (format!("{code:04x}"), format!("{code:o}")),
+                _ => (format!("{code:08x}"), format!("{code:o}")),
+            };
+            format!("<{shown}> {code}, Hex {hex}, Octal {octal}")
+        })
+        .join(" ")
+}
+
+/// The UTF-8 encoding of each character as `g8` shows it, like `c3 a9`, with
+/// any combining characters after a `+`.
+fn utf8_bytes(grapheme: &str) -> String {
+    grapheme
+        .chars()
+        .map(|ch| {
+            ch.to_string()
+                .bytes()
+                .map(|byte| format!("{byte:02x}"))
+                .join(" ")
+        })
+        .join(" + ")
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{state::Mode, test::VimTestContext, Vim};
+
+    #[gpui::test]
+    async fn test_character_info(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let status_message = |cx: &mut VimTestContext| {
+            cx.update(|cx| Vim::read(cx).workspace_state.status_message.clone())
+        };
+
+        cx.set_state("ˇa é 😀 e\u{301}\n", Mode::Normal);
+        cx.simulate_keystrokes("g a");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("<a> 97, Hex 61, Octal 141".into())
+        );
+        cx.simulate_keystrokes("g 8");
+        assert_eq!(status_message(&mut cx), Some("61".into()));
+
+        cx.simulate_keystrokes("w g a");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("<é> 233, Hex 00e9, Octal 351".into())
+        );
+        cx.simulate_keystrokes("g 8");
+        assert_eq!(status_message(&mut cx), Some("c3 a9".into()));
+
+        cx.simulate_keystrokes("w g a");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("<😀> 128512, Hex 0001f600, Octal 373000".into())
+        );
+        cx.simulate_keystrokes("g 8");
+        assert_eq!(status_message(&mut cx), Some("f0 9f 98 80".into()));
+
+        cx.simulate_keystrokes("w g a");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("<e> 101, Hex 65, Octal 145 < \u{301}> 769, Hex 0301, Octal 1401".into())
+        );
+        cx.simulate_keystrokes("g 8");
+        assert_eq!(status_message(&mut cx), Some("65 + cc 81".into()));
+
+        cx.simulate_keystrokes("j g a");
+        assert_eq!(status_message(&mut cx), Some("NUL".into()));
+    }
+}
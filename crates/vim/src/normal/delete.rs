@@ -1,11 +1,16 @@
-use crate::{motion::Motion, normal::yank::copy_selections_content, object::Object, Vim};
+use crate::{
+    motion::{first_non_whitespace, Motion},
+    normal::yank::copy_selections_content,
+    object::Object,
+    Vim,
+};
 use collections::{HashMap, HashSet};
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     scroll::Autoscroll,
-    Bias, DisplayPoint,
+    Bias, DisplayPoint, Editor,
 };
-use gpui::WindowContext;
+use gpui::{ViewContext, WindowContext};
 use language::{Point, Selection};
 use multi_buffer::MultiBufferRow;
 
@@ -45,7 +50,7 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
                 });
             });
             copy_selections_content(vim, editor, motion.linewise(), cx);
-            editor.insert("", cx);
+            delete_selections(vim, editor, cx);
 
             // Fixup cursor position after the deletion
             editor.set_clip_at_line_ends(true, cx);
@@ -65,7 +70,13 @@ pub fn delete_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &m
     });
 }
 
-pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+pub fn delete_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
     vim.stop_recording();
     vim.update_active_editor(cx, |vim, editor, cx| {
         editor.transact(cx, |editor, cx| {
@@ -73,59 +84,76 @@ pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut Windo
             // Emulates behavior in vim where if we expanded backwards to include a newline
             // the cursor gets set back to the start of the line
             let mut should_move_to_start: HashSet<_> = Default::default();
-            editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                s.move_with(|map, selection| {
-                    object.expand_selection(map, selection, around);
-                    let offset_range = selection.map(|p| p.to_offset(map, Bias::Left)).range();
-                    let mut move_selection_start_to_previous_line =
-                        |map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>| {
-                            let start = selection.start.to_offset(map, Bias::Left);
-                            if selection.start.row().0 > 0 {
-                                should_move_to_start.insert(selection.id);
-                                selection.start = (start - '\n'.len_utf8()).to_display_point(map);
+            let mut whole_lines: HashSet<_> = Default::default();
+            vim.with_object_cache(|cache| {
+                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                    s.move_with(|map, selection| {
+                        object.expand_selection(map, selection, around, times, cache);
+                        if object.covers_whole_lines(map, selection, around) {
+                            whole_lines.insert(selection.id);
+                        }
+                        let offset_range = selection.map(|p| p.to_offset(map, Bias::Left)).range();
+                        let mut move_selection_start_to_previous_line =
+                            |map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>| {
+                                let start = selection.start.to_offset(map, Bias::Left);
+                                if selection.start.row().0 > 0 {
+                                    should_move_to_start.insert(selection.id);
+                                    selection.start =
+                                        (start - '\n'.len_utf8()).to_display_point(map);
+                                }
+                            };
+                        let range = selection.start.to_offset(map, Bias::Left)
+                            ..selection.end.to_offset(map, Bias::Right);
+                        let contains_only_newlines = map
+                            .buffer_chars_at(range.start)
+                            .take_while(|(_, p)| p < &range.end)
+                            .all(|(char, _)| char == '\n')
+                            && !offset_range.is_empty();
+                        let end_at_newline = map
+                            .buffer_chars_at(range.end)
+                            .next()
+                            .map(|(c, _)| c == '\n')
+                            .unwrap_or(false);
+
+                        // If expanded range contains only newlines and
+                        // the object is around or sentence, expand to include a newline
+                        // at the end or start
+                        if (around || object == Object::Sentence) && contains_only_newlines {
+                            if end_at_newline {
+                                move_selection_end_to_next_line(map, selection);
+                            } else {
+                                move_selection_start_to_previous_line(map, selection);
                             }
-                        };
-                    let range = selection.start.to_offset(map, Bias::Left)
-                        ..selection.end.to_offset(map, Bias::Right);
-                    let contains_only_newlines = map
-                        .buffer_chars_at(range.start)
-                        .take_while(|(_, p)| p < &range.end)
-                        .all(|(char, _)| char == '\n')
-                        && !offset_range.is_empty();
-                    let end_at_newline = map
-                        .buffer_chars_at(range.end)
-                        .next()
-                        .map(|(c, _)| c == '\n')
-                        .unwrap_or(false);
-
-                    // If expanded range contains only newlines and
-                    // the object is around or sentence, expand to include a newline
-                    // at the end or start
-                    if (around || object == Object::Sentence) && contains_only_newlines {
-                        if end_at_newline {
-                            move_selection_end_to_next_line(map, selection);
-                        } else {
-                            move_selection_start_to_previous_line(map, selection);
                         }
-                    }
 
-                    // Does post-processing for the trailing newline and EOF
-                    // when not cancelled.
-                    let cancelled = around && selection.start == selection.end;
-                    if object == Object::Paragraph && !cancelled {
-                        // EOF check should be done before including a trailing newline.
-                        if ends_at_eof(map, selection) {
-                            move_selection_start_to_previous_line(map, selection);
-                        }
+                        // Does post-processing for the trailing newline and EOF
+                        // when not cancelled.
+                        let cancelled = around && selection.start == selection.end;
+                        if matches!(
+                            object,
+                            Object::Paragraph | Object::IndentObj { .. } | Object::Section
+                        ) && !cancelled
+                        {
+                            // EOF check should be done before including a trailing newline.
+                            if ends_at_eof(map, selection) {
+                                move_selection_start_to_previous_line(map, selection);
+                            }
 
-                        if end_at_newline {
-                            move_selection_end_to_next_line(map, selection);
+                            if end_at_newline {
+                                move_selection_end_to_next_line(map, selection);
+                            }
                         }
-                    }
+                    });
                 });
             });
-            copy_selections_content(vim, editor, false, cx);
-            editor.insert("", cx);
+            // The register is only linewise if every selection was.
+            let linewise = editor
+                .selections
+                .all::<usize>(cx)
+                .iter()
+                .all(|selection| whole_lines.contains(&selection.id));
+            copy_selections_content(vim, editor, linewise, cx);
+            delete_selections(vim, editor, cx);
 
             // Fixup cursor position after the deletion
             editor.set_clip_at_line_ends(true, cx);
@@ -134,6 +162,8 @@ pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut Windo
                     let mut cursor = selection.head();
                     if should_move_to_start.contains(&selection.id) {
                         *cursor.column_mut() = 0;
+                    } else if whole_lines.contains(&selection.id) {
+                        cursor = first_non_whitespace(map, false, cursor);
                     }
                     cursor = map.clip_point(cursor, Bias::Left);
                     selection.collapse_to(cursor, selection.goal)
@@ -143,6 +173,15 @@ pub fn delete_object(vim: &mut Vim, object: Object, around: bool, cx: &mut Windo
     });
 }
 
+/// Deletes the selected text, reporting "N fewer lines" as vim does when more
+/// lines than the `report` setting are removed.
+pub(crate) fn delete_selections(vim: &mut Vim, editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let line_count = editor.buffer().read(cx).snapshot(cx).max_point().row;
+    editor.insert("", cx);
+    let removed = line_count.saturating_sub(editor.buffer().read(cx).snapshot(cx).max_point().row);
+    vim.report(removed, format!("{} fewer lines", removed), cx);
+}
+
 fn move_selection_end_to_next_line(map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>) {
     let end = selection.end.to_offset(map, Bias::Left);
     selection.end = (end + '\n'.len_utf8()).to_display_point(map);
@@ -155,10 +194,12 @@ fn ends_at_eof(map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>) -
 #[cfg(test)]
 mod test {
     use indoc::indoc;
+    use settings::SettingsStore;
 
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim, VimSettings,
     };
 
     #[gpui::test]
@@ -589,4 +630,89 @@ mod test {
         cx.simulate("d t x", "ˇax").await.assert_matches();
         cx.simulate("d t x", "aˇx").await.assert_matches();
     }
+
+    #[gpui::test]
+    async fn test_delete_reports_fewer_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.report = Some(2));
+        });
+        let status_message = |cx: &mut VimTestContext| {
+            cx.update(|cx| Vim::read(cx).workspace_state.status_message.clone())
+        };
+        let text = indoc! {"
+            ˇone
+            two
+            three
+            four
+            five
+            six
+            seven"};
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes("d d");
+        assert_eq!(status_message(&mut cx), None);
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes("5 d d");
+        assert_eq!(status_message(&mut cx), Some("5 fewer lines".into()));
+        cx.assert_state(
+            indoc! {"
+            ˇsix
+            seven"},
+            Mode::Normal,
+        );
+        // The message is cleared by the next keystroke.
+        cx.simulate_keystrokes("j");
+        assert_eq!(status_message(&mut cx), None);
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes("shift-v 2 j d");
+        assert_eq!(status_message(&mut cx), Some("3 fewer lines".into()));
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes(": 2 , 5 d enter");
+        assert_eq!(status_message(&mut cx), Some("4 fewer lines".into()));
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes("3 y y");
+        assert_eq!(status_message(&mut cx), Some("3 lines yanked".into()));
+
+        cx.set_state(text, Mode::Normal);
+        cx.simulate_keystrokes("2 y y");
+        assert_eq!(status_message(&mut cx), None);
+    }
+
+    #[gpui::test]
+    async fn test_delete_inner_lines_with_multiple_cursors(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+            let a = [
+                ˇ1,
+            ];
+            let b = [ˇ2];"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("\" a d i [");
+        cx.assert_state(
+            indoc! {"
+            let a = [
+            ˇ];
+            let b = [ˇ];"},
+            Mode::Normal,
+        );
+        // only one of the cursors deleted whole lines, so the register isn't
+        // linewise
+        let linewise = cx.update(|cx| {
+            Vim::read(cx).workspace_state.registers[&'a']
+                .clipboard_selections
+                .as_ref()
+                .unwrap()
+                .iter()
+                .any(|selection| selection.is_entire_line)
+        });
+        assert!(!linewise);
+    }
 }
@@ -0,0 +1,73 @@
+//! `!`, which filters lines through an external command. As in vim, it opens
+//! the command line with the range of lines it covers, ready for `:{range}!`.
+
+use editor::Editor;
+use gpui::{actions, ViewContext, WindowContext};
+use language::Point;
+use workspace::Workspace;
+
+use crate::{motion::Motion, object::Object, Vim};
+
+actions!(vim, [VisualFilter]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|workspace, _: &VisualFilter, cx| {
+        command_palette::CommandPalette::toggle(workspace, "'<,'>!", cx);
+    });
+}
+
+pub fn filter_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(map, selection, times, false, &text_layout_details);
+            });
+        });
+        open_filter_command(editor, cx);
+    });
+}
+
+pub fn filter_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        vim.with_object_cache(|cache| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    object.expand_selection(map, selection, around, times, cache);
+                });
+            });
+        });
+        open_filter_command(editor, cx);
+    });
+}
+
+/// Moves the cursor to the first of the selected lines and opens the command
+/// line to filter them with `:.,.+{n}!`.
+fn open_filter_command(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    let selection = editor.selections.newest::<Point>(cx);
+    let start = selection.start;
+    let mut end_row = selection.end.row;
+    if selection.end.column == 0 && end_row > start.row {
+        end_row -= 1;
+    }
+    editor.change_selections(None, cx, |s| s.select_ranges([start..start]));
+
+    let range = match end_row - start.row {
+        0 => ".!".to_string(),
+        lines => format!(".,.+{lines}!"),
+    };
+    let Some(workspace) = editor.workspace() else {
+        return;
+    };
+    cx.defer(move |_, cx| {
+        workspace.update(cx, |workspace, cx| {
+            command_palette::CommandPalette::toggle(workspace, &range, cx)
+        });
+    });
+}
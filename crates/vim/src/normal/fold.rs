@@ -0,0 +1,118 @@
+//! Vim's `z` fold commands, on top of the editor's folds. Zed only tracks
+//! folds while they're closed, so opening a fold and deleting it are the same.
+
+use editor::{display_map::ToDisplayPoint, Editor};
+use gpui::{actions, ViewContext, WindowContext};
+use language::SelectionGoal;
+use multi_buffer::MultiBufferRow;
+use workspace::Workspace;
+
+use crate::{motion::Motion, object::Object, Vim};
+
+actions!(vim, [ToggleFold, FoldAll, UnfoldAll]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    // Zed's folds have no levels, so unlike vim these ignore their count.
+    workspace.register_action(|_: &mut Workspace, _: &ToggleFold, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.take_count(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let (map, selections) = editor.selections.all_adjusted_display(cx);
+                let is_folded = selections.iter().any(|selection| {
+                    let row = selection.head().to_point(&map).row;
+                    map.is_line_folded(MultiBufferRow(row))
+                });
+                if is_folded {
+                    editor.unfold_lines(&Default::default(), cx);
+                } else {
+                    editor.fold(&Default::default(), cx);
+                }
+            });
+        });
+    });
+
+    workspace.register_action(|_: &mut Workspace, _: &FoldAll, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.take_count(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let (map, _) = editor.selections.all_adjusted_display(cx);
+                let max_row = map.buffer_snapshot.max_point().row;
+                let mut ranges = Vec::new();
+                let mut row = 0;
+                while row <= max_row {
+                    if let Some((range, placeholder)) = map.foldable_range(MultiBufferRow(row)) {
+                        row = range.end.row + 1;
+                        ranges.push((range, placeholder));
+                    } else {
+                        row += 1;
+                    }
+                }
+                editor.fold_ranges(ranges, true, cx);
+            });
+        });
+    });
+
+    workspace.register_action(|_: &mut Workspace, _: &UnfoldAll, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.take_count(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                let len = editor.buffer().read(cx).snapshot(cx).len();
+                editor.unfold_ranges([0..len], true, true, cx);
+            });
+        });
+    });
+}
+
+pub fn fold_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut WindowContext) {
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(map, selection, times, false, &text_layout_details);
+            });
+        });
+        fold_selected_lines(editor, cx);
+    });
+}
+
+pub fn fold_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        vim.with_object_cache(|cache| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    object.expand_selection(map, selection, around, times, cache);
+                });
+            });
+        });
+        fold_selected_lines(editor, cx);
+    });
+}
+
+/// Folds the whole lines the selections cover, as vim's folds are linewise,
+/// and leaves the cursor on the start of each fold.
+fn fold_selected_lines(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    editor.change_selections(None, cx, |s| {
+        s.move_with(|map, selection| {
+            let mut start = selection.start.to_point(map);
+            let mut end = selection.end.to_point(map);
+            if end.column == 0 && end.row > start.row {
+                end.row -= 1;
+            }
+            start.column = 0;
+            end.column = map.buffer_snapshot.line_len(MultiBufferRow(end.row));
+            selection.start = start.to_display_point(map);
+            selection.end = end.to_display_point(map);
+            selection.reversed = false;
+        });
+    });
+    editor.fold_selected_ranges(&Default::default(), cx);
+    editor.change_selections(None, cx, |s| {
+        s.move_with(|_, selection| selection.collapse_to(selection.start, SelectionGoal::None));
+    });
+}
@@ -0,0 +1,409 @@
+//! `gq` and `gw`, which reflow lines of text to fit within `textwidth`.
+
+use std::ops::{Range, RangeInclusive};
+
+use collections::HashMap;
+use editor::{display_map::ToDisplayPoint, Anchor, Bias, Editor};
+use gpui::{impl_actions, ViewContext, WindowContext};
+use language::{Point, SelectionGoal};
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
+use serde::Deserialize;
+use settings::Settings;
+use workspace::Workspace;
+
+use super::save_selection_starts;
+use crate::{
+    motion::{first_non_whitespace, Motion},
+    object::Object,
+    state::Mode,
+    Vim, VimSettings,
+};
+
+/// Reflows the lines of the visual selection, as `gq` (or `gw`) does.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct FormatText {
+    #[serde(default)]
+    keep_cursor: bool,
+}
+
+impl_actions!(vim, [FormatText]);
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, action: &FormatText, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.record_current_action(cx);
+            vim.update_active_editor(cx, |_, editor, cx| {
+                editor.transact(cx, |editor, cx| {
+                    let original_positions = save_selection_starts(editor, cx);
+                    format_selections(editor, action.keep_cursor, original_positions, cx)
+                });
+            });
+            vim.switch_mode(Mode::Normal, false, cx);
+        })
+    });
+}
+
+pub fn format_motion(
+    vim: &mut Vim,
+    motion: Motion,
+    times: Option<usize>,
+    keep_cursor: bool,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        editor.transact(cx, |editor, cx| {
+            let mut original_positions: HashMap<_, _> = Default::default();
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
+                    original_positions.insert(selection.id, anchor);
+                    motion.expand_selection(map, selection, times, false, &text_layout_details);
+                });
+            });
+            format_selections(editor, keep_cursor, original_positions, cx);
+        });
+    });
+}
+
+pub fn format_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    keep_cursor: bool,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        editor.transact(cx, |editor, cx| {
+            let mut original_positions: HashMap<_, _> = Default::default();
+            vim.with_object_cache(|cache| {
+                editor.change_selections(None, cx, |s| {
+                    s.move_with(|map, selection| {
+                        let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
+                        original_positions.insert(selection.id, anchor);
+                        object.expand_selection(map, selection, around, times, cache);
+                    });
+                });
+            });
+            format_selections(editor, keep_cursor, original_positions, cx);
+        });
+    });
+}
+
+/// Reflows the lines covered by each selection, then leaves the cursor where
+/// it was for `gw`, or on the last formatted line for `gq`.
+fn format_selections(
+    editor: &mut Editor,
+    keep_cursor: bool,
+    mut original_positions: HashMap<usize, Anchor>,
+    cx: &mut ViewContext<Editor>,
+) {
+    let textwidth = match VimSettings::get_global(cx).textwidth {
+        // vim falls back to a width of 79 too.
+        0 => 79,
+        textwidth => textwidth,
+    };
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let mut edits = Vec::new();
+    let mut last_lines: HashMap<_, _> = Default::default();
+    for selection in editor.selections.all::<Point>(cx) {
+        let mut end_row = selection.end.row;
+        if selection.end.column == 0 && end_row > selection.start.row {
+            end_row -= 1;
+        }
+        edits.extend(reflow(&snapshot, selection.start.row..=end_row, textwidth));
+        let line_end = Point::new(end_row, snapshot.line_len(MultiBufferRow(end_row)));
+        last_lines.insert(selection.id, snapshot.anchor_after(line_end));
+    }
+    editor.edit(edits, cx);
+
+    editor.change_selections(None, cx, |s| {
+        s.move_with(|map, selection| {
+            let point = if keep_cursor {
+                original_positions
+                    .remove(&selection.id)
+                    .unwrap()
+                    .to_display_point(map)
+            } else {
+                let line_end = last_lines.remove(&selection.id).unwrap();
+                first_non_whitespace(map, false, line_end.to_display_point(map))
+            };
+            selection.collapse_to(point, SelectionGoal::None);
+        });
+    });
+}
+
+/// The edits that reflow each paragraph within `rows`. Paragraphs are
+/// separated by blank lines or by a change of comment leader, and every line
+/// of a paragraph gets the indentation and leader of its first line.
+///
+/// Only the whitespace between words is replaced, so that anchors within the
+/// words (like the cursor for `gw`) stay where they are.
+fn reflow(
+    snapshot: &MultiBufferSnapshot,
+    rows: RangeInclusive<u32>,
+    textwidth: usize,
+) -> Vec<(Range<Point>, String)> {
+    let leaders = comment_leaders(snapshot, Point::new(*rows.start(), 0));
+    let mut edits = Vec::new();
+    let mut prefix = String::new();
+    let mut words: Vec<(Range<Point>, usize)> = Vec::new();
+
+    for row in rows {
+        let line_len = snapshot.line_len(MultiBufferRow(row));
+        let line = snapshot
+            .text_for_range(Point::new(row, 0)..Point::new(row, line_len))
+            .collect::<String>();
+        let (line_prefix, content) = line.split_at(prefix_len(&line, &leaders));
+        if content.is_empty() || line_prefix.trim() != prefix.trim() {
+            wrap(snapshot, &prefix, &words, textwidth, &mut edits);
+            words.clear();
+        }
+        if content.is_empty() {
+            continue;
+        }
+        if words.is_empty() {
+            prefix = line_prefix.to_string();
+        }
+
+        let mut word_start = None;
+        for (ix, ch) in content.char_indices().chain([(content.len(), ' ')]) {
+            match (word_start, ch.is_whitespace()) {
+                (None, false) => word_start = Some(ix),
+                (Some(start), true) => {
+                    let column = |ix: usize| (line_prefix.len() + ix) as u32;
+                    words.push((
+                        Point::new(row, column(start))..Point::new(row, column(ix)),
+                        content[start..ix].chars().count(),
+                    ));
+                    word_start = None;
+                }
+                _ => {}
+            }
+        }
+    }
+    wrap(snapshot, &prefix, &words, textwidth, &mut edits);
+    edits
+}
+
+/// Joins a paragraph's words with spaces, starting a new line (with the
+/// paragraph's prefix) before any word that would go past `textwidth`.
+fn wrap(
+    snapshot: &MultiBufferSnapshot,
+    prefix: &str,
+    words: &[(Range<Point>, usize)],
+    textwidth: usize,
+    edits: &mut Vec<(Range<Point>, String)>,
+) {
+    let Some((_, first_width)) = words.first() else {
+        return;
+    };
+    let prefix_width = prefix.chars().count();
+    let mut width = prefix_width + first_width;
+    for pair in words.windows(2) {
+        let (previous, (word, word_width)) = (&pair[0].0, &pair[1]);
+        let separator = if width + 1 + word_width > textwidth {
+            width = prefix_width + word_width;
+            format!("\n{prefix}")
+        } else {
+            width += 1 + word_width;
+            " ".to_string()
+        };
+        let gap = previous.end..word.start;
+        if snapshot.text_for_range(gap.clone()).collect::<String>() != separator {
+            edits.push((gap, separator));
+        }
+    }
+}
+
+/// The comment markers that can start a line of the paragraph, longest
+/// first so that `///` is preferred over `//`. The `*` that continues a
+/// `/* */` comment counts too.
+fn comment_leaders(snapshot: &MultiBufferSnapshot, point: Point) -> Vec<String> {
+    let Some(scope) = snapshot.language_scope_at(point) else {
+        return Vec::new();
+    };
+    let mut leaders = scope
+        .line_comment_prefixes()
+        .iter()
+        .map(|prefix| prefix.trim_end().to_string())
+        .collect::<Vec<_>>();
+    if scope
+        .block_comment_delimiters()
+        .is_some_and(|(start, _)| start.trim_end().ends_with('*'))
+    {
+        leaders.push("*".to_string());
+    }
+    leaders.sort_by_key(|leader| std::cmp::Reverse(leader.len()));
+    leaders
+}
+
+/// The length of the indentation, comment leader and whitespace that start
+/// `line`. A `*` only counts when it is followed by whitespace, so that the
+/// `*/` ending a comment is left alone.
+fn prefix_len(line: &str, leaders: &[String]) -> usize {
+    let indent = line.len() - line.trim_start().len();
+    let rest = &line[indent..];
+    let leader = leaders.iter().find(|leader| {
+        rest.strip_prefix(leader.as_str()).is_some_and(|after| {
+            leader.as_str() != "*" || after.is_empty() || after.starts_with(char::is_whitespace)
+        })
+    });
+    let Some(leader) = leader else {
+        return indent;
+    };
+    let after_leader = &rest[leader.len()..];
+    indent + leader.len() + after_leader.len() - after_leader.trim_start().len()
+}
+
+#[cfg(test)]
+mod test {
+    use indoc::indoc;
+    use settings::SettingsStore;
+
+    use crate::{state::Mode, test::VimTestContext, VimSettings};
+
+    fn set_textwidth(cx: &mut VimTestContext, textwidth: usize) {
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.textwidth = Some(textwidth));
+        });
+    }
+
+    #[gpui::test]
+    async fn test_format_long_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        set_textwidth(&mut cx, 20);
+
+        cx.set_state(
+            "ˇthe quick brown fox jumps over the lazy dog\n",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("g q q");
+        cx.assert_state(
+            indoc! {"
+            the quick brown fox
+            jumps over the lazy
+            ˇdog
+            "},
+            Mode::Normal,
+        );
+
+        // gw leaves the cursor in the same word
+        cx.set_state(
+            "    the quick ˇbrown fox jumps over the lazy dog\n",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("g w w");
+        cx.assert_state(
+            "    the quick ˇbrown\n    fox jumps over\n    the lazy dog\n",
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_format_paragraphs(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        set_textwidth(&mut cx, 20);
+
+        // short lines are joined, but not across a blank line
+        cx.set_state(
+            indoc! {"
+            ˇone
+            two three
+
+            four
+            five"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("g q shift-g");
+        cx.assert_state(
+            indoc! {"
+            one two three
+
+            ˇfour five"},
+            Mode::Normal,
+        );
+
+        // visual mode formats the selected lines
+        cx.set_state(
+            indoc! {"
+            ˇone
+            two
+            three
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("shift-v j g q");
+        cx.assert_state(
+            indoc! {"
+            ˇone two
+            three
+            "},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_format_comments(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        set_textwidth(&mut cx, 20);
+
+        let language = std::sync::Arc::new(language::Language::new(
+            language::LanguageConfig {
+                line_comments: vec!["// ".into(), "/// ".into()],
+                block_comment: Some(("/* ".into(), " */".into())),
+                ..Default::default()
+            },
+            Some(language::tree_sitter_rust::language()),
+        ));
+        cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+        cx.set_state(
+            indoc! {"
+            fn a() {
+                ˇ/// the quick brown fox jumps
+                /// over the lazy dog
+                // a
+                // b
+            }
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("g q 3 j");
+        cx.assert_state(
+            indoc! {"
+            fn a() {
+                /// the quick
+                /// brown fox
+                /// jumps over
+                /// the lazy dog
+                ˇ// a b
+            }
+            "},
+            Mode::Normal,
+        );
+
+        cx.set_state(
+            indoc! {"
+            /*
+             * ˇthe quick brown fox jumps
+             */
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("g q q");
+        cx.assert_state(
+            indoc! {"
+            /*
+             * the quick brown
+             ˇ* fox jumps
+             */
+            "},
+            Mode::Normal,
+        );
+    }
+}
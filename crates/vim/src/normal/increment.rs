@@ -1,12 +1,17 @@
 use std::ops::Range;
 
+use chrono::{Duration, NaiveDate};
 use editor::{scroll::Autoscroll, MultiBufferSnapshot, ToOffset, ToPoint};
 use gpui::{impl_actions, ViewContext, WindowContext};
 use language::{Bias, Point};
+use lazy_static::lazy_static;
+use multi_buffer::MultiBufferRow;
+use regex::Regex;
 use serde::Deserialize;
+use settings::Settings;
 use workspace::Workspace;
 
-use crate::{state::Mode, Vim};
+use crate::{state::Mode, NumberFormat, Vim, VimSettings};
 
 #[derive(Clone, Deserialize, PartialEq)]
 #[serde(rename_all = "camelCase")]
@@ -49,6 +54,7 @@ fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
         let mut edits = Vec::new();
         let mut new_anchors = Vec::new();
 
+        let formats = VimSettings::get_global(cx).nrformats.clone();
         let snapshot = editor.buffer().read(cx).snapshot(cx);
         for selection in editor.selections.all_adjusted(cx) {
             if !selection.is_empty() {
@@ -63,22 +69,9 @@ fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
                     Point::new(row, 0)
                 };
 
-                if let Some((range, num, radix)) = find_number(&snapshot, start) {
-                    if let Ok(val) = i32::from_str_radix(&num, radix) {
-                        let result = val + delta;
-                        delta += step;
-                        let replace = match radix {
-                            10 => format!("{}", result),
-                            16 => {
-                                if num.to_ascii_lowercase() == num {
-                                    format!("{:x}", result)
-                                } else {
-                                    format!("{:X}", result)
-                                }
-                            }
-                            2 => format!("{:b}", result),
-                            _ => unreachable!(),
-                        };
+                if let Some((range, target)) = find_target(&snapshot, start, &formats) {
+                    if let Some(replace) = target.incremented(delta) {
+                        delta = delta.saturating_add(step);
                         edits.push((range.clone(), replace));
                     }
                     if selection.is_empty() {
@@ -112,76 +105,214 @@ fn increment(vim: &mut Vim, mut delta: i32, step: i32, cx: &mut WindowContext) {
     vim.switch_mode(Mode::Normal, true, cx)
 }
 
-fn find_number(
+/// Something that `ctrl-a` and `ctrl-x` can change.
+enum Target {
+    Number { digits: String, radix: u32 },
+    Letter(char),
+    Date(NaiveDate),
+}
+
+impl Target {
+    /// The text of the target once `delta` has been added to it, if it can be
+    /// without overflowing.
+    fn incremented(&self, delta: i32) -> Option<String> {
+        match self {
+            Target::Number { digits, radix } => {
+                let result = i32::from_str_radix(digits, *radix)
+                    .ok()?
+                    .checked_add(delta)?;
+                // Leading zeros are kept so that the number stays the same width.
+                let width = digits.len();
+                Some(match radix {
+                    10 if digits.starts_with('0') => format!("{:0width$}", result),
+                    10 => format!("{}", result),
+                    16 => {
+                        if digits.to_ascii_lowercase() == *digits {
+                            format!("{:0width$x}", result)
+                        } else {
+                            format!("{:0width$X}", result)
+                        }
+                    }
+                    8 => format!("0{:0width$o}", result, width = width - 1),
+                    2 => format!("{:0width$b}", result),
+                    _ => unreachable!(),
+                })
+            }
+            // As in vim, letters stop at `a` and `z` rather than wrapping.
+            Target::Letter(letter) => {
+                let base = if letter.is_ascii_lowercase() {
+                    b'a'
+                } else {
+                    b'A'
+                };
+                let index = ((*letter as u8 - base) as i32).saturating_add(delta);
+                Some(((base + index.clamp(0, 25) as u8) as char).to_string())
+            }
+            Target::Date(date) => {
+                let date = date.checked_add_signed(Duration::days(delta as i64))?;
+                Some(date.format("%Y-%m-%d").to_string())
+            }
+        }
+    }
+}
+
+lazy_static! {
+    static ref DATE_REGEX: Regex = Regex::new(r"\b\d{4}-\d{2}-\d{2}\b").unwrap();
+}
+
+/// Finds what to change for a cursor at `start`: an ISO date or a single
+/// letter under the cursor if those formats are enabled, or else a number.
+fn find_target(
     snapshot: &MultiBufferSnapshot,
     start: Point,
-) -> Option<(Range<Point>, String, u32)> {
-    let mut offset = start.to_offset(snapshot);
-
-    let ch0 = snapshot.chars_at(offset).next();
-    if ch0.as_ref().is_some_and(char::is_ascii_digit) || matches!(ch0, Some('-' | 'b' | 'x')) {
-        // go backwards to the start of any number the selection is within
-        for ch in snapshot.reversed_chars_at(offset) {
-            if ch.is_ascii_digit() || ch == '-' || ch == 'b' || ch == 'x' {
-                offset -= ch.len_utf8();
-                continue;
+    formats: &[NumberFormat],
+) -> Option<(Range<Point>, Target)> {
+    let line_end = Point::new(start.row, snapshot.line_len(MultiBufferRow(start.row)));
+    let line = snapshot
+        .text_for_range(Point::new(start.row, 0)..line_end)
+        .collect::<String>();
+    let column = start.column as usize;
+    let range = |begin: usize, end: usize| {
+        Point::new(start.row, begin as u32)..Point::new(start.row, end as u32)
+    };
+
+    if formats.contains(&NumberFormat::Date) {
+        let date = DATE_REGEX.find_iter(&line).find_map(|found| {
+            (found.start() <= column && column < found.end())
+                .then(|| NaiveDate::parse_from_str(found.as_str(), "%Y-%m-%d").ok())
+                .flatten()
+                .map(|date| (range(found.start(), found.end()), Target::Date(date)))
+        });
+        if date.is_some() {
+            return date;
+        }
+    }
+
+    if formats.contains(&NumberFormat::Alpha) {
+        let is_alphanumeric = |ch: Option<char>| ch.is_some_and(|ch| ch.is_alphanumeric());
+        if let Some(letter) = line[column..].chars().next() {
+            if letter.is_ascii_alphabetic()
+                && !is_alphanumeric(line[..column].chars().next_back())
+                && !is_alphanumeric(line[column + 1..].chars().next())
+            {
+                return Some((range(column, column + 1), Target::Letter(letter)));
             }
-            break;
         }
     }
 
-    let mut begin = None;
-    let mut end = None;
-    let mut num = String::new();
-    let mut radix = 10;
-
-    let mut chars = snapshot.chars_at(offset).peekable();
-    // find the next number on the line (may start after the original cursor position)
-    while let Some(ch) = chars.next() {
-        if num == "0" && ch == 'b' && chars.peek().is_some() && chars.peek().unwrap().is_digit(2) {
-            radix = 2;
-            begin = None;
-            num = String::new();
+    find_number(snapshot, start, formats)
+        .map(|(range, digits, radix)| (range, Target::Number { digits, radix }))
+}
+
+/// Finds the number that contains `start`, or else the next one after it on
+/// the same line. A number with a `0x` or `0b` prefix is recognized as a
+/// whole wherever the cursor is within it, including on the leading `0`.
+fn find_number(
+    snapshot: &MultiBufferSnapshot,
+    start: Point,
+    formats: &[NumberFormat],
+) -> Option<(Range<Point>, String, u32)> {
+    let line_end = Point::new(start.row, snapshot.line_len(MultiBufferRow(start.row)));
+    let line = snapshot
+        .text_for_range(Point::new(start.row, 0)..line_end)
+        .collect::<String>();
+    let chars = line.char_indices().collect::<Vec<_>>();
+    let cursor = chars
+        .iter()
+        .position(|(offset, _)| *offset >= start.column as usize)
+        .unwrap_or(chars.len());
+    let char_at = |ix: usize| chars.get(ix).map(|(_, ch)| *ch);
+
+    // The radix and end of a number with a prefix starting at `ix`.
+    let prefixed_number = |ix: usize| -> Option<(u32, usize)> {
+        if char_at(ix) != Some('0') {
+            return None;
         }
-        if num == "0" && ch == 'x' && chars.peek().is_some() && chars.peek().unwrap().is_digit(16) {
-            radix = 16;
-            begin = None;
-            num = String::new();
+        let radix = match char_at(ix + 1)? {
+            'x' | 'X' if formats.contains(&NumberFormat::Hex) => 16,
+            'b' | 'B' if formats.contains(&NumberFormat::Bin) => 2,
+            _ => return None,
+        };
+        let digits = chars[ix + 2..]
+            .iter()
+            .take_while(|(_, ch)| ch.is_digit(radix))
+            .count();
+        (digits > 0).then_some((radix, ix + 2 + digits))
+    };
+
+    let mut number = None;
+    // Look back over anything that could be part of a prefixed number for one
+    // that the cursor is within.
+    let mut ix = cursor;
+    while ix > 0
+        && char_at(ix - 1).is_some_and(|ch| ch.is_ascii_hexdigit() || ch == 'x' || ch == 'X')
+    {
+        ix -= 1;
+    }
+    for begin in ix..=cursor.min(chars.len().saturating_sub(1)) {
+        if let Some((radix, end)) = prefixed_number(begin) {
+            if end > cursor {
+                number = Some((begin + 2, end, radix));
+                break;
+            }
         }
+    }
 
-        if ch.is_digit(radix)
-            || (begin.is_none()
-                && ch == '-'
-                && chars.peek().is_some()
-                && chars.peek().unwrap().is_digit(radix))
-        {
-            if begin.is_none() {
-                begin = Some(offset);
+    if number.is_none() {
+        // Otherwise start from the beginning of any decimal number the cursor is
+        // on, and take the first number from there.
+        let mut ix = cursor;
+        if char_at(ix).is_some_and(|ch| ch.is_ascii_digit()) {
+            while ix > 0 && char_at(ix - 1).is_some_and(|ch| ch.is_ascii_digit()) {
+                ix -= 1;
+            }
+            if ix > 0 && char_at(ix - 1) == Some('-') {
+                ix -= 1;
             }
-            num.push(ch);
-        } else {
-            if begin.is_some() {
-                end = Some(offset);
+        }
+        while ix < chars.len() {
+            if let Some((radix, end)) = prefixed_number(ix) {
+                number = Some((ix + 2, end, radix));
                 break;
-            } else if ch == '\n' {
+            }
+            let negative =
+                char_at(ix) == Some('-') && char_at(ix + 1).is_some_and(|ch| ch.is_ascii_digit());
+            if negative || char_at(ix).is_some_and(|ch| ch.is_ascii_digit()) {
+                let digits_start = if negative { ix + 1 } else { ix };
+                let end = digits_start
+                    + chars[digits_start..]
+                        .iter()
+                        .take_while(|(_, ch)| ch.is_ascii_digit())
+                        .count();
+                let is_octal = formats.contains(&NumberFormat::Octal)
+                    && !negative
+                    && end - ix > 1
+                    && char_at(ix) == Some('0')
+                    && chars[ix..end].iter().all(|(_, ch)| ch.is_digit(8));
+                number = Some((ix, end, if is_octal { 8 } else { 10 }));
                 break;
             }
+            ix += 1;
         }
-        offset += ch.len_utf8();
-    }
-    if let Some(begin) = begin {
-        let end = end.unwrap_or(offset);
-        Some((begin.to_point(snapshot)..end.to_point(snapshot), num, radix))
-    } else {
-        None
     }
+
+    let (begin, end, radix) = number?;
+    let offset = |ix: usize| chars.get(ix).map_or(line.len(), |(offset, _)| *offset);
+    let range =
+        Point::new(start.row, offset(begin) as u32)..Point::new(start.row, offset(end) as u32);
+    Some((range, line[offset(begin)..offset(end)].to_string(), radix))
 }
 
 #[cfg(test)]
 mod test {
     use indoc::indoc;
+    use settings::SettingsStore;
 
-    use crate::test::NeovimBackedTestContext;
+    use crate::{
+        state::Mode,
+        test::{NeovimBackedTestContext, VimTestContext},
+        NumberFormat, VimSettings,
+    };
 
     #[gpui::test]
     async fn test_increment(cx: &mut gpui::TestAppContext) {
@@ -271,6 +402,115 @@ mod test {
         cx.simulate("ctrl-a", "banˇana").await.assert_matches();
     }
 
+    #[gpui::test]
+    async fn test_increment_cursor_within_prefixed_number(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.simulate("ctrl-a", "ˇ0xff").await.assert_matches();
+        cx.simulate("ctrl-a", "0ˇxff").await.assert_matches();
+        cx.simulate("ctrl-a", "0xˇff").await.assert_matches();
+        cx.simulate("ctrl-a", "0xfˇf").await.assert_matches();
+
+        cx.simulate("ctrl-a", "ˇ0b101").await.assert_matches();
+        cx.simulate("ctrl-a", "0ˇb101").await.assert_matches();
+        cx.simulate("ctrl-a", "0b10ˇ1").await.assert_matches();
+
+        cx.simulate("ctrl-a", "ˇ0x0f").await.assert_matches();
+        cx.simulate("ctrl-a", "ˇ010").await.assert_matches();
+    }
+
+    #[gpui::test]
+    async fn test_increment_octal(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.nrformats = Some(vec![
+                    NumberFormat::Bin,
+                    NumberFormat::Hex,
+                    NumberFormat::Octal,
+                ])
+            });
+        });
+
+        cx.set_state("ˇ010", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("01ˇ1", Mode::Normal);
+        cx.set_state("ˇ07", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("01ˇ0", Mode::Normal);
+        cx.set_state("ˇ019", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("02ˇ0", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_increment_alpha(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.nrformats = Some(vec![NumberFormat::Hex, NumberFormat::Alpha])
+            });
+        });
+
+        cx.set_state("ˇy", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("ˇz", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("ˇz", Mode::Normal);
+        cx.set_state("a ˇC 1", Mode::Normal);
+        cx.simulate_keystrokes("5 ctrl-x");
+        cx.assert_state("a ˇA 1", Mode::Normal);
+        cx.set_state("ˇab 1", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("ab ˇ2", Mode::Normal);
+        cx.set_state("0ˇxff", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("0x10ˇ0", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_increment_overflow(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // numbers that would overflow are left alone
+        cx.set_state("ˇ2147483647", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("214748364ˇ7", Mode::Normal);
+        cx.set_state("ˇ2147483640 1", Mode::Normal);
+        cx.simulate_keystrokes("2 0 ctrl-a");
+        cx.assert_state("214748364ˇ0 1", Mode::Normal);
+
+        // while the others still change
+        cx.set_state("ˇ2147483640\n1", Mode::Normal);
+        cx.simulate_keystrokes("shift-v j 2 0 ctrl-a");
+        cx.assert_state("ˇ2147483640\n21", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_increment_date(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.nrformats = Some(vec![NumberFormat::Date])
+            });
+        });
+
+        cx.set_state("due ˇ2023-12-31", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("due 2024-01-0ˇ1", Mode::Normal);
+        cx.set_state("2024-03-ˇ01", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-x");
+        cx.assert_state("2024-02-2ˇ9", Mode::Normal);
+        cx.simulate_keystrokes("3 6 6 ctrl-a");
+        cx.assert_state("2025-03-0ˇ1", Mode::Normal);
+        cx.set_state("ˇ1 2023-12-31", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-a");
+        cx.assert_state("ˇ2 2023-12-31", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_increment_steps(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -4,10 +4,10 @@ use editor::{display_map::ToDisplayPoint, Bias};
 use gpui::WindowContext;
 use language::SelectionGoal;
 
-#[derive(PartialEq, Eq)]
 pub(super) enum IndentDirection {
     In,
     Out,
+    Auto,
 }
 
 pub fn indent_motion(
@@ -29,10 +29,10 @@ pub fn indent_motion(
                     motion.expand_selection(map, selection, times, false, &text_layout_details);
                 });
             });
-            if dir == IndentDirection::In {
-                editor.indent(&Default::default(), cx);
-            } else {
-                editor.outdent(&Default::default(), cx);
+            match dir {
+                IndentDirection::In => editor.indent(&Default::default(), cx),
+                IndentDirection::Out => editor.outdent(&Default::default(), cx),
+                IndentDirection::Auto => editor.autoindent(&Default::default(), cx),
             }
             editor.change_selections(None, cx, |s| {
                 s.move_with(|map, selection| {
@@ -48,24 +48,27 @@ pub fn indent_object(
     vim: &mut Vim,
     object: Object,
     around: bool,
+    times: Option<usize>,
     dir: IndentDirection,
     cx: &mut WindowContext,
 ) {
     vim.stop_recording();
-    vim.update_active_editor(cx, |_, editor, cx| {
+    vim.update_active_editor(cx, |vim, editor, cx| {
         editor.transact(cx, |editor, cx| {
             let mut original_positions: HashMap<_, _> = Default::default();
-            editor.change_selections(None, cx, |s| {
-                s.move_with(|map, selection| {
-                    let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
-                    original_positions.insert(selection.id, anchor);
-                    object.expand_selection(map, selection, around);
+            vim.with_object_cache(|cache| {
+                editor.change_selections(None, cx, |s| {
+                    s.move_with(|map, selection| {
+                        let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
+                        original_positions.insert(selection.id, anchor);
+                        object.expand_selection(map, selection, around, times, cache);
+                    });
                 });
             });
-            if dir == IndentDirection::In {
-                editor.indent(&Default::default(), cx);
-            } else {
-                editor.outdent(&Default::default(), cx);
+            match dir {
+                IndentDirection::In => editor.indent(&Default::default(), cx),
+                IndentDirection::Out => editor.outdent(&Default::default(), cx),
+                IndentDirection::Auto => editor.autoindent(&Default::default(), cx),
             }
             editor.change_selections(None, cx, |s| {
                 s.move_with(|map, selection| {
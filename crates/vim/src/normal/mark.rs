@@ -33,7 +33,7 @@ pub fn create_mark(vim: &mut Vim, text: Arc<str>, tail: bool, cx: &mut WindowCon
 pub fn create_visual_marks(vim: &mut Vim, mode: Mode, cx: &mut WindowContext) {
     let mut starts = vec![];
     let mut ends = vec![];
-    let mut reversed = vec![];
+    let mut directions = vec![];
 
     vim.update_active_editor(cx, |_, editor, cx| {
         let (map, selections) = editor.selections.all_display(cx);
@@ -47,14 +47,24 @@ pub fn create_visual_marks(vim: &mut Vim, mode: Mode, cx: &mut WindowContext) {
                 map.buffer_snapshot
                     .anchor_after(selection.start.to_offset(&map, Bias::Right)),
             );
-            reversed.push(selection.reversed)
+            // Only a block's `$` is kept, as other goals are positions that
+            // edits in the meantime could make stale.
+            let goal = match selection.goal {
+                SelectionGoal::HorizontalRange { end, .. }
+                    if mode == Mode::VisualBlock && end == f32::INFINITY =>
+                {
+                    selection.goal
+                }
+                _ => SelectionGoal::None,
+            };
+            directions.push((selection.reversed, goal))
         }
     });
 
     vim.update_state(|state| {
         state.marks.insert("<".to_string(), starts);
         state.marks.insert(">".to_string(), ends);
-        state.stored_visual_mode.replace((mode, reversed));
+        state.stored_visual_mode.replace((mode, directions));
     });
     vim.clear_operator(cx);
 }
@@ -0,0 +1,230 @@
+//! `g@`, which hands the text an operator covers to the function set with
+//! [`set_operator_func`], like vim's `'operatorfunc'`. This is how extensions
+//! can define operators of their own that work with any motion or object.
+
+use std::{ops::Range, rc::Rc};
+
+use editor::{Anchor, Editor};
+use gpui::{actions, AppContext, UpdateGlobal, ViewContext, WindowContext};
+use language::Point;
+use multi_buffer::MultiBufferRow;
+use workspace::Workspace;
+
+use crate::{motion::Motion, object::Object, state::Mode, Vim};
+
+/// How the text given to an operator function was chosen, which vim passes
+/// to `'operatorfunc'` as `"char"`, `"line"` or `"block"`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OperatorFuncType {
+    /// A characterwise motion or object, or visual mode.
+    Char,
+    /// A linewise motion, or visual line mode.
+    Line,
+    /// Visual block mode, with a range for each line of the block.
+    Block,
+}
+
+pub(crate) type OperatorFunc =
+    Rc<dyn Fn(&mut Editor, Vec<Range<Anchor>>, OperatorFuncType, &mut ViewContext<Editor>)>;
+
+actions!(vim, [VisualOperatorFunc]);
+
+/// Sets the function that `g@` calls with the ranges it covers. The cursors
+/// are already at the start of the ranges, and the function may move them on.
+pub fn set_operator_func(
+    cx: &mut AppContext,
+    func: impl Fn(&mut Editor, Vec<Range<Anchor>>, OperatorFuncType, &mut ViewContext<Editor>) + 'static,
+) {
+    Vim::update_global(cx, |vim, _| {
+        vim.workspace_state.operator_func = Some(Rc::new(func));
+    });
+}
+
+pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
+    workspace.register_action(|_: &mut Workspace, _: &VisualOperatorFunc, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.record_current_action(cx);
+            vim.store_visual_marks(cx);
+            let mode = vim.state().mode;
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let ranges = editor
+                    .selections
+                    .all::<Point>(cx)
+                    .into_iter()
+                    .map(|selection| match mode {
+                        Mode::VisualLine => {
+                            let end_row = MultiBufferRow(selection.end.row);
+                            Point::new(selection.start.row, 0)
+                                ..Point::new(end_row.0, snapshot.line_len(end_row))
+                        }
+                        _ => selection.range(),
+                    })
+                    .collect();
+                let func_type = match mode {
+                    Mode::VisualLine => OperatorFuncType::Line,
+                    Mode::VisualBlock => OperatorFuncType::Block,
+                    _ => OperatorFuncType::Char,
+                };
+                run_operator_func(vim, editor, ranges, func_type, cx);
+            });
+            vim.switch_mode(Mode::Normal, true, cx);
+        });
+    });
+}
+
+pub fn operator_func_motion(
+    vim: &mut Vim,
+    motion: Motion,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    let func_type = if motion.linewise() {
+        OperatorFuncType::Line
+    } else {
+        OperatorFuncType::Char
+    };
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        let text_layout_details = editor.text_layout_details(cx);
+        editor.change_selections(None, cx, |s| {
+            s.move_with(|map, selection| {
+                motion.expand_selection(map, selection, times, false, &text_layout_details);
+            });
+        });
+        let ranges = selected_ranges(editor, cx);
+        run_operator_func(vim, editor, ranges, func_type, cx);
+    });
+}
+
+pub fn operator_func_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
+    vim.stop_recording();
+    vim.update_active_editor(cx, |vim, editor, cx| {
+        vim.with_object_cache(|cache| {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    object.expand_selection(map, selection, around, times, cache);
+                });
+            });
+        });
+        let ranges = selected_ranges(editor, cx);
+        run_operator_func(vim, editor, ranges, OperatorFuncType::Char, cx);
+    });
+}
+
+fn selected_ranges(editor: &mut Editor, cx: &mut ViewContext<Editor>) -> Vec<Range<Point>> {
+    editor
+        .selections
+        .all::<Point>(cx)
+        .into_iter()
+        .map(|selection| selection.range())
+        .collect()
+}
+
+/// Moves the cursors to the start of `ranges`, or of the first of them for a
+/// block, and calls the operator function on them as one undoable edit.
+fn run_operator_func(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    ranges: Vec<Range<Point>>,
+    func_type: OperatorFuncType,
+    cx: &mut ViewContext<Editor>,
+) {
+    let cursors = ranges
+        .iter()
+        .take(if func_type == OperatorFuncType::Block {
+            1
+        } else {
+            ranges.len()
+        })
+        .map(|range| range.start..range.start)
+        .collect::<Vec<_>>();
+    editor.change_selections(None, cx, |s| s.select_ranges(cursors));
+
+    let Some(func) = vim.workspace_state.operator_func.clone() else {
+        vim.set_status_message("E774: 'operatorfunc' is empty".to_string());
+        return;
+    };
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let ranges = ranges
+        .into_iter()
+        .map(|range| snapshot.anchor_before(range.start)..snapshot.anchor_after(range.end))
+        .collect();
+    editor.transact(cx, |editor, cx| func(editor, ranges, func_type, cx));
+}
+
+#[cfg(test)]
+mod test {
+    use std::{cell::RefCell, rc::Rc};
+
+    use crate::{state::Mode, test::VimTestContext, Vim};
+
+    use super::{set_operator_func, OperatorFuncType};
+
+    #[gpui::test]
+    async fn test_operator_func(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇfoo bar", Mode::Normal);
+        cx.simulate_keystrokes("g @ i w");
+        cx.assert_state("ˇfoo bar", Mode::Normal);
+        assert_eq!(
+            cx.update(|cx| Vim::read(cx).workspace_state.status_message.clone()),
+            Some("E774: 'operatorfunc' is empty".into())
+        );
+
+        let types = Rc::new(RefCell::new(Vec::new()));
+        let recorded_types = types.clone();
+        cx.update(|cx| {
+            set_operator_func(cx, move |editor, ranges, func_type, cx| {
+                recorded_types.borrow_mut().push(func_type);
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                let edits = ranges
+                    .into_iter()
+                    .map(|range| {
+                        let text = snapshot.text_for_range(range.clone()).collect::<String>();
+                        (range, text.to_uppercase())
+                    })
+                    .collect::<Vec<_>>();
+                editor.edit(edits, cx);
+            })
+        });
+
+        cx.set_state("foo bˇar baz", Mode::Normal);
+        cx.simulate_keystrokes("g @ i w");
+        cx.assert_state("foo ˇBAR baz", Mode::Normal);
+        cx.simulate_keystrokes("w .");
+        cx.assert_state("foo BAR ˇBAZ", Mode::Normal);
+        cx.simulate_keystrokes("u");
+        cx.assert_state("foo BAR ˇbaz", Mode::Normal);
+
+        cx.set_state("aˇb\ncd\nef", Mode::Normal);
+        cx.simulate_keystrokes("g @ j");
+        cx.assert_state("ˇAB\nCD\nef", Mode::Normal);
+
+        cx.set_state("ˇab\ncd\nef", Mode::Normal);
+        cx.simulate_keystrokes("shift-v j g @");
+        cx.assert_state("ˇAB\nCD\nef", Mode::Normal);
+
+        cx.set_state("ˇab\ncd\nef", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j g @");
+        cx.assert_state("ˇAb\nCd\nef", Mode::Normal);
+
+        assert_eq!(
+            *types.borrow(),
+            [
+                OperatorFuncType::Char,
+                OperatorFuncType::Char,
+                OperatorFuncType::Line,
+                OperatorFuncType::Line,
+                OperatorFuncType::Block,
+            ]
+        );
+    }
+}
@@ -1,6 +1,8 @@
 use std::cmp;
 
-use editor::{display_map::ToDisplayPoint, movement, scroll::Autoscroll, DisplayPoint, RowExt};
+use editor::{
+    display_map::ToDisplayPoint, movement, scroll::Autoscroll, DisplayPoint, Editor, RowExt,
+};
 use gpui::{impl_actions, ViewContext};
 use language::{Bias, SelectionGoal};
 use serde::Deserialize;
@@ -8,7 +10,7 @@ use workspace::Workspace;
 
 use crate::{
     normal::yank::copy_selections_content,
-    state::{Mode, Register},
+    state::{LastPaste, Mode, Register},
     Vim,
 };
 
@@ -19,12 +21,24 @@ struct Paste {
     before: bool,
     #[serde(default)]
     preserve_clipboard: bool,
+    /// Leaves the cursor just after the pasted text, as `gp` and `gP` do.
+    #[serde(default)]
+    cursor_after: bool,
+}
+
+/// Swaps the text that was just pasted for another entry in the yank ring.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct CyclePaste {
+    #[serde(default)]
+    previous: bool,
 }
 
-impl_actions!(vim, [Paste]);
+impl_actions!(vim, [Paste, CyclePaste]);
 
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(paste);
+    workspace.register_action(cycle_paste);
 }
 
 fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
@@ -34,179 +48,305 @@ fn paste(_: &mut Workspace, action: &Paste, cx: &mut ViewContext<Workspace>) {
         let count = vim.take_count(cx).unwrap_or(1);
 
         vim.update_active_editor(cx, |vim, editor, cx| {
-            let text_layout_details = editor.text_layout_details(cx);
-            editor.transact(cx, |editor, cx| {
-                editor.set_clip_at_line_ends(false, cx);
-
-                let selected_register = vim.update_state(|state| state.selected_register.take());
-
-                let Some(Register {
-                    text,
-                    clipboard_selections,
-                }) = vim
-                    .read_register(selected_register, Some(editor), cx)
-                    .filter(|reg| !reg.text.is_empty())
-                else {
-                    return;
-                };
-                let clipboard_selections = clipboard_selections
-                    .filter(|sel| sel.len() > 1 && vim.state().mode != Mode::VisualLine);
+            editor.set_clip_at_line_ends(false, cx);
+
+            let selected_register = vim.update_state(|state| state.selected_register.take());
+            let Some(register) = vim
+                .read_register(selected_register, Some(editor), cx)
+                .filter(|reg| !reg.text.is_empty())
+            else {
+                return;
+            };
+
+            // Only a paste in normal mode can be swapped for another entry
+            // in the yank ring, as it's undone before the next one is put.
+            vim.workspace_state.last_paste = (!vim.state().mode.is_visual()).then(|| LastPaste {
+                before: action.before,
+                cursor_after: action.cursor_after,
+                count,
+                ring_index: vim
+                    .workspace_state
+                    .yank_ring
+                    .iter()
+                    .position(|entry| entry.text == register.text),
+            });
+            put(vim, editor, register, action, count, cx);
+        });
+        vim.switch_mode(Mode::Normal, true, cx);
+    });
+}
+
+/// Replaces the text that was just pasted with the previous (older) or the
+/// next (newer) entry in the yank ring.
+fn cycle_paste(_: &mut Workspace, action: &CyclePaste, cx: &mut ViewContext<Workspace>) {
+    let Some(last_paste) = Vim::read(cx).workspace_state.last_paste.clone() else {
+        return;
+    };
+
+    Vim::update(cx, |vim, cx| {
+        let ring_len = vim.workspace_state.yank_ring.len();
+        if ring_len == 0 {
+            return;
+        }
+        let ring_index = match (last_paste.ring_index, action.previous) {
+            (None, true) => 0,
+            (None, false) => ring_len - 1,
+            (Some(ix), true) => (ix + 1) % ring_len,
+            (Some(ix), false) => (ix + ring_len - 1) % ring_len,
+        };
+        let register = vim.workspace_state.yank_ring[ring_index].clone();
+        let paste = Paste {
+            before: last_paste.before,
+            preserve_clipboard: false,
+            cursor_after: last_paste.cursor_after,
+        };
+
+        vim.update_active_editor(cx, |vim, editor, cx| {
+            editor.undo(&editor::actions::Undo, cx);
+            editor.set_clip_at_line_ends(false, cx);
+            put(vim, editor, register, &paste, last_paste.count, cx);
+        });
+        vim.workspace_state.last_paste = Some(LastPaste {
+            ring_index: Some(ring_index),
+            ..last_paste
+        });
+        vim.switch_mode(Mode::Normal, true, cx);
+    });
+}
+
+fn put(
+    vim: &mut Vim,
+    editor: &mut Editor,
+    register: Register,
+    action: &Paste,
+    count: usize,
+    cx: &mut ViewContext<Editor>,
+) {
+    let text_layout_details = editor.text_layout_details(cx);
+    editor.transact(cx, |editor, cx| {
+        let Register {
+            text,
+            clipboard_selections,
+            ragged_block,
+        } = register;
+        let clipboard_selections = clipboard_selections
+            .filter(|sel| sel.len() > 1 && vim.state().mode != Mode::VisualLine);
+
+        if !action.preserve_clipboard && vim.state().mode.is_visual() {
+            copy_selections_content(vim, editor, vim.state().mode == Mode::VisualLine, cx);
+        }
+
+        // A block put on the last lines of the buffer gets the new
+        // lines it needs to keep its shape.
+        if let Some(clipboard_selections) = clipboard_selections
+            .as_ref()
+            .filter(|sels| !sels.iter().any(|sel| sel.is_entire_line))
+        {
+            let (display_map, current_selections) = editor.selections.all_adjusted_display(cx);
+            let last_row = current_selections.last().unwrap().end.row().0
+                + clipboard_selections
+                    .len()
+                    .saturating_sub(current_selections.len()) as u32;
+            let missing = last_row.saturating_sub(display_map.max_point().row().0);
+            if missing > 0 {
+                let end = display_map.buffer_snapshot.len();
+                editor.buffer().update(cx, |buffer, cx| {
+                    buffer.edit([(end..end, "\n".repeat(missing as usize))], None, cx)
+                });
+            }
+        }
+
+        let (display_map, current_selections) = editor.selections.all_adjusted_display(cx);
+
+        // unlike zed, if you have a multi-cursor selection from vim block mode,
+        // pasting it will paste it on subsequent lines, even if you don't yet
+        // have a cursor there.
+        let mut selections_to_process = Vec::new();
+        let mut left = None;
+        let mut i = 0;
+        while i < current_selections.len() {
+            selections_to_process
+                .push((current_selections[i].start..current_selections[i].end, true));
+            i += 1;
+        }
+        if let Some(clipboard_selections) = clipboard_selections.as_ref() {
+            let column = current_selections
+                .iter()
+                .map(|selection| cmp::min(selection.start.column(), selection.end.column()))
+                .min()
+                .unwrap();
+            left = Some(column);
+            let mut row = current_selections.last().unwrap().end.row().next_row();
+            while i < clipboard_selections.len() {
+                let cursor = display_map.clip_point(DisplayPoint::new(row, column), Bias::Left);
+                selections_to_process.push((cursor..cursor, false));
+                i += 1;
+                row.0 += 1;
+            }
+        }
 
-                if !action.preserve_clipboard && vim.state().mode.is_visual() {
-                    copy_selections_content(vim, editor, vim.state().mode == Mode::VisualLine, cx);
+        let first_selection_indent_column =
+            clipboard_selections.as_ref().and_then(|zed_selections| {
+                zed_selections
+                    .first()
+                    .map(|selection| selection.first_line_indent)
+            });
+        let before = action.before || vim.state().mode == Mode::VisualLine;
+        // A block put from a single cursor is a rectangle unless it
+        // was yanked to the end of each line, so its shorter rows are
+        // padded out to its width when there's text after them.
+        let block_width = clipboard_selections
+            .as_ref()
+            .filter(|_| !ragged_block && current_selections.len() == 1)
+            .and_then(|_| text.split('\n').map(|row| row.chars().count()).max());
+
+        let mut edits = Vec::new();
+        let mut new_selections = Vec::new();
+        let mut original_indent_columns = Vec::new();
+        let mut start_offset = 0;
+
+        for (ix, (selection, preserve)) in selections_to_process.iter().enumerate() {
+            let (mut to_insert, original_indent_column) =
+                if let Some(clipboard_selections) = &clipboard_selections {
+                    if let Some(clipboard_selection) = clipboard_selections.get(ix) {
+                        let end_offset = start_offset + clipboard_selection.len;
+                        let text = text[start_offset..end_offset].to_string();
+                        start_offset = end_offset + 1;
+                        (text, Some(clipboard_selection.first_line_indent))
+                    } else {
+                        ("".to_string(), first_selection_indent_column)
+                    }
+                } else {
+                    (text.to_string(), first_selection_indent_column)
+                };
+            let line_mode = to_insert.ends_with('\n');
+            let is_multiline = to_insert.contains('\n');
+
+            if line_mode && !before {
+                if selection.is_empty() {
+                    to_insert = "\n".to_owned() + &to_insert[..to_insert.len() - "\n".len()];
+                } else {
+                    to_insert = "\n".to_owned() + &to_insert;
                 }
+            } else if !line_mode && vim.state().mode == Mode::VisualLine {
+                to_insert = to_insert + "\n";
+            }
 
-                let (display_map, current_selections) = editor.selections.all_adjusted_display(cx);
-
-                // unlike zed, if you have a multi-cursor selection from vim block mode,
-                // pasting it will paste it on subsequent lines, even if you don't yet
-                // have a cursor there.
-                let mut selections_to_process = Vec::new();
-                let mut i = 0;
-                while i < current_selections.len() {
-                    selections_to_process
-                        .push((current_selections[i].start..current_selections[i].end, true));
-                    i += 1;
+            let display_range = if !selection.is_empty() {
+                selection.start..selection.end
+            } else if line_mode {
+                let point = if before {
+                    movement::line_beginning(&display_map, selection.start, false)
+                } else {
+                    movement::line_end(&display_map, selection.start, false)
+                };
+                point..point
+            } else {
+                let point = if before {
+                    selection.start
+                } else {
+                    movement::saturating_right(&display_map, selection.start)
+                };
+                point..point
+            };
+
+            // Lines too short to reach the block's column are padded out
+            // to it rather than having their row shifted left.
+            let mut padding = String::new();
+            if let Some(left) = left.filter(|_| !*preserve && !line_mode) {
+                let column = if before { left } else { left + 1 };
+                let line_len = display_map.line_len(display_range.start.row());
+                if line_len < column {
+                    padding = " ".repeat((column - line_len) as usize);
                 }
-                if let Some(clipboard_selections) = clipboard_selections.as_ref() {
-                    let left = current_selections
-                        .iter()
-                        .map(|selection| cmp::min(selection.start.column(), selection.end.column()))
-                        .min()
-                        .unwrap();
-                    let mut row = current_selections.last().unwrap().end.row().next_row();
-                    while i < clipboard_selections.len() {
-                        let cursor =
-                            display_map.clip_point(DisplayPoint::new(row, left), Bias::Left);
-                        selections_to_process.push((cursor..cursor, false));
-                        i += 1;
-                        row.0 += 1;
-                    }
+            }
+            if let Some(width) = block_width.filter(|_| !line_mode) {
+                let row_width = to_insert.chars().count();
+                let line_len = display_map.line_len(display_range.end.row());
+                let from_register = clipboard_selections
+                    .as_ref()
+                    .map_or(false, |sels| ix < sels.len());
+                if from_register && row_width < width && display_range.end.column() < line_len {
+                    to_insert.push_str(&" ".repeat(width - row_width));
                 }
+            }
 
-                let first_selection_indent_column =
-                    clipboard_selections.as_ref().and_then(|zed_selections| {
-                        zed_selections
-                            .first()
-                            .map(|selection| selection.first_line_indent)
-                    });
-                let before = action.before || vim.state().mode == Mode::VisualLine;
-
-                let mut edits = Vec::new();
-                let mut new_selections = Vec::new();
-                let mut original_indent_columns = Vec::new();
-                let mut start_offset = 0;
-
-                for (ix, (selection, preserve)) in selections_to_process.iter().enumerate() {
-                    let (mut to_insert, original_indent_column) =
-                        if let Some(clipboard_selections) = &clipboard_selections {
-                            if let Some(clipboard_selection) = clipboard_selections.get(ix) {
-                                let end_offset = start_offset + clipboard_selection.len;
-                                let text = text[start_offset..end_offset].to_string();
-                                start_offset = end_offset + 1;
-                                (text, Some(clipboard_selection.first_line_indent))
-                            } else {
-                                ("".to_string(), first_selection_indent_column)
-                            }
-                        } else {
-                            (text.to_string(), first_selection_indent_column)
-                        };
-                    let line_mode = to_insert.ends_with('\n');
-                    let is_multiline = to_insert.contains('\n');
-
-                    if line_mode && !before {
-                        if selection.is_empty() {
-                            to_insert =
-                                "\n".to_owned() + &to_insert[..to_insert.len() - "\n".len()];
-                        } else {
-                            to_insert = "\n".to_owned() + &to_insert;
+            let point_range = display_range.start.to_point(&display_map)
+                ..display_range.end.to_point(&display_map);
+            let anchor = if is_multiline || vim.state().mode == Mode::VisualLine {
+                display_map.buffer_snapshot.anchor_before(point_range.start)
+            } else {
+                display_map.buffer_snapshot.anchor_after(point_range.end)
+            };
+
+            if *preserve {
+                let end_anchor = display_map.buffer_snapshot.anchor_after(point_range.end);
+                new_selections.push((anchor, end_anchor, line_mode, is_multiline));
+            }
+            edits.push((point_range, padding + &to_insert.repeat(count)));
+            original_indent_columns.extend(original_indent_column);
+        }
+
+        editor.edit_with_block_indent(edits, original_indent_columns, cx);
+
+        // in line_mode vim will insert the new text on the next (or previous if before) line
+        // and put the cursor on the first non-blank character of the first inserted line (or at the end if the first line is blank).
+        // otherwise vim will insert the next text at (or before) the current cursor position,
+        // the cursor will go to the last (or first, if is_multiline) inserted character.
+        editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+            s.replace_cursors_with(|map| {
+                let mut cursors = Vec::new();
+                for (anchor, end_anchor, line_mode, is_multiline) in &new_selections {
+                    let mut cursor = anchor.to_display_point(map);
+                    if action.cursor_after {
+                        cursor = end_anchor.to_display_point(map);
+                        // linewise text pasted below ends at the end of a line,
+                        // so the cursor goes to the start of the next.
+                        if *line_mode && !before {
+                            cursor = map.clip_point(
+                                DisplayPoint::new(cursor.row().next_row(), 0),
+                                Bias::Left,
+                            );
                         }
-                    } else if !line_mode && vim.state().mode == Mode::VisualLine {
-                        to_insert = to_insert + "\n";
+                    } else if *line_mode {
+                        if !before {
+                            cursor = movement::down(
+                                map,
+                                cursor,
+                                SelectionGoal::None,
+                                false,
+                                &text_layout_details,
+                            )
+                            .0;
+                        }
+                        cursor = movement::indented_line_beginning(map, cursor, true);
+                    } else if !is_multiline {
+                        cursor = movement::saturating_left(map, cursor)
                     }
-
-                    let display_range = if !selection.is_empty() {
-                        selection.start..selection.end
-                    } else if line_mode {
-                        let point = if before {
-                            movement::line_beginning(&display_map, selection.start, false)
-                        } else {
-                            movement::line_end(&display_map, selection.start, false)
-                        };
-                        point..point
-                    } else {
-                        let point = if before {
-                            selection.start
-                        } else {
-                            movement::saturating_right(&display_map, selection.start)
-                        };
-                        point..point
-                    };
-
-                    let point_range = display_range.start.to_point(&display_map)
-                        ..display_range.end.to_point(&display_map);
-                    let anchor = if is_multiline || vim.state().mode == Mode::VisualLine {
-                        display_map.buffer_snapshot.anchor_before(point_range.start)
-                    } else {
-                        display_map.buffer_snapshot.anchor_after(point_range.end)
-                    };
-
-                    if *preserve {
-                        new_selections.push((anchor, line_mode, is_multiline));
+                    cursors.push(cursor);
+                    if vim.state().mode == Mode::VisualBlock {
+                        break;
                     }
-                    edits.push((point_range, to_insert.repeat(count)));
-                    original_indent_columns.extend(original_indent_column);
                 }
 
-                editor.edit_with_block_indent(edits, original_indent_columns, cx);
-
-                // in line_mode vim will insert the new text on the next (or previous if before) line
-                // and put the cursor on the first non-blank character of the first inserted line (or at the end if the first line is blank).
-                // otherwise vim will insert the next text at (or before) the current cursor position,
-                // the cursor will go to the last (or first, if is_multiline) inserted character.
-                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                    s.replace_cursors_with(|map| {
-                        let mut cursors = Vec::new();
-                        for (anchor, line_mode, is_multiline) in &new_selections {
-                            let mut cursor = anchor.to_display_point(map);
-                            if *line_mode {
-                                if !before {
-                                    cursor = movement::down(
-                                        map,
-                                        cursor,
-                                        SelectionGoal::None,
-                                        false,
-                                        &text_layout_details,
-                                    )
-                                    .0;
-                                }
-                                cursor = movement::indented_line_beginning(map, cursor, true);
-                            } else if !is_multiline {
-                                cursor = movement::saturating_left(map, cursor)
-                            }
-                            cursors.push(cursor);
-                            if vim.state().mode == Mode::VisualBlock {
-                                break;
-                            }
-                        }
-
-                        cursors
-                    });
-                })
+                cursors
             });
-        });
-        vim.switch_mode(Mode::Normal, true, cx);
+        })
     });
 }
 
 #[cfg(test)]
 mod test {
+    use std::{cell::RefCell, rc::Rc};
+
     use crate::{
+        clipboard::{ClipboardBackend, ClipboardKind, Osc52Clipboard},
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
-        UseSystemClipboard, VimSettings,
+        UseSystemClipboard, Vim, VimSettings,
     };
-    use gpui::ClipboardItem;
+    use editor::Editor;
+    use gpui::{ClipboardItem, UpdateGlobal};
     use indoc::indoc;
     use settings::SettingsStore;
 
@@ -290,9 +430,54 @@ mod test {
             the lazy dog"});
     }
 
+    #[gpui::test]
+    async fn test_paste_cursor_after(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // multiline, cursor just after the pasted text
+        cx.set_shared_state(indoc! {"
+            The quick brown
+            fox jumps ˇover
+            the lazy dog"})
+            .await;
+        cx.simulate_shared_keystrokes("v j y g p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            fox jumps oover
+            the lazy doˇver
+            the lazy dog"});
+        cx.simulate_shared_keystrokes("u g shift-p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            The quick brown
+            fox jumps over
+            the lazy doˇover
+            the lazy dog"});
+
+        // line mode, cursor on the line after the pasted lines
+        cx.set_shared_state(indoc! {"
+            ˇone
+            two
+            three"})
+            .await;
+        cx.simulate_shared_keystrokes("y y g p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            one
+            one
+            ˇtwo
+            three"});
+        cx.simulate_shared_keystrokes("g shift-p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            one
+            one
+            one
+            ˇtwo
+            three"});
+    }
+
     #[gpui::test]
     async fn test_yank_system_clipboard_never(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
+        let clipboard = cx.use_test_clipboard();
 
         cx.update_global(|store: &mut SettingsStore, cx| {
             store.update_user_settings::<VimSettings>(cx, |s| {
@@ -323,12 +508,13 @@ mod test {
                 the lazy dog"},
             Mode::Normal,
         );
-        assert_eq!(cx.read_from_clipboard(), None);
+        assert_eq!(clipboard.text(ClipboardKind::Clipboard), None);
     }
 
     #[gpui::test]
     async fn test_yank_system_clipboard_on_yank(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
+        let clipboard = cx.use_test_clipboard();
 
         cx.update_global(|store: &mut SettingsStore, cx| {
             store.update_user_settings::<VimSettings>(cx, |s| {
@@ -361,8 +547,7 @@ mod test {
             Mode::Normal,
         );
         assert_eq!(
-            cx.read_from_clipboard()
-                .map(|item| item.text().unwrap().to_string()),
+            clipboard.text(ClipboardKind::Clipboard),
             Some("jumps".into())
         );
         cx.simulate_keystrokes("d d p");
@@ -374,11 +559,10 @@ mod test {
             Mode::Normal,
         );
         assert_eq!(
-            cx.read_from_clipboard()
-                .map(|item| item.text().unwrap().to_string()),
+            clipboard.text(ClipboardKind::Clipboard),
             Some("jumps".into())
         );
-        cx.write_to_clipboard(ClipboardItem::new_string("test-copy".to_string()));
+        clipboard.set_text(ClipboardKind::Clipboard, "test-copy");
         cx.simulate_keystrokes("shift-p");
         cx.assert_state(
             indoc! {"
@@ -389,6 +573,86 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_clipboard_and_primary_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let clipboard = cx.use_test_clipboard();
+
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes("\" + y w w \" * y w");
+        assert_eq!(
+            clipboard.text(ClipboardKind::Clipboard),
+            Some("one ".into())
+        );
+        assert_eq!(clipboard.text(ClipboardKind::Primary), Some("two".into()));
+
+        clipboard.set_text(ClipboardKind::Primary, "middle");
+        cx.simulate_keystrokes("\" * shift-p");
+        cx.assert_state("one middlˇetwo", Mode::Normal);
+        cx.simulate_keystrokes("0 \" + p");
+        cx.assert_state("oone ˇne middletwo", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_osc52_clipboard(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let sequences = Rc::new(RefCell::new(Vec::new()));
+        let output = sequences.clone();
+        cx.update(|cx| {
+            Vim::update_global(cx, |vim, _| {
+                vim.osc52_clipboard =
+                    Osc52Clipboard::new(move |sequence| output.borrow_mut().push(sequence.into()))
+            })
+        });
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.clipboard = Some(ClipboardBackend::Osc52)
+            });
+        });
+
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes("\" + y w w \" * y w");
+        assert_eq!(
+            *sequences.borrow(),
+            ["\x1b]52;c;b25lIA==\x07", "\x1b]52;p;dHdv\x07"]
+        );
+
+        // the terminal isn't asked for its clipboard, so the registers paste
+        // what was last written to them
+        cx.simulate_keystrokes("0 \" + p");
+        cx.assert_state("oone ˇne two", Mode::Normal);
+        cx.simulate_keystrokes("\" * shift-p");
+        cx.assert_state("oone twˇone two", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_osc52_clipboard_without_terminal(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update(|cx| {
+            Vim::update_global(cx, |vim, _| {
+                vim.osc52_clipboard = Osc52Clipboard::without_terminal()
+            })
+        });
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.clipboard = Some(ClipboardBackend::Osc52)
+            });
+        });
+
+        // with nowhere to send escape sequences, the system clipboard is used
+        cx.set_state("ˇone two", Mode::Normal);
+        cx.simulate_keystrokes("\" + y w");
+        assert_eq!(
+            cx.read_from_clipboard()
+                .map(|item| item.text().unwrap().to_string()),
+            Some("one ".to_string())
+        );
+
+        cx.write_to_clipboard(ClipboardItem::new_string("copied".to_string()));
+        cx.simulate_keystrokes("\" + p");
+        cx.assert_state("ocopieˇdne two", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_paste_visual(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -504,6 +768,106 @@ mod test {
             the lzy dog"});
     }
 
+    #[gpui::test]
+    async fn test_paste_visual_block_rectangle(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // short lines are padded out to the block's column
+        cx.set_shared_state("ˇab12\ncd34\n\nxyz\nw").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l y j j j l p")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("ab12\ncd34\n\nxyˇabz\nw cd");
+
+        // and lines are added after the end of the buffer
+        cx.set_shared_state("ˇab\ncd\nx").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l y j j p").await;
+        cx.shared_state().await.assert_eq("ab\ncd\nxˇab\n cd");
+
+        // rows of the block that are short are padded out to its width
+        cx.set_shared_state("ˇab\n\nab\nxyz\nxyz\nxyz").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j l y 3 j p").await;
+        cx.shared_state()
+            .await
+            .assert_eq("ab\n\nab\nxˇabyz\nx  yz\nxabyz");
+    }
+
+    #[gpui::test]
+    async fn test_paste_deleted_visual_block(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {"
+            ˇabcd
+            efgh
+            ijkl
+            mnop"})
+            .await;
+        cx.simulate_shared_keystrokes("ctrl-v j j l d").await;
+        cx.shared_clipboard().await.assert_eq("ab\nef\nij");
+        cx.shared_state().await.assert_eq(indoc! {"
+            ˇcd
+            gh
+            kl
+            mnop"});
+        cx.simulate_shared_keystrokes("l p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            cdˇab
+            ghef
+            klij
+            mnop"});
+
+        // a single row is put characterwise
+        cx.simulate_shared_keystrokes("j j j ctrl-v l d 0 p").await;
+        cx.shared_state().await.assert_eq(indoc! {"
+            cdab
+            ghef
+            klij
+            moˇpn"});
+
+        // lines too short to reach the block are still rows of it
+        cx.set_shared_state("ˇabc\n\nabc").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j l d").await;
+        cx.shared_clipboard().await.assert_eq("ab\n\nab");
+        cx.shared_state().await.assert_eq("ˇc\n\nc");
+    }
+
+    #[gpui::test]
+    async fn test_paste_visual_block_to_end_of_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state(
+            indoc! {"
+                ˇone two
+                three
+                four five six
+                abc
+
+                a"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("ctrl-v j j $ y");
+        assert_eq!(
+            cx.read_from_clipboard()
+                .map(|item| item.text().unwrap().to_string()),
+            Some("one two\nthree\nfour five six".to_string())
+        );
+
+        // each row keeps its own length, and lines that are too short are
+        // padded out to the column the block is pasted at.
+        cx.simulate_keystrokes("3 j l p");
+        cx.assert_state(
+            indoc! {"
+                one two
+                three
+                four five six
+                abone twˇoc
+                  three
+                a four five six"},
+            Mode::Normal,
+        );
+    }
+
     #[gpui::test]
     async fn test_paste_indent(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new_typescript(cx).await;
@@ -658,6 +1022,62 @@ mod test {
         cx.shared_register('a').await.assert_eq(" over");
     }
 
+    #[gpui::test]
+    async fn test_black_hole_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let register = |cx: &mut VimTestContext, name: char| {
+            cx.update(|cx| {
+                Vim::read(cx)
+                    .workspace_state
+                    .registers
+                    .get(&name)
+                    .map(|register| register.text.to_string())
+            })
+        };
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("y y j \" _ d d");
+        cx.assert_state("one\nˇthree", Mode::Normal);
+        assert_eq!(register(&mut cx, '"'), Some("one\n".into()));
+        assert_eq!(register(&mut cx, '1'), None);
+
+        cx.simulate_keystrokes("\" _ d w");
+        assert_eq!(register(&mut cx, '"'), Some("one\n".into()));
+        assert_eq!(register(&mut cx, '-'), None);
+    }
+
+    #[gpui::test]
+    async fn test_report_registers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let status_message = |cx: &mut VimTestContext| {
+            cx.update(|cx| Vim::read(cx).workspace_state.status_message.clone())
+        };
+
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("\" a y y");
+        assert_eq!(status_message(&mut cx), None);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.report_registers = Some(true));
+        });
+        cx.simulate_keystrokes("\" a y y");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("yanked into register a".into())
+        );
+        cx.simulate_keystrokes("\" shift-b d d");
+        assert_eq!(
+            status_message(&mut cx),
+            Some("deleted into register B".into())
+        );
+
+        // the unnamed register isn't worth mentioning
+        cx.simulate_keystrokes("y y");
+        assert_eq!(status_message(&mut cx), None);
+        cx.simulate_keystrokes("\" \" y y");
+        assert_eq!(status_message(&mut cx), None);
+    }
+
     #[gpui::test]
     async fn test_special_registers(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -706,6 +1126,29 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_alternate_file_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let fs = cx.workspace(|workspace, cx| workspace.project().read(cx).fs().clone());
+        fs.as_fake()
+            .insert_file("/root/dir/other.rs", Vec::new())
+            .await;
+
+        // there's no alternate file until another has been opened
+        cx.simulate_keystrokes("\" # p");
+        cx.assert_state("ˇ", Mode::Normal);
+
+        let other = cx
+            .workspace(|workspace, cx| {
+                workspace.open_abs_path("/root/dir/other.rs".into(), true, cx)
+            })
+            .await
+            .unwrap();
+        cx.simulate_keystrokes("\" # p \" % p");
+        let text = cx.update(|cx| other.act_as::<Editor>(cx).unwrap().read(cx).text(cx));
+        assert_eq!(text, "dir/file.rsdir/other.rs");
+    }
+
     #[gpui::test]
     async fn test_multicursor_paste(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -736,4 +1179,28 @@ mod test {
             Mode::Normal,
         );
     }
+
+    #[gpui::test]
+    async fn test_cycle_paste(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two three", Mode::Normal);
+        cx.simulate_keystrokes("y e w y e w y e $ p");
+        cx.assert_state("one two threethreˇe", Mode::Normal);
+
+        // older entries first, wrapping around to the newest
+        cx.simulate_keystrokes("ctrl-p");
+        cx.assert_state("one two threetwˇo", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-p");
+        cx.assert_state("one two threeonˇe", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-p");
+        cx.assert_state("one two threethreˇe", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-n");
+        cx.assert_state("one two threeonˇe", Mode::Normal);
+
+        // the replaced paste is undone along with the one that replaced it
+        cx.simulate_keystrokes("u");
+        cx.assert_state("one two threˇe", Mode::Normal);
+        cx.update(|cx| assert!(Vim::read(cx).workspace_state.last_paste.is_none()));
+    }
 }
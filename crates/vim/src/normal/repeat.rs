@@ -1,17 +1,31 @@
 use std::{cell::RefCell, ops::Range, rc::Rc, sync::Arc};
 
 use crate::{
-    insert::NormalBefore,
+    command::enter_ex_mode,
+    insert::{self, NormalBefore},
     motion::Motion,
     state::{Mode, Operator, RecordedSelection, ReplayableAction},
     visual::visual_motion,
-    Vim,
+    QBehavior, Vim, VimSettings,
 };
-use gpui::{actions, Action, ViewContext, WindowContext};
+use editor::Editor;
+use gpui::{actions, Action, AppContext, ViewContext, WindowContext};
+use language::{Point, SelectionGoal};
+use multi_buffer::MultiBufferRow;
+use settings::Settings;
 use util::ResultExt;
 use workspace::Workspace;
 
-actions!(vim, [Repeat, EndRepeat, ToggleRecord, ReplayLastRecording]);
+actions!(
+    vim,
+    [
+        Repeat,
+        EndRepeat,
+        ToggleRecord,
+        ReplayLastRecording,
+        ReplayLastRecordingOrExMode
+    ]
+);
 
 fn should_replay(action: &dyn Action) -> bool {
     // skip so that we don't leave the character palette open
@@ -28,6 +42,7 @@ fn repeatable_insert(action: &ReplayableAction) -> Option<Box<dyn Action>> {
                 || super::InsertAfter.partial_eq(&**action)
                 || super::InsertFirstNonWhitespace.partial_eq(&**action)
                 || super::InsertEndOfLine.partial_eq(&**action)
+                || super::InsertAtPrevious.partial_eq(&**action)
             {
                 Some(super::InsertBefore.boxed_clone())
             } else if super::InsertLineAbove.partial_eq(&**action)
@@ -64,13 +79,25 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
     });
 
     workspace.register_action(|_: &mut Workspace, _: &ReplayLastRecording, cx| {
-        let Some(register) = Vim::read(cx).workspace_state.last_recorded_register else {
-            return;
-        };
-        replay_register(register, cx)
+        replay_last_recording(cx)
+    });
+
+    // `Q`, which does either, as `q_behavior` says.
+    workspace.register_action(|workspace, _: &ReplayLastRecordingOrExMode, cx| {
+        match VimSettings::get_global(cx).q_behavior {
+            QBehavior::Ex => enter_ex_mode(workspace, cx),
+            QBehavior::LastMacro => replay_last_recording(cx),
+        }
     });
 }
 
+fn replay_last_recording(cx: &mut WindowContext) {
+    let Some(register) = Vim::read(cx).workspace_state.last_recorded_register else {
+        return;
+    };
+    replay_register(register, cx)
+}
+
 pub struct ReplayerState {
     actions: Vec<ReplayableAction>,
     running: bool,
@@ -123,7 +150,10 @@ impl Replayer {
             ReplayableAction::Action(action) => {
                 if should_replay(&*action) {
                     cx.dispatch_action(action.boxed_clone());
-                    cx.defer(move |cx| observe_action(action.boxed_clone(), cx));
+                    cx.defer(move |cx| {
+                        observe_action(action.boxed_clone(), cx);
+                        insert::resume_insert(&*action, cx);
+                    });
                 }
             }
             ReplayableAction::Insertion {
@@ -143,6 +173,42 @@ impl Replayer {
     }
 }
 
+/// Records the size of a visual block so that `.` selects a block of the same
+/// size down and to the right of the cursor, whichever corner was used to
+/// extend it. Its width is counted in characters, as `.` moves right by them.
+pub(crate) fn recorded_block(editor: &Editor, cx: &AppContext) -> RecordedSelection {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let selections = editor.selections.all::<Point>(cx);
+    let (Some(first), Some(last)) = (selections.first(), selections.last()) else {
+        return RecordedSelection::None;
+    };
+    let rows = last.end.row - first.start.row;
+    RecordedSelection::VisualBlock {
+        rows,
+        cols: selections
+            .iter()
+            .map(|selection| {
+                snapshot
+                    .text_for_range(selection.range())
+                    .flat_map(str::chars)
+                    .count() as u32
+            })
+            .max()
+            .unwrap_or_default(),
+        to_end_of_line: block_to_end_of_line(editor),
+    }
+}
+
+/// Whether the visual block was extended with `$`, so that each row is
+/// selected up to its own end of line rather than to a shared column.
+pub(crate) fn block_to_end_of_line(editor: &Editor) -> bool {
+    // After `$` the block's goal extends every row to its own end of line.
+    matches!(
+        editor.selections.newest_anchor().goal,
+        SelectionGoal::HorizontalRange { end, .. } if end == f32::INFINITY
+    )
+}
+
 pub(crate) fn record_register(register: char, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         vim.workspace_state.recording_register = Some(register);
@@ -190,6 +256,11 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
         }
 
         let count = vim.take_count(cx);
+        // As with the count, a register given to `.` replaces the recorded one.
+        let register = vim
+            .update_state(|state| state.selected_register.take())
+            .or(vim.workspace_state.recorded_register);
+        vim.workspace_state.recorded_register = register;
 
         let selection = vim.workspace_state.recorded_selection.clone();
         match selection {
@@ -211,6 +282,7 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
                 }
             }
         }
+        vim.update_state(|state| state.selected_register = register);
 
         if vim.workspace_state.replayer.is_none() {
             if let Some(recording_register) = vim.workspace_state.recording_register {
@@ -252,7 +324,11 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
                 visual_motion(Motion::Right, Some(cols as usize - 1), cx)
             }
         }
-        RecordedSelection::VisualBlock { rows, cols } => {
+        RecordedSelection::VisualBlock {
+            rows,
+            cols,
+            to_end_of_line,
+        } => {
             visual_motion(
                 Motion::Down {
                     display_lines: false,
@@ -260,7 +336,15 @@ pub(crate) fn repeat(cx: &mut WindowContext, from_insert_mode: bool) {
                 Some(rows as usize),
                 cx,
             );
-            if cols > 1 {
+            if to_end_of_line {
+                visual_motion(
+                    Motion::EndOfLine {
+                        display_lines: false,
+                    },
+                    None,
+                    cx,
+                );
+            } else if cols > 1 {
                 visual_motion(Motion::Right, Some(cols as usize - 1), cx);
             }
         }
@@ -381,11 +465,14 @@ mod test {
     use futures::StreamExt;
     use indoc::indoc;
 
+    use command_palette::CommandPalette;
     use gpui::ViewInputHandler;
+    use settings::SettingsStore;
 
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        QBehavior, Vim, VimSettings,
     };
 
     #[gpui::test]
@@ -618,6 +705,118 @@ mod test {
         });
     }
 
+    #[gpui::test]
+    async fn test_repeat_visual_block_insert(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // the block is the same size, wherever the cursor is moved to
+        cx.set_state("ˇone\ntwo\nthree\nfour\nfive\nsix", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j j shift-i x escape");
+        cx.assert_state("ˇxone\nxtwo\nxthree\nfour\nfive\nsix", Mode::Normal);
+        cx.simulate_keystrokes("3 j .");
+        cx.assert_state("xone\nxtwo\nxthree\nˇxfour\nxfive\nxsix", Mode::Normal);
+
+        cx.set_state("ˇabc\ndef\nghi\njkl\nmno", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l shift-a - escape");
+        cx.assert_state("ˇab-c\nde-f\nghi\njkl\nmno", Mode::Normal);
+        cx.simulate_keystrokes("3 j .");
+        cx.assert_state("ab-c\nde-f\nghi\nˇjk-l\nmn-o", Mode::Normal);
+
+        cx.set_state("ˇabc\ndef\nghi\njkl\nmno", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l c x escape");
+        cx.assert_state("ˇxc\nxf\nghi\njkl\nmno", Mode::Normal);
+        cx.simulate_keystrokes("3 j .");
+        cx.assert_state("xc\nxf\nghi\nˇxl\nxo", Mode::Normal);
+
+        // the block's width is in characters rather than bytes
+        cx.set_state("ˇäöx\näöx\nabcdef\nabcdef", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l d");
+        cx.assert_state("ˇx\nx\nabcdef\nabcdef", Mode::Normal);
+        cx.simulate_keystrokes("j j .");
+        cx.assert_state("x\nx\nˇcdef\ncdef", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_repeat_visual_block_other_end(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {
+            "thˇe quick brown
+            fox jumps over
+            the lazy dog
+            one two three"
+        })
+        .await;
+        cx.simulate_shared_keystrokes("ctrl-v j h h shift-o l d")
+            .await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "ˇquick brown
+            jumps over
+            the lazy dog
+            one two three"
+        });
+        cx.simulate_shared_keystrokes("j j .").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "quick brown
+            jumps over
+            ˇlazy dog
+            two three"
+        });
+
+        // extending upwards from the bottom corner
+        cx.set_shared_state(indoc! {
+            "the quick brown
+            fox ˇjumps over
+            the lazy dog
+            one two three"
+        })
+        .await;
+        cx.simulate_shared_keystrokes("ctrl-v k l shift-o d").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "the ˇick brown
+            fox mps over
+            the lazy dog
+            one two three"
+        });
+        cx.simulate_shared_keystrokes("j j .").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "the ick brown
+            fox mps over
+            the ˇzy dog
+            one o three"
+        });
+    }
+
+    #[gpui::test]
+    async fn test_repeat_visual_block_to_end_of_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {
+            "theˇ quick
+            fox jumps over
+            the lazy dog
+            one two three four
+            "
+        })
+        .await;
+        cx.simulate_shared_keystrokes("ctrl-v j $ d").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "thˇe
+            fox
+            the lazy dog
+            one two three four
+            "
+        });
+        cx.simulate_shared_keystrokes("j j .").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "the
+            fox
+            tˇh
+            on
+            "
+        });
+    }
+
     #[gpui::test]
     async fn test_repeat_motion_counts(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -648,6 +847,67 @@ mod test {
         });
     }
 
+    #[gpui::test]
+    async fn test_repeat_with_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇone two three four five six seven eight nine")
+            .await;
+        cx.simulate_shared_keystrokes("2 d w").await;
+        cx.shared_state()
+            .await
+            .assert_eq("ˇthree four five six seven eight nine");
+        // without a count the recorded one is used
+        cx.simulate_shared_keystrokes(".").await;
+        cx.shared_state()
+            .await
+            .assert_eq("ˇfive six seven eight nine");
+        // and a count replaces it
+        cx.simulate_shared_keystrokes("3 .").await;
+        cx.shared_state().await.assert_eq("ˇeight nine");
+    }
+
+    #[gpui::test]
+    async fn test_repeat_with_register(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let register = |cx: &mut VimTestContext, name: char| {
+            cx.update(|cx| {
+                Vim::read(cx)
+                    .workspace_state
+                    .registers
+                    .get(&name)
+                    .map(|register| register.text.to_string())
+            })
+        };
+
+        cx.set_state("ˇone\ntwo\nthree\nfour", Mode::Normal);
+        cx.simulate_keystrokes("\" a d d");
+        assert_eq!(register(&mut cx, 'a'), Some("one\n".into()));
+        cx.simulate_keystrokes(".");
+        cx.assert_state("ˇthree\nfour", Mode::Normal);
+        assert_eq!(register(&mut cx, 'a'), Some("two\n".into()));
+
+        // a register given to `.` is used instead
+        cx.simulate_keystrokes("\" b .");
+        cx.assert_state("ˇfour", Mode::Normal);
+        assert_eq!(register(&mut cx, 'a'), Some("two\n".into()));
+        assert_eq!(register(&mut cx, 'b'), Some("three\n".into()));
+        cx.simulate_keystrokes("\" a p");
+        cx.assert_state("four\nˇtwo", Mode::Normal);
+
+        // and an uppercase register is appended to on each repeat
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("\" shift-c d d .");
+        cx.assert_state("ˇthree", Mode::Normal);
+        assert_eq!(register(&mut cx, 'c'), Some("one\ntwo\n".into()));
+
+        // while without a register the unnamed one is used
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("d d .");
+        assert_eq!(register(&mut cx, '"'), Some("two\n".into()));
+        assert_eq!(register(&mut cx, 'a'), Some("two\n".into()));
+    }
+
     #[gpui::test]
     async fn test_record_interrupted(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -719,6 +979,52 @@ mod test {
         cx.shared_state().await.assert_eq("ababˇb world");
     }
 
+    #[gpui::test]
+    async fn test_q_behavior(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇhello world", Mode::Normal);
+        cx.simulate_keystrokes("q a r x l q");
+        cx.assert_state("xˇello world", Mode::Normal);
+        cx.simulate_keystrokes("shift-q");
+        cx.assert_state("xxˇllo world", Mode::Normal);
+        cx.simulate_keystrokes("2 shift-q");
+        cx.assert_state("xxxxˇo world", Mode::Normal);
+
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.q_behavior = Some(QBehavior::Ex));
+        });
+        let prompting = |cx: &mut VimTestContext| {
+            cx.workspace(|workspace, cx| workspace.active_modal::<CommandPalette>(cx).is_some())
+        };
+
+        // the command line comes back after each command
+        cx.set_state("ˇone\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("shift-q d enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇtwo\nthree", Mode::Normal);
+        assert!(prompting(&mut cx));
+        cx.simulate_keystrokes("d enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇthree", Mode::Normal);
+        assert!(prompting(&mut cx));
+
+        // until `:visual`
+        cx.simulate_keystrokes("v i enter");
+        cx.run_until_parked();
+        assert!(!prompting(&mut cx));
+
+        // or until it's closed without a command
+        cx.set_state("ˇone\ntwo", Mode::Normal);
+        cx.simulate_keystrokes("shift-q escape");
+        cx.run_until_parked();
+        assert!(!prompting(&mut cx));
+        cx.simulate_keystrokes(": d enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇtwo", Mode::Normal);
+        assert!(!prompting(&mut cx));
+    }
+
     #[gpui::test]
     async fn test_record_replay_of_dot(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1,11 +1,13 @@
-use crate::Vim;
+use crate::{motion::first_non_whitespace, Vim};
 use editor::{
+    actions::{ScrollCursorBottom, ScrollCursorCenter, ScrollCursorTop},
     display_map::{DisplayRow, ToDisplayPoint},
     scroll::ScrollAmount,
     DisplayPoint, Editor, EditorSettings,
 };
-use gpui::{actions, ViewContext};
-use language::Bias;
+use gpui::{actions, impl_actions, ViewContext};
+use language::{Bias, Point};
+use serde::Deserialize;
 use settings::Settings;
 use workspace::Workspace;
 
@@ -14,6 +16,28 @@ actions!(
     [LineUp, LineDown, ScrollUp, ScrollDown, PageUp, PageDown]
 );
 
+#[derive(Clone, Copy, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+enum CursorLine {
+    Top,
+    Center,
+    Bottom,
+}
+
+/// Scrolls so that the cursor's line, or the line given by the count, is at
+/// the top (`zt`), center (`zz`) or bottom (`zb`) of the screen. With
+/// `first_non_blank` the cursor also moves to the line's first non-blank
+/// character, as with `z<CR>`, `z.` and `z-`.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct ScrollCursor {
+    line: CursorLine,
+    #[serde(default)]
+    first_non_blank: bool,
+}
+
+impl_actions!(vim, [ScrollCursor]);
+
 pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, _: &LineDown, cx| {
         scroll(cx, false, |c| ScrollAmount::Line(c.unwrap_or(1.)))
@@ -45,6 +69,44 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             }
         })
     });
+    workspace.register_action(|_: &mut Workspace, action: &ScrollCursor, cx| {
+        Vim::update(cx, |vim, cx| {
+            let count = vim.take_count(cx);
+            vim.update_active_editor(cx, |_, editor, cx| scroll_cursor(editor, action, count, cx));
+        })
+    });
+}
+
+fn scroll_cursor(
+    editor: &mut Editor,
+    action: &ScrollCursor,
+    count: Option<usize>,
+    cx: &mut ViewContext<Editor>,
+) {
+    if count.is_some() || action.first_non_blank {
+        editor.change_selections(None, cx, |s| {
+            s.move_cursors_with(|map, cursor, goal| {
+                let mut cursor = cursor;
+                if let Some(count) = count {
+                    let max_row = map.buffer_snapshot.max_point().row;
+                    let row = (count as u32).saturating_sub(1).min(max_row);
+                    let column = cursor.to_point(map).column;
+                    cursor =
+                        map.clip_point(Point::new(row, column).to_display_point(map), Bias::Left);
+                }
+                if action.first_non_blank {
+                    (first_non_whitespace(map, false, cursor), Default::default())
+                } else {
+                    (cursor, goal)
+                }
+            })
+        });
+    }
+    match action.line {
+        CursorLine::Top => editor.scroll_cursor_top(&ScrollCursorTop, cx),
+        CursorLine::Center => editor.scroll_cursor_center(&ScrollCursorCenter, cx),
+        CursorLine::Bottom => editor.scroll_cursor_bottom(&ScrollCursorBottom, cx),
+    }
 }
 
 fn scroll(
@@ -141,10 +203,15 @@ mod test {
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        VimSettings,
     };
     use gpui::{point, px, size, Context};
     use indoc::indoc;
-    use language::Point;
+    use language::{
+        language_settings::{AllLanguageSettings, SoftWrap},
+        Point,
+    };
+    use settings::SettingsStore;
 
     #[gpui::test]
     async fn test_scroll(cx: &mut gpui::TestAppContext) {
@@ -235,6 +302,140 @@ mod test {
             )
         });
     }
+
+    #[gpui::test]
+    async fn test_scroll_cursor(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // 15 whole lines are visible, with the default scroll margin of 3
+        cx.set_scroll_height(15);
+
+        let text = (0..40)
+            .map(|row| match row {
+                14 => "  liˇne 14\n".to_string(),
+                _ => format!("  line {row}\n"),
+            })
+            .collect::<String>();
+        cx.set_state(&text, Mode::Normal);
+
+        cx.simulate_keystrokes("z z");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 7.))
+        });
+        cx.simulate_keystrokes("z t");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 11.))
+        });
+        cx.simulate_keystrokes("z b");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 2.));
+            assert_eq!(
+                editor.selections.newest(cx).range(),
+                Point::new(14, 4)..Point::new(14, 4)
+            )
+        });
+
+        // a count scrolls that line instead, keeping the column
+        cx.simulate_keystrokes("1 0 z t");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 6.));
+            assert_eq!(
+                editor.selections.newest(cx).range(),
+                Point::new(9, 4)..Point::new(9, 4)
+            )
+        });
+
+        // z<CR>, z. and z- also move to the first non-blank
+        cx.simulate_keystrokes("2 0 z enter");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 16.));
+            assert_eq!(
+                editor.selections.newest(cx).range(),
+                Point::new(19, 2)..Point::new(19, 2)
+            )
+        });
+        cx.simulate_keystrokes("l z .");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 12.));
+            assert_eq!(
+                editor.selections.newest(cx).range(),
+                Point::new(19, 2)..Point::new(19, 2)
+            )
+        });
+        cx.simulate_keystrokes("z -");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(editor.snapshot(cx).scroll_position(), point(0., 7.))
+        });
+    }
+
+    #[gpui::test]
+    async fn test_scroll_off(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.scroll_off = Some(5);
+                s.side_scroll_off = Some(10);
+            });
+        });
+        cx.set_scroll_height(15);
+        let visible_line_count = cx.editor(|editor, _| editor.visible_line_count().unwrap());
+        let scroll_position = |cx: &mut VimTestContext| {
+            cx.update_editor(|editor, cx| editor.snapshot(cx).scroll_position())
+        };
+
+        let mut text = (0..40).map(|row| format!("{row}\n")).collect::<String>();
+        text.insert(0, 'ˇ');
+        cx.set_state(&text, Mode::Normal);
+
+        // near the top of the file there's less to keep visible
+        cx.simulate_keystrokes("3 j");
+        assert_eq!(scroll_position(&mut cx), point(0., 0.));
+
+        // moving down keeps 5 lines below the cursor
+        cx.simulate_keystrokes("2 0 j");
+        let expected_top = 23. + 1. + 5. - visible_line_count;
+        assert!((scroll_position(&mut cx).y - expected_top).abs() < 0.01);
+
+        // and moving up keeps 5 lines above it
+        cx.simulate_keystrokes("z t");
+        assert_eq!(scroll_position(&mut cx), point(0., 18.));
+        cx.simulate_keystrokes("k");
+        assert_eq!(scroll_position(&mut cx), point(0., 17.));
+        cx.simulate_keystrokes("4 k");
+        assert_eq!(scroll_position(&mut cx), point(0., 13.));
+
+        // H and L stop short of the edges by as much
+        cx.simulate_keystrokes("shift-h");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(
+                editor.selections.newest::<Point>(cx).head(),
+                Point::new(18, 0)
+            )
+        });
+        cx.simulate_keystrokes("shift-l");
+        cx.update_editor(|editor, cx| {
+            assert_eq!(
+                editor.selections.newest::<Point>(cx).head(),
+                Point::new(22, 0)
+            )
+        });
+
+        // columns are kept visible to the left of the cursor
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<AllLanguageSettings>(cx, |s| {
+                s.defaults.soft_wrap = Some(SoftWrap::None)
+            });
+        });
+        cx.set_state(&format!("ˇ{}", "x".repeat(300)), Mode::Normal);
+        cx.simulate_keystrokes("$ 2 0 0 h");
+        let wide_margin_left = scroll_position(&mut cx).x;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.side_scroll_off = Some(3));
+        });
+        cx.simulate_keystrokes("0 $ 2 0 0 h");
+        assert!(scroll_position(&mut cx).x > wide_margin_left);
+    }
+
     #[gpui::test]
     async fn test_ctrl_d_u(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
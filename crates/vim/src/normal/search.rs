@@ -1,17 +1,27 @@
-use std::{iter::Peekable, str::Chars, time::Duration};
+use std::{iter::Peekable, ops::Range, str::Chars, time::Duration};
 
-use gpui::{actions, impl_actions, ViewContext};
-use language::Point;
+use anyhow::anyhow;
+use command_palette::CommandPalette;
+use editor::{
+    display_map::DisplayRow, Anchor, Bias, DisplayPoint, Editor, MultiBufferSnapshot, ToOffset,
+    ToPoint,
+};
+use gpui::{actions, impl_actions, AppContext, ViewContext, WindowContext};
+use language::{char_kind, CharKind, Point};
+use multi_buffer::MultiBufferRow;
+use regex::{Captures, RegexBuilder};
 use search::{buffer_search, BufferSearchBar, SearchOptions};
 use serde_derive::Deserialize;
+use settings::Settings;
 use workspace::{notifications::NotifyResultExt, searchable::Direction, Workspace};
 
 use crate::{
-    command::CommandRange,
-    motion::{search_motion, Motion},
+    command::{command_interceptor, push_history, CommandRange},
+    expression::evaluate_with_submatches,
+    motion::{coerce_punctuation, search_motion, Motion},
     normal::move_cursor,
-    state::{Mode, SearchState},
-    Vim,
+    state::{Mode, SearchOffset, SearchState},
+    Vim, VimSettings,
 };
 
 #[derive(Clone, Deserialize, PartialEq)]
@@ -52,12 +62,38 @@ pub(crate) struct Replacement {
     replacement: String,
     should_replace_all: bool,
     is_case_sensitive: bool,
+    is_global: bool,
+    /// Set by the `&` flag, which adds the flags of the last substitution.
+    keep_flags: bool,
+}
+
+/// Repeats the last `:s`, as `&`, `g&`, `:&` and `:&&` do.
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepeatSubstitute {
+    #[serde(default)]
+    pub(crate) range: Option<CommandRange>,
+    #[serde(default)]
+    pub(crate) whole_file: bool,
+    #[serde(default)]
+    pub(crate) keep_flags: bool,
+    /// Search for the last search pattern rather than the last substitute
+    /// pattern, as `g&` (`:%s//~/&`) does.
+    #[serde(default)]
+    pub(crate) last_search: bool,
 }
 
 actions!(vim, [SearchSubmit, MoveToNextMatch, MoveToPrevMatch]);
 impl_actions!(
     vim,
-    [FindCommand, ReplaceCommand, Search, MoveToPrev, MoveToNext]
+    [
+        FindCommand,
+        ReplaceCommand,
+        RepeatSubstitute,
+        Search,
+        MoveToPrev,
+        MoveToNext
+    ]
 );
 
 pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
@@ -71,6 +107,7 @@ pub(crate) fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>
 
     workspace.register_action(find_command);
     workspace.register_action(replace_command);
+    workspace.register_action(repeat_substitute);
 }
 
 fn move_to_next(workspace: &mut Workspace, action: &MoveToNext, cx: &mut ViewContext<Workspace>) {
@@ -122,6 +159,12 @@ fn search(workspace: &mut Workspace, action: &Search, cx: &mut ViewContext<Works
                         search_bar.set_replacement(None, cx);
                         search_bar.set_search_options(SearchOptions::REGEX, cx);
                     }
+                    let mut options = search_bar.search_options();
+                    options.set(
+                        SearchOptions::CASE_SENSITIVE,
+                        !VimSettings::get_global(cx).ignorecase,
+                    );
+                    search_bar.set_search_options(options, cx);
                     vim.update_state(|state| {
                         state.search = SearchState {
                             direction,
@@ -130,6 +173,7 @@ fn search(workspace: &mut Workspace, action: &Search, cx: &mut ViewContext<Works
                             prior_selections,
                             prior_operator: state.operator_stack.last().cloned(),
                             prior_mode: state.mode,
+                            offset: state.search.offset,
                         }
                     });
                 });
@@ -147,6 +191,64 @@ fn search_deploy(_: &mut Workspace, _: &buffer_search::Deploy, cx: &mut ViewCont
 }
 
 fn search_submit(workspace: &mut Workspace, _: &SearchSubmit, cx: &mut ViewContext<Workspace>) {
+    // An offset such as the `e` in `/foo/e`, or a `\c` in the pattern, is not
+    // part of what the search bar should look for, so search again for just
+    // the pattern before moving to the match.
+    let pane = workspace.active_pane().clone();
+    let search = Vim::update(cx, |vim, cx| {
+        pane.update(cx, |pane, cx| {
+            let search_bar = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>()?;
+            search_bar.update(cx, |search_bar, cx| {
+                let query = search_bar.query(cx);
+                push_history(&mut vim.workspace_state.search_history, &query);
+                let delimiter = match vim.state().search.direction {
+                    Direction::Next => '/',
+                    Direction::Prev => '?',
+                };
+                let pattern = match split_search_offset(&query, delimiter) {
+                    // As in vim, `//e` reuses the last pattern.
+                    Some(("", offset)) => {
+                        vim.update_state(|state| state.search.offset = offset);
+                        vim.workspace_state
+                            .registers
+                            .get(&'/')
+                            .map(|register| register.text.to_string())
+                            .unwrap_or_default()
+                    }
+                    Some((pattern, offset)) => {
+                        vim.update_state(|state| state.search.offset = offset);
+                        pattern.to_string()
+                    }
+                    None => {
+                        if query != vim.state().search.initial_query {
+                            vim.update_state(|state| state.search.offset = SearchOffset::None);
+                        }
+                        query.clone()
+                    }
+                };
+                let (pattern, case_sensitive) = case_sensitivity(&pattern, true, cx);
+                let mut options = search_bar.search_options();
+                options.set(SearchOptions::CASE_SENSITIVE, case_sensitive);
+                if pattern == query && options == search_bar.search_options() {
+                    return None;
+                }
+                Some(search_bar.search(&pattern, Some(options), cx))
+            })
+        })
+    });
+
+    if let Some(search) = search {
+        cx.spawn(|workspace, mut cx| async move {
+            search.await?;
+            workspace.update(&mut cx, |workspace, cx| submit_search(workspace, cx))
+        })
+        .detach_and_log_err(cx);
+    } else {
+        submit_search(workspace, cx);
+    }
+}
+
+fn submit_search(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
     let mut motion = None;
     Vim::update(cx, |vim, cx| {
         vim.store_visual_marks(cx);
@@ -180,7 +282,8 @@ fn search_submit(workspace: &mut Workspace, _: &SearchSubmit, cx: &mut ViewConte
                         .registers
                         .insert('/', search_bar.query(cx).into());
 
-                    let new_selections = vim.editor_selections(cx);
+                    let offset = vim.state().search.offset;
+                    let new_selections = apply_search_offset(vim, offset, cx);
 
                     // If the active editor has changed during a search, don't panic.
                     if prior_selections.iter().any(|s| {
@@ -222,6 +325,11 @@ pub fn move_to_match_internal(
         let pane = workspace.active_pane().clone();
         let count = vim.take_count(cx).unwrap_or(1);
         let prior_selections = vim.editor_selections(cx);
+        // `n` continues a backward search backwards.
+        let direction = match vim.state().search.direction {
+            Direction::Next => direction,
+            Direction::Prev => direction.opposite(),
+        };
 
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
@@ -231,7 +339,8 @@ pub fn move_to_match_internal(
                     }
                     search_bar.select_match(direction, count, cx);
 
-                    let new_selections = vim.editor_selections(cx);
+                    let offset = vim.state().search.offset;
+                    let new_selections = apply_search_offset(vim, offset, cx);
                     motion = Some(Motion::ZedSearchResult {
                         prior_selections,
                         new_selections,
@@ -255,23 +364,65 @@ pub fn move_to_internal(
         let pane = workspace.active_pane().clone();
         let count = vim.take_count(cx).unwrap_or(1);
         let prior_selections = vim.editor_selections(cx);
+        let is_visual = vim.state().mode.is_visual();
+        let word = vim
+            .update_active_editor(cx, |_, editor, cx| {
+                let snapshot = editor.buffer().read(cx).snapshot(cx);
+                if is_visual {
+                    // In visual mode the selected text is searched for literally,
+                    // including any line breaks it spans.
+                    let selection = editor.selections.newest_adjusted(cx);
+                    Some(snapshot.text_for_range(selection.range()).collect())
+                } else {
+                    word_under_cursor(
+                        &snapshot,
+                        editor.selections.newest::<usize>(cx).head(),
+                        false,
+                    )
+                }
+            })
+            .flatten();
 
         pane.update(cx, |pane, cx| {
             if let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() {
                 let search = search_bar.update(cx, |search_bar, cx| {
-                    let options = SearchOptions::CASE_SENSITIVE | SearchOptions::REGEX;
+                    let mut options = SearchOptions::REGEX;
+                    // As in vim, `smartcase` doesn't apply to `*` and `#`.
+                    let (_, case_sensitive) = case_sensitivity("", false, cx);
+                    options.set(SearchOptions::CASE_SENSITIVE, case_sensitive);
                     if !search_bar.show(cx) {
                         return None;
                     }
-                    let Some(query) = search_bar.query_suggestion(cx) else {
+                    let Some(word) = word.clone() else {
                         vim.clear_operator(cx);
                         drop(search_bar.search("", None, cx));
                         return None;
                     };
-                    let mut query = regex::escape(&query);
-                    if whole_word {
-                        query = format!(r"\<{}\>", query);
+                    let mut query = regex::escape(&word).replace('\n', "\\n");
+                    if whole_word && !is_visual {
+                        // As in vim, only anchor the ends that are keyword characters.
+                        let is_word =
+                            |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+                        if is_word(word.chars().next()) {
+                            query.insert_str(0, r"\<");
+                        }
+                        if is_word(word.chars().last()) {
+                            query.push_str(r"\>");
+                        }
                     }
+                    // Leave things as if the query had been typed, so that `n`
+                    // and `N` continue in the same direction.
+                    vim.workspace_state
+                        .registers
+                        .insert('/', query.clone().into());
+                    vim.update_state(|state| {
+                        state.search = SearchState {
+                            direction,
+                            count: 1,
+                            initial_query: query.clone(),
+                            ..Default::default()
+                        }
+                    });
                     Some(search_bar.search(&query, Some(options), cx))
                 });
 
@@ -299,12 +450,178 @@ pub fn move_to_internal(
             }
         });
 
-        if vim.state().mode.is_visual() {
+        if is_visual {
             vim.switch_mode(Mode::Normal, false, cx)
         }
     });
 }
 
+/// Returns the keyword under or after `offset` on its line, or failing that
+/// the punctuation there, as vim does for `*` and `#`. With
+/// `ignore_punctuation` the WORD there is returned instead.
+pub(crate) fn word_under_cursor(
+    snapshot: &MultiBufferSnapshot,
+    offset: usize,
+    ignore_punctuation: bool,
+) -> Option<String> {
+    let scope = snapshot.language_scope_at(offset);
+    let kind = |c| coerce_punctuation(char_kind(&scope, c), ignore_punctuation);
+    for target in [CharKind::Word, CharKind::Punctuation] {
+        let Some(skipped) = snapshot
+            .chars_at(offset)
+            .take_while(|c| *c != '\n')
+            .position(|c| kind(c) == target)
+        else {
+            continue;
+        };
+        let mut start = offset
+            + snapshot
+                .chars_at(offset)
+                .take(skipped)
+                .map(char::len_utf8)
+                .sum::<usize>();
+        if skipped == 0 {
+            for c in snapshot.reversed_chars_at(offset) {
+                if c == '\n' || kind(c) != target {
+                    break;
+                }
+                start -= c.len_utf8();
+            }
+        }
+        let end = start
+            + snapshot
+                .chars_at(start)
+                .take_while(|c| *c != '\n' && kind(*c) == target)
+                .map(char::len_utf8)
+                .sum::<usize>();
+        return Some(snapshot.text_for_range(start..end).collect());
+    }
+    None
+}
+
+/// Returns `pattern` without any `\c` or `\C`, and whether it should match
+/// case given those and the `ignorecase` and `smartcase` settings.
+fn case_sensitivity(pattern: &str, smartcase: bool, cx: &AppContext) -> (String, bool) {
+    let settings = VimSettings::get_global(cx);
+    let mut forced = None;
+    let mut stripped = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            stripped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('c') => forced = Some(false),
+            Some('C') => forced = Some(true),
+            Some(c) => {
+                stripped.push('\\');
+                stripped.push(c);
+            }
+            None => stripped.push('\\'),
+        }
+    }
+    let case_sensitive = forced.unwrap_or_else(|| {
+        !settings.ignorecase
+            || (smartcase && settings.smartcase && stripped.chars().any(char::is_uppercase))
+    });
+    (stripped, case_sensitive)
+}
+
+/// Splits `query` at the first unescaped `delimiter` into the pattern and
+/// the offset after it, if what follows is a valid offset.
+fn split_search_offset(query: &str, delimiter: char) -> Option<(&str, SearchOffset)> {
+    let mut escaped = false;
+    for (ix, c) in query.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == delimiter {
+            let offset = SearchOffset::parse(&query[ix + 1..])?;
+            return Some((&query[..ix], offset));
+        }
+    }
+    None
+}
+
+/// Returns the active editor's selections, moved from each match to the
+/// position the search offset asks for.
+fn apply_search_offset(
+    vim: &mut Vim,
+    offset: SearchOffset,
+    cx: &mut WindowContext,
+) -> Vec<Range<Anchor>> {
+    let selections = vim.editor_selections(cx);
+    if offset == SearchOffset::None {
+        return selections;
+    }
+    vim.update_active_editor(cx, |_, editor, cx| {
+        let snapshot = editor.buffer().read(cx).snapshot(cx);
+        selections
+            .iter()
+            .map(|range| {
+                let anchor = snapshot.anchor_before(offset_point(&snapshot, offset, range));
+                anchor..anchor
+            })
+            .collect()
+    })
+    .unwrap_or(selections)
+}
+
+fn offset_point(
+    snapshot: &MultiBufferSnapshot,
+    offset: SearchOffset,
+    range: &Range<Anchor>,
+) -> Point {
+    match offset {
+        SearchOffset::None => range.start.to_point(snapshot),
+        SearchOffset::Lines(lines) => {
+            let row = range.start.to_point(snapshot).row as i64 + lines as i64;
+            Point::new(row.clamp(0, snapshot.max_point().row as i64) as u32, 0)
+        }
+        SearchOffset::Start(chars) => {
+            move_in_line(snapshot, range.start.to_offset(snapshot), chars)
+        }
+        SearchOffset::End(chars) => {
+            // The end of a match is exclusive, so start from its last character.
+            let mut end = range.end.to_offset(snapshot);
+            if end > range.start.to_offset(snapshot) {
+                end -= snapshot
+                    .reversed_chars_at(end)
+                    .next()
+                    .map_or(0, char::len_utf8);
+            }
+            move_in_line(snapshot, end, chars)
+        }
+    }
+}
+
+/// Moves `chars` characters from `offset`, stopping at either end of the line.
+fn move_in_line(snapshot: &MultiBufferSnapshot, mut offset: usize, chars: i32) -> Point {
+    if chars > 0 {
+        let mut line = snapshot.chars_at(offset).peekable();
+        for _ in 0..chars {
+            let Some(c) = line.next() else { break };
+            if matches!(line.peek(), None | Some('\n')) {
+                break;
+            }
+            offset += c.len_utf8();
+        }
+    } else {
+        for c in snapshot
+            .reversed_chars_at(offset)
+            .take(chars.unsigned_abs() as usize)
+        {
+            if c == '\n' {
+                break;
+            }
+            offset -= c.len_utf8();
+        }
+    }
+    offset.to_point(snapshot)
+}
+
 fn find_command(workspace: &mut Workspace, action: &FindCommand, cx: &mut ViewContext<Workspace>) {
     let pane = workspace.active_pane().clone();
     pane.update(cx, |pane, cx| {
@@ -348,27 +665,33 @@ fn replace_command(
     action: &ReplaceCommand,
     cx: &mut ViewContext<Workspace>,
 ) {
-    let replacement = action.replacement.clone();
+    let mut replacement = action.replacement.clone();
+    if replacement.keep_flags {
+        if let Some(last_replacement) = Vim::read(cx).workspace_state.last_replacement.as_ref() {
+            replacement.add_flags(last_replacement);
+        }
+    }
     let pane = workspace.active_pane().clone();
     let editor = Vim::read(cx)
         .active_editor
         .as_ref()
         .and_then(|editor| editor.upgrade());
-    if let Some(range) = &action.range {
-        if let Some(result) = Vim::update(cx, |vim, cx| {
-            vim.update_active_editor(cx, |vim, editor, cx| {
-                let range = range.buffer_range(vim, editor, cx)?;
-                let snapshot = &editor.snapshot(cx).buffer_snapshot;
-                let end_point = Point::new(range.end.0, snapshot.line_len(range.end));
-                let range = snapshot.anchor_before(Point::new(range.start.0, 0))
-                    ..snapshot.anchor_after(end_point);
-                editor.set_search_within_ranges(&[range], cx);
-                anyhow::Ok(())
-            })
-        }) {
-            result.notify_err(workspace, cx);
-        }
-    }
+    // `:s` without a range changes the whole buffer.
+    let range = action.range.clone().unwrap_or(CommandRange::whole_file());
+    let Some(rows) = Vim::update(cx, |vim, cx| {
+        vim.update_active_editor(cx, |vim, editor, cx| {
+            let rows = range.buffer_range(vim, editor, cx)?;
+            let snapshot = &editor.snapshot(cx).buffer_snapshot;
+            let end_point = Point::new(rows.end.0, snapshot.line_len(rows.end));
+            let range = snapshot.anchor_before(Point::new(rows.start.0, 0))
+                ..snapshot.anchor_after(end_point);
+            editor.set_search_within_ranges(&[range], cx);
+            anyhow::Ok(rows)
+        })
+    })
+    .and_then(|result| result.notify_err(workspace, cx)) else {
+        return;
+    };
     pane.update(cx, |pane, cx| {
         let Some(search_bar) = pane.toolbar().read(cx).item_of_type::<BufferSearchBar>() else {
             return;
@@ -385,20 +708,37 @@ fn replace_command(
             let search = if replacement.search == "" {
                 search_bar.query(cx)
             } else {
-                replacement.search
+                replacement.search.clone()
             };
 
-            search_bar.set_replacement(Some(&replacement.replacement), cx);
-            Some(search_bar.search(&search, Some(options), cx))
+            // Remember the substitution with the pattern it used, so that `&`
+            // repeats it even if the search changes in between. As in vim the
+            // pattern also becomes the last search pattern.
+            Vim::update(cx, |vim, _| {
+                vim.workspace_state.last_replacement = Some(Replacement {
+                    search: search.clone(),
+                    ..replacement.clone()
+                });
+                vim.workspace_state
+                    .registers
+                    .insert('/', search.clone().into());
+            });
+
+            Some((search_bar.search(&search, Some(options), cx), search))
         });
-        let Some(search) = search else { return };
+        let Some((search_task, search)) = search else {
+            return;
+        };
         let search_bar = search_bar.downgrade();
         cx.spawn(|_, mut cx| async move {
-            search.await?;
-            search_bar.update(&mut cx, |search_bar, cx| {
+            search_task.await?;
+            search_bar.update(&mut cx, |_, cx| {
                 if replacement.should_replace_all {
-                    search_bar.select_last_match(cx);
-                    search_bar.replace_all(&Default::default(), cx);
+                    if let Some(editor) = editor.as_ref() {
+                        editor.update(cx, |editor, cx| {
+                            replace_in_rows(editor, rows, &search, &replacement, cx)
+                        })?;
+                    }
                     if let Some(editor) = editor {
                         cx.spawn(|_, mut cx| async move {
                             cx.background_executor()
@@ -421,23 +761,308 @@ fn replace_command(
                         )
                     })
                 }
-            })?;
+                anyhow::Ok(())
+            })??;
             anyhow::Ok(())
         })
         .detach_and_log_err(cx);
     })
 }
 
+/// Replaces the matches of `search` in `rows` as `:s` does, only replacing
+/// the first match on each line when repeated with `&`. The cursor is left on
+/// the last line that changed.
+fn replace_in_rows(
+    editor: &mut Editor,
+    rows: Range<MultiBufferRow>,
+    search: &str,
+    replacement: &Replacement,
+    cx: &mut ViewContext<Editor>,
+) -> anyhow::Result<()> {
+    let snapshot = editor.buffer().read(cx).snapshot(cx);
+    let edits = substitution_edits(&snapshot, rows, search, replacement)?;
+    let Some((last_edit, _)) = edits.last() else {
+        return Ok(());
+    };
+    let last_row = snapshot.offset_to_point(last_edit.start).row;
+    editor.transact(cx, |editor, cx| {
+        editor.edit(edits, cx);
+        editor.change_selections(None, cx, |s| {
+            s.select_ranges([Point::new(last_row, 0)..Point::new(last_row, 0)])
+        });
+    });
+    Ok(())
+}
+
+/// The edits that `:s` makes to `rows`, as offsets into the buffer.
+fn substitution_edits(
+    snapshot: &MultiBufferSnapshot,
+    rows: Range<MultiBufferRow>,
+    search: &str,
+    replacement: &Replacement,
+) -> anyhow::Result<Vec<(Range<usize>, String)>> {
+    let regex = RegexBuilder::new(search)
+        .case_insensitive(!replacement.is_case_sensitive)
+        .multi_line(true)
+        .build()?;
+    let start = snapshot.point_to_offset(Point::new(rows.start.0, 0));
+    let end = snapshot.point_to_offset(Point::new(rows.end.0, snapshot.line_len(rows.end)));
+    let text = snapshot.text_for_range(start..end).collect::<String>();
+
+    let mut edits = Vec::new();
+    let mut last_row = None;
+    for captures in regex.captures_iter(&text) {
+        let found = captures.get(0).unwrap();
+        let row = snapshot.offset_to_point(start + found.start()).row;
+        if !replacement.is_global && last_row == Some(row) {
+            continue;
+        }
+        let new_text = expand_replacement(&replacement.replacement, &captures)?;
+        edits.push((start + found.start()..start + found.end(), new_text));
+        last_row = Some(row);
+    }
+    Ok(edits)
+}
+
+/// Marks the background of the matches in a substitution preview.
+struct SubstitutePreview;
+
+/// Shows what the `:s` being typed into the command palette would change, as
+/// Neovim's `'inccommand'` does: the matches on screen are highlighted, with
+/// their replacements shown after them. Any other command clears the preview.
+pub(crate) fn preview_substitution(query: &str, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        clear_substitution_preview(vim, cx);
+        if !vim.enabled || !VimSettings::get_global(cx).inccommand {
+            return;
+        }
+        let in_palette = vim
+            .active_editor
+            .as_ref()
+            .and_then(|editor| editor.upgrade()?.read(cx).workspace())
+            .is_some_and(|workspace| {
+                workspace
+                    .read(cx)
+                    .active_modal::<CommandPalette>(cx)
+                    .is_some()
+            });
+        if !in_palette {
+            return;
+        }
+        let Some(action) = command_interceptor(query, cx).and_then(|result| {
+            result
+                .action
+                .as_any()
+                .downcast_ref::<ReplaceCommand>()
+                .cloned()
+        }) else {
+            return;
+        };
+
+        let mut replacement = action.replacement;
+        if replacement.keep_flags {
+            if let Some(last_replacement) = vim.workspace_state.last_replacement.as_ref() {
+                replacement.add_flags(last_replacement);
+            }
+        }
+        let search = if replacement.search.is_empty() {
+            vim.workspace_state
+                .registers
+                .get(&'/')
+                .map(|register| register.text.to_string())
+                .unwrap_or_default()
+        } else {
+            replacement.search.clone()
+        };
+        if search.is_empty() {
+            return;
+        }
+        let range = action.range.unwrap_or(CommandRange::whole_file());
+
+        let shown = vim.update_active_editor(cx, |vim, editor, cx| {
+            let Ok(rows) = range.buffer_range(vim, editor, cx) else {
+                return false;
+            };
+            let snapshot = editor.snapshot(cx);
+            let map = &snapshot.display_snapshot;
+            // Only the lines on screen are searched, to keep typing fast in
+            // large files.
+            let rows = match editor.visible_line_count() {
+                Some(visible_lines) => {
+                    let top = snapshot.scroll_position().y as u32;
+                    let bottom = top + visible_lines.ceil() as u32;
+                    let first = DisplayPoint::new(DisplayRow(top), 0)
+                        .to_point(map)
+                        .row
+                        .max(rows.start.0);
+                    let last = map
+                        .clip_point(DisplayPoint::new(DisplayRow(bottom), 0), Bias::Left)
+                        .to_point(map)
+                        .row
+                        .min(rows.end.0);
+                    if first > last {
+                        return false;
+                    }
+                    MultiBufferRow(first)..MultiBufferRow(last)
+                }
+                None => rows,
+            };
+            // The pattern is often not a valid regex while it's being typed.
+            let Ok(edits) = substitution_edits(&map.buffer_snapshot, rows, &search, &replacement)
+            else {
+                return false;
+            };
+
+            let buffer = &map.buffer_snapshot;
+            let matches = edits
+                .iter()
+                .map(|(range, _)| buffer.anchor_after(range.start)..buffer.anchor_before(range.end))
+                .collect::<Vec<_>>();
+            let inlays = edits
+                .into_iter()
+                .filter(|(_, new_text)| !new_text.is_empty())
+                .map(|(range, new_text)| (buffer.anchor_after(range.end), new_text))
+                .collect::<Vec<_>>();
+            editor.highlight_background::<SubstitutePreview>(
+                &matches,
+                |colors| colors.search_match_background,
+                cx,
+            );
+            editor.set_preview_inlays(inlays, cx);
+            true
+        });
+        if shown == Some(true) {
+            vim.substitute_preview = vim.active_editor.clone();
+        }
+    })
+}
+
+/// Removes the highlights and replacements shown by [`preview_substitution`].
+pub(crate) fn clear_substitution_preview(vim: &mut Vim, cx: &mut WindowContext) {
+    let Some(editor) = vim
+        .substitute_preview
+        .take()
+        .and_then(|editor| editor.upgrade())
+    else {
+        return;
+    };
+    editor.update(cx, |editor, cx| {
+        editor.clear_background_highlights::<SubstitutePreview>(cx);
+        editor.set_preview_inlays([], cx);
+    });
+}
+
+#[derive(Clone, Copy)]
+enum Case {
+    Upper,
+    Lower,
+}
+
+/// The text being built for a replacement, with the case changes requested
+/// by `\u`, `\l`, `\U` and `\L` applied as it is added.
+#[derive(Default)]
+struct ReplacementText {
+    text: String,
+    next_char_case: Option<Case>,
+    case: Option<Case>,
+}
+
+impl ReplacementText {
+    fn push_str(&mut self, text: &str) {
+        for c in text.chars() {
+            match self.next_char_case.take().or(self.case) {
+                Some(Case::Upper) => self.text.extend(c.to_uppercase()),
+                Some(Case::Lower) => self.text.extend(c.to_lowercase()),
+                None => self.text.push(c),
+            }
+        }
+    }
+}
+
+/// Expands a vim replacement for one match: `&` and `\0`..`\9` insert the
+/// match and its groups, `\u`, `\l`, `\U`, `\L` and `\E` change case, and a
+/// replacement starting with `\=` is evaluated as an expression in which
+/// `submatch(n)` returns the groups.
+fn expand_replacement(replacement: &str, captures: &Captures) -> anyhow::Result<String> {
+    let submatch = |ix: usize| captures.get(ix).map_or("", |m| m.as_str());
+    if let Some(expr) = replacement.strip_prefix("\\=") {
+        let submatches = (0..captures.len()).map(submatch).collect::<Vec<_>>();
+        return Ok(evaluate_with_submatches(expr, &submatches)?
+            .to_lines()
+            .join("\n"));
+    }
+
+    let mut text = ReplacementText::default();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '&' => text.push_str(submatch(0)),
+            '\\' => match chars.next() {
+                Some(digit @ '0'..='9') => text.push_str(submatch(digit as usize - '0' as usize)),
+                Some('u') => text.next_char_case = Some(Case::Upper),
+                Some('l') => text.next_char_case = Some(Case::Lower),
+                Some('U') => text.case = Some(Case::Upper),
+                Some('L') => text.case = Some(Case::Lower),
+                Some('E' | 'e') => text.case = None,
+                Some('n' | 'r') => text.push_str("\n"),
+                Some('t') => text.push_str("\t"),
+                Some(c) => text.push_str(c.encode_utf8(&mut [0; 4])),
+                None => text.push_str("\\"),
+            },
+            c => text.push_str(c.encode_utf8(&mut [0; 4])),
+        }
+    }
+    Ok(text.text)
+}
+
+fn repeat_substitute(
+    workspace: &mut Workspace,
+    action: &RepeatSubstitute,
+    cx: &mut ViewContext<Workspace>,
+) {
+    let last_replacement = Vim::read(cx).workspace_state.last_replacement.clone();
+    let Some(mut replacement) = last_replacement else {
+        workspace.show_error(&anyhow!("No previous substitute regular expression"), cx);
+        return;
+    };
+    if !action.keep_flags {
+        replacement.reset_flags();
+    }
+    if action.last_search {
+        let last_search = Vim::read(cx).workspace_state.registers.get(&'/').cloned();
+        let Some(last_search) = last_search else {
+            workspace.show_error(&anyhow!("No previous regular expression"), cx);
+            return;
+        };
+        replacement.search = last_search.text.to_string();
+    }
+    let range = if action.whole_file {
+        CommandRange::whole_file()
+    } else {
+        action.range.clone().unwrap_or(CommandRange::current_line())
+    };
+    replace_command(
+        workspace,
+        &ReplaceCommand {
+            range: Some(range),
+            replacement,
+        },
+        cx,
+    );
+}
+
 impl Replacement {
     // convert a vim query into something more usable by zed.
     // we don't attempt to fully convert between the two regex syntaxes,
-    // but we do flip \( and \) to ( and ) (and vice-versa) in the pattern,
-    // and convert \0..\9 to $0..$9 in the replacement so that common idioms work.
+    // but we do flip \(, \), \|, \+, \? and \{ to (, ), |, +, ? and {
+    // (and vice-versa) in the pattern so that common idioms work.
+    // the replacement is kept as vim wrote it, see `expand_replacement`.
     pub(crate) fn parse(mut chars: Peekable<Chars>) -> Option<Replacement> {
-        let Some(delimiter) = chars
-            .next()
-            .filter(|c| !c.is_alphanumeric() && *c != '"' && *c != '|' && *c != '\'')
-        else {
+        // as in vim, any character can be the delimiter except for letters,
+        // digits, whitespace and a few that mean something else after `:s`.
+        let Some(delimiter) = chars.next().filter(|c| {
+            !c.is_alphanumeric() && !c.is_whitespace() && !matches!(c, '"' | '|' | '\'' | '\\')
+        }) else {
             return None;
         };
 
@@ -453,17 +1078,32 @@ impl Replacement {
         // 2 - parsing flags
         let mut phase = 0;
 
+        // whether a `\{` interval is open, so its `}` is kept unescaped.
+        let mut in_interval = false;
+
         for c in chars {
             if escaped {
                 escaped = false;
-                if phase == 1 && c.is_digit(10) {
-                    buffer.push('$')
-                // unescape escaped parens
-                } else if phase == 0 && c == '(' || c == ')' {
-                } else if c != delimiter {
-                    buffer.push('\\')
+                if c == delimiter {
+                    buffer.push(c)
+                } else if phase == 0 {
+                    match c {
+                        // unescape escaped operators
+                        '(' | ')' | '|' | '+' | '?' => buffer.push(c),
+                        '=' => buffer.push('?'),
+                        '{' => {
+                            in_interval = true;
+                            buffer.push(c)
+                        }
+                        _ => {
+                            buffer.push('\\');
+                            buffer.push(c)
+                        }
+                    }
+                } else {
+                    buffer.push('\\');
+                    buffer.push(c)
                 }
-                buffer.push(c)
             } else if c == '\\' {
                 escaped = true;
             } else if c == delimiter {
@@ -477,9 +1117,14 @@ impl Replacement {
                     break;
                 }
             } else {
-                // escape unescaped parens
-                if phase == 0 && c == '(' || c == ')' {
-                    buffer.push('\\')
+                // escape unescaped operators
+                if phase == 0 {
+                    match c {
+                        '(' | ')' | '|' | '+' | '?' | '{' => buffer.push('\\'),
+                        '}' if !in_interval => buffer.push('\\'),
+                        '}' => in_interval = false,
+                        _ => {}
+                    }
                 }
                 buffer.push(c)
             }
@@ -490,8 +1135,18 @@ impl Replacement {
             replacement,
             should_replace_all: true,
             is_case_sensitive: true,
+            is_global: true,
+            keep_flags: false,
         };
 
+        // as in vim, `&` is only a flag when it comes first.
+        let flags = match flags.strip_prefix('&') {
+            Some(flags) => {
+                replacement.keep_flags = true;
+                flags
+            }
+            None => &flags,
+        };
         for c in flags.chars() {
             match c {
                 'g' | 'I' => {}
@@ -503,6 +1158,22 @@ impl Replacement {
 
         Some(replacement)
     }
+
+    /// Drops the flags given to the substitution, as `&` does, which also
+    /// limits it to the first match on each line.
+    fn reset_flags(&mut self) {
+        self.should_replace_all = true;
+        self.is_case_sensitive = true;
+        self.is_global = false;
+    }
+
+    /// Adds the flags given to another substitution to this one's.
+    fn add_flags(&mut self, other: &Replacement) {
+        self.should_replace_all &= other.should_replace_all;
+        self.is_case_sensitive &= other.is_case_sensitive;
+        self.is_global |= other.is_global;
+        self.keep_flags = false;
+    }
 }
 
 #[cfg(test)]
@@ -512,6 +1183,7 @@ mod test {
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim, VimSettings,
     };
     use editor::EditorSettings;
     use editor::{display_map::DisplayRow, DisplayPoint};
@@ -556,6 +1228,35 @@ mod test {
         cx.assert_state("hi\nˇhigh\nhi\n", Mode::Normal);
     }
 
+    #[gpui::test]
+    async fn test_star_and_hash(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇfoo foobar foo").await;
+        cx.simulate_shared_keystrokes("*").await;
+        cx.shared_state().await.assert_eq("foo foobar ˇfoo");
+        cx.shared_register('/').await.assert_eq(r"\<foo\>");
+
+        cx.set_shared_state("ˇfoo foobar foo").await;
+        cx.simulate_shared_keystrokes("g *").await;
+        cx.shared_state().await.assert_eq("foo ˇfoobar foo");
+        cx.shared_register('/').await.assert_eq("foo");
+
+        // on whitespace, the next word on the line is used
+        cx.set_shared_state("fooˇ bar foo bar").await;
+        cx.simulate_shared_keystrokes("*").await;
+        cx.shared_state().await.assert_eq("foo bar foo ˇbar");
+
+        // n and N continue in the direction of the search
+        cx.set_shared_state("foo bar foo bar ˇfoo").await;
+        cx.simulate_shared_keystrokes("#").await;
+        cx.shared_state().await.assert_eq("foo bar ˇfoo bar foo");
+        cx.simulate_shared_keystrokes("n").await;
+        cx.shared_state().await.assert_eq("ˇfoo bar foo bar foo");
+        cx.simulate_shared_keystrokes("shift-n").await;
+        cx.shared_state().await.assert_eq("foo bar ˇfoo bar foo");
+    }
+
     #[gpui::test]
     async fn test_move_to_next_with_no_search_wrap(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -694,6 +1395,156 @@ mod test {
         cx.assert_state("aa\nbb\nˇcc\ncc\ncc\n", Mode::Normal);
     }
 
+    #[gpui::test]
+    async fn test_search_offset(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇone word two word\nthree word\n")
+            .await;
+        cx.simulate_shared_keystrokes("/ w o r d / e enter").await;
+        cx.shared_state()
+            .await
+            .assert_eq("one worˇd two word\nthree word\n");
+        cx.simulate_shared_keystrokes("n").await;
+        cx.shared_state()
+            .await
+            .assert_eq("one word two worˇd\nthree word\n");
+        cx.simulate_shared_keystrokes("n").await;
+        cx.shared_state()
+            .await
+            .assert_eq("one word two word\nthree worˇd\n");
+        cx.simulate_shared_keystrokes("shift-n").await;
+        cx.shared_state()
+            .await
+            .assert_eq("one word two worˇd\nthree word\n");
+
+        cx.set_shared_state("ˇone word two word\nthree word\n")
+            .await;
+        cx.simulate_shared_keystrokes("/ w o r d / e - 1 enter")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("one woˇrd two word\nthree word\n");
+        cx.simulate_shared_keystrokes("/ w o r d / b + 2 enter")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("one word two woˇrd\nthree word\n");
+
+        cx.set_shared_state("ˇone\ntwo word\nthree\n").await;
+        cx.simulate_shared_keystrokes("/ w o r d / + enter").await;
+        cx.shared_state().await.assert_eq("one\ntwo word\nˇthree\n");
+    }
+
+    #[gpui::test]
+    async fn test_search_offset_clamps_to_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇone two\nthree word\n", Mode::Normal);
+        cx.simulate_keystrokes("/ w o r d / e + 5 enter");
+        cx.run_until_parked();
+        cx.assert_state("one two\nthree worˇd\n", Mode::Normal);
+
+        cx.set_state("one two\nthree word\nˇ", Mode::Normal);
+        cx.simulate_keystrokes("? t w o ? s - 9 enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇone two\nthree word\n", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_search_history(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇapple\nbanana\napricot\ncherry\n", Mode::Normal);
+        cx.simulate_keystrokes("/ a p p enter");
+        cx.simulate_keystrokes("/ b a n enter");
+        cx.simulate_keystrokes("/ a p r enter");
+        cx.simulate_keystrokes("/ c h enter");
+        cx.assert_state("apple\nbanana\napricot\nˇcherry\n", Mode::Normal);
+
+        let search_bar = cx.workspace(|workspace, cx| {
+            workspace
+                .active_pane()
+                .read(cx)
+                .toolbar()
+                .read(cx)
+                .item_of_type::<BufferSearchBar>()
+                .expect("Buffer search bar should be deployed")
+        });
+        let assert_query = |cx: &mut VimTestContext, keystrokes: &str, query: &str| {
+            cx.simulate_keystrokes(keystrokes);
+            cx.update_view(search_bar.clone(), |bar, cx| {
+                assert_eq!(bar.query(cx), query)
+            });
+        };
+
+        // the search bar's own history is browsed, starting from the last
+        // search, which `/` leaves in the query
+        assert_query(&mut cx, "/ up", "apr");
+        assert_query(&mut cx, "up", "ban");
+        assert_query(&mut cx, "ctrl-p", "app");
+        assert_query(&mut cx, "up", "app");
+        assert_query(&mut cx, "ctrl-n", "ban");
+        assert_query(&mut cx, "down", "apr");
+        cx.simulate_keystrokes("enter");
+        cx.assert_state("apple\nbanana\nˇapricot\ncherry\n", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_search_ignorecase(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.ignorecase = Some(true));
+        });
+
+        cx.set_state("ˇone Foo foo", Mode::Normal);
+        cx.simulate_keystrokes("/ f o o enter");
+        cx.run_until_parked();
+        cx.assert_state("one ˇFoo foo", Mode::Normal);
+        cx.simulate_keystrokes("n");
+        cx.assert_state("one Foo ˇfoo", Mode::Normal);
+
+        // \C forces a case sensitive search
+        cx.set_state("ˇone Foo foo", Mode::Normal);
+        cx.simulate_keystrokes("/ f o o \\ C enter");
+        cx.run_until_parked();
+        cx.assert_state("one Foo ˇfoo", Mode::Normal);
+
+        cx.set_state("ˇfoo Foo", Mode::Normal);
+        cx.simulate_keystrokes("*");
+        cx.run_until_parked();
+        cx.assert_state("foo ˇFoo", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_search_smartcase(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| {
+                s.ignorecase = Some(true);
+                s.smartcase = Some(true);
+            });
+        });
+
+        cx.set_state("ˇone foo Foo", Mode::Normal);
+        cx.simulate_keystrokes("/ F o o enter");
+        cx.run_until_parked();
+        cx.assert_state("one foo ˇFoo", Mode::Normal);
+        cx.simulate_keystrokes("n");
+        cx.assert_state("one foo ˇFoo", Mode::Normal);
+
+        cx.set_state("ˇone Foo foo", Mode::Normal);
+        cx.simulate_keystrokes("/ f o o enter");
+        cx.run_until_parked();
+        cx.assert_state("one ˇFoo foo", Mode::Normal);
+
+        // \c forces a case insensitive search
+        cx.set_state("ˇone foo Foo", Mode::Normal);
+        cx.simulate_keystrokes("/ F o o \\ c enter");
+        cx.run_until_parked();
+        cx.assert_state("one ˇfoo Foo", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_non_vim_search(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, false).await;
@@ -717,6 +1568,42 @@ mod test {
         cx.shared_state().await.assert_eq("a.c. abcd ˇa.c. abcd");
     }
 
+    #[gpui::test]
+    async fn test_visual_star_is_literal(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa.b axb a.b axb", Mode::Normal);
+        cx.simulate_keystrokes("v 2 l *");
+        cx.run_until_parked();
+        cx.assert_state("a.b axb ˇa.b axb", Mode::Normal);
+        cx.simulate_keystrokes("n");
+        cx.run_until_parked();
+        cx.assert_state("ˇa.b axb a.b axb", Mode::Normal);
+
+        cx.set_state("a.b axb ˇa.b axb", Mode::Normal);
+        cx.simulate_keystrokes("v 2 l #");
+        cx.run_until_parked();
+        cx.assert_state("ˇa.b axb a.b axb", Mode::Normal);
+        cx.update(|cx| {
+            assert_eq!(
+                Vim::read(cx)
+                    .workspace_state
+                    .registers
+                    .get(&'/')
+                    .unwrap()
+                    .text
+                    .as_ref(),
+                r"a\.b"
+            )
+        });
+
+        // Line breaks in the selection must match line breaks.
+        cx.set_state("ˇone\ntwo\none two\none\ntwo", Mode::Normal);
+        cx.simulate_keystrokes("shift-v j *");
+        cx.run_until_parked();
+        cx.assert_state("one\ntwo\none two\nˇone\ntwo", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_d_search(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -860,4 +1747,178 @@ mod test {
                  "
         });
     }
+
+    #[gpui::test]
+    async fn test_repeat_substitute(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // With nothing to repeat, `&` leaves the buffer alone.
+        cx.set_state("ˇa a", Mode::Normal);
+        cx.simulate_keystrokes("&");
+        cx.run_until_parked();
+        cx.assert_state("ˇa a", Mode::Normal);
+
+        cx.set_state("ˇa\nb\na a a", Mode::Normal);
+        cx.simulate_keystrokes(": 1 s / a / b enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇb\nb\na a a", Mode::Normal);
+
+        cx.simulate_keystrokes("j j &");
+        cx.run_until_parked();
+        cx.assert_state("b\nb\nˇb a a", Mode::Normal);
+
+        // `g&` repeats across the file with the last flags.
+        cx.set_state("ˇa a\nb\na a a", Mode::Normal);
+        cx.simulate_keystrokes(": 1 s / a / c / g enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇc c\nb\na a a", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+        cx.simulate_keystrokes("g &");
+        cx.run_until_parked();
+        cx.assert_state("c c\nb\nˇc c c", Mode::Normal);
+
+        // `:&&` keeps the flags, `:&` doesn't.
+        cx.set_state("ˇa a\na a", Mode::Normal);
+        cx.simulate_keystrokes(": & & enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇc c\na a", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+        cx.simulate_keystrokes("j : & enter");
+        cx.run_until_parked();
+        cx.assert_state("c c\nˇc a", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_substitute_keep_flags(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇA a a\nC c c\nc", Mode::Normal);
+        cx.simulate_keystrokes(": 1 s / a / b / i enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇb b b\nC c c\nc", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+
+        // `&` reuses `i`
+        cx.simulate_keystrokes(": 2 s / c / d / & enter");
+        cx.run_until_parked();
+        cx.assert_state("b b b\nˇd d d\nc", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+
+        // and other flags can follow it
+        cx.set_state("ˇX x\nY y", Mode::Normal);
+        cx.simulate_keystrokes(": 1 s / x / z / i enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇz z\nY y", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+        cx.simulate_keystrokes(": 2 s / y / w / & g enter");
+        cx.run_until_parked();
+        cx.assert_state("z z\nˇw w", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_repeat_substitute_with_last_search(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa b\nb a b", Mode::Normal);
+        cx.simulate_keystrokes(": 1 s / a / X / enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇX b\nb a b", Mode::Normal);
+        cx.executor().advance_clock(Duration::from_millis(250));
+        cx.run_until_parked();
+
+        // `g&` searches for the last search pattern, not the last substitute
+        // pattern, but keeps the replacement and flags.
+        cx.simulate_keystrokes("/ b enter");
+        cx.run_until_parked();
+        cx.simulate_keystrokes("g &");
+        cx.run_until_parked();
+        cx.assert_state("X X\nˇX a X", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_substitute_preview(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        cx.update_global(|store: &mut SettingsStore, cx| {
+            store.update_user_settings::<VimSettings>(cx, |s| s.inccommand = Some(true));
+        });
+        let preview = |cx: &mut VimTestContext| {
+            cx.update_editor(|editor, cx| {
+                let matches = editor
+                    .all_text_background_highlights(cx)
+                    .into_iter()
+                    .map(|(range, _)| range)
+                    .collect::<Vec<_>>();
+                (matches, editor.display_text(cx))
+            })
+        };
+
+        cx.set_state("ˇfoo a foo\nbar foo\nbaz", Mode::Normal);
+        cx.simulate_keystrokes(": % s / f o o / b a r");
+        cx.run_until_parked();
+        assert_eq!(
+            preview(&mut cx),
+            (
+                vec![
+                    DisplayPoint::new(DisplayRow(0), 0)..DisplayPoint::new(DisplayRow(0), 3),
+                    DisplayPoint::new(DisplayRow(0), 6)..DisplayPoint::new(DisplayRow(0), 9),
+                    DisplayPoint::new(DisplayRow(1), 4)..DisplayPoint::new(DisplayRow(1), 7),
+                ],
+                "foobar a foobar\nbar foobar\nbaz".to_string()
+            )
+        );
+
+        // the preview follows the command as it changes
+        cx.simulate_keystrokes("backspace backspace backspace b a z");
+        cx.run_until_parked();
+        assert_eq!(preview(&mut cx).1, "foobaz a foobaz\nbar foobaz\nbaz");
+
+        // and is cleared when the command is abandoned
+        cx.simulate_keystrokes("escape");
+        cx.run_until_parked();
+        assert_eq!(
+            preview(&mut cx),
+            (Vec::new(), "foo a foo\nbar foo\nbaz".to_string())
+        );
+
+        cx.simulate_keystrokes(": s / f o o / x enter");
+        cx.run_until_parked();
+        assert_eq!(preview(&mut cx).1, "x a x\nbar x\nbaz");
+    }
+
+    #[gpui::test]
+    async fn test_replace_case_modifiers(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇhello big world", Mode::Normal);
+        cx.simulate_keystrokes(": s / \\ w \\ + / \\ u & / g enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇHello Big World", Mode::Normal);
+
+        cx.set_state("ˇfoo bar", Mode::Normal);
+        cx.simulate_keystrokes(
+            ": s / \\ ( \\ w \\ + \\ ) space \\ ( \\ w \\ + \\ ) / \\ U \\ 2 \\ E space \\ 1 / enter",
+        );
+        cx.run_until_parked();
+        cx.assert_state("ˇBAR foo", Mode::Normal);
+
+        cx.set_state("ˇFOO Bar", Mode::Normal);
+        cx.simulate_keystrokes(": s / \\ w \\ + / \\ L & / g enter");
+        cx.run_until_parked();
+        cx.assert_state("ˇfoo bar", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_replace_expression(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("ˇa 1 b 21\nc 3", Mode::Normal);
+        cx.simulate_keystrokes(": % s / \\ d \\ + / \\ = s u b m a t c h ( 0 ) * 2 / g enter");
+        cx.run_until_parked();
+        cx.assert_state("a 2 b 42\nˇc 6", Mode::Normal);
+    }
 }
@@ -33,16 +33,24 @@ pub fn toggle_comments_motion(
     });
 }
 
-pub fn toggle_comments_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+pub fn toggle_comments_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
     vim.stop_recording();
-    vim.update_active_editor(cx, |_, editor, cx| {
+    vim.update_active_editor(cx, |vim, editor, cx| {
         editor.transact(cx, |editor, cx| {
             let mut original_positions: HashMap<_, _> = Default::default();
-            editor.change_selections(None, cx, |s| {
-                s.move_with(|map, selection| {
-                    let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
-                    original_positions.insert(selection.id, anchor);
-                    object.expand_selection(map, selection, around);
+            vim.with_object_cache(|cache| {
+                editor.change_selections(None, cx, |s| {
+                    s.move_with(|map, selection| {
+                        let anchor = map.display_point_to_anchor(selection.head(), Bias::Right);
+                        original_positions.insert(selection.id, anchor);
+                        object.expand_selection(map, selection, around, times, cache);
+                    });
                 });
             });
             editor.toggle_comments(&Default::default(), cx);
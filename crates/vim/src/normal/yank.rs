@@ -2,6 +2,7 @@ use std::time::Duration;
 
 use crate::{
     motion::Motion,
+    normal::repeat::block_to_end_of_line,
     object::Object,
     state::{Mode, Register},
     Vim,
@@ -37,16 +38,24 @@ pub fn yank_motion(vim: &mut Vim, motion: Motion, times: Option<usize>, cx: &mut
     });
 }
 
-pub fn yank_object(vim: &mut Vim, object: Object, around: bool, cx: &mut WindowContext) {
+pub fn yank_object(
+    vim: &mut Vim,
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    cx: &mut WindowContext,
+) {
     vim.update_active_editor(cx, |vim, editor, cx| {
         editor.transact(cx, |editor, cx| {
             editor.set_clip_at_line_ends(false, cx);
             let mut original_positions: HashMap<_, _> = Default::default();
-            editor.change_selections(None, cx, |s| {
-                s.move_with(|map, selection| {
-                    let original_position = (selection.head(), selection.goal);
-                    object.expand_selection(map, selection, around);
-                    original_positions.insert(selection.id, original_position);
+            vim.with_object_cache(|cache| {
+                editor.change_selections(None, cx, |s| {
+                    s.move_with(|map, selection| {
+                        let original_position = (selection.head(), selection.goal);
+                        object.expand_selection(map, selection, around, times, cache);
+                        original_positions.insert(selection.id, original_position);
+                    });
                 });
             });
             yank_selections_content(vim, editor, false, cx);
@@ -92,6 +101,8 @@ fn copy_selections_content_internal(
     let mut text = String::new();
     let mut clipboard_selections = Vec::with_capacity(selections.len());
     let mut ranges_to_highlight = Vec::new();
+    let blockwise = vim.state().mode == Mode::VisualBlock && !linewise;
+    let ragged_block = blockwise && block_to_end_of_line(editor);
 
     vim.update_state(|state| {
         state.marks.insert(
@@ -112,9 +123,23 @@ fn copy_selections_content_internal(
 
     {
         let mut is_first = true;
+        let mut previous_row = None;
         for selection in selections.iter() {
             let mut start = selection.start;
             let end = selection.end;
+            // Lines too short to reach a block have no selection, but still
+            // hold an (empty) row of it so that it keeps its shape when put.
+            if let Some(previous_row) = previous_row.filter(|_| blockwise) {
+                for row in previous_row + 1..start.row {
+                    text.push_str("\n");
+                    clipboard_selections.push(ClipboardSelection {
+                        len: 0,
+                        is_entire_line: false,
+                        first_line_indent: buffer.indent_size_for_line(MultiBufferRow(row)).len,
+                    });
+                }
+            }
+            previous_row = Some(end.row);
             if is_first {
                 is_first = false;
             } else {
@@ -154,11 +179,26 @@ fn copy_selections_content_internal(
         }
     }
 
+    if is_yank {
+        // Blocks are joined with newlines, one selection per row.
+        let mut lines = text.matches('\n').count() as u32;
+        if !linewise || !text.ends_with('\n') {
+            lines += 1;
+        }
+        let message = if vim.state().mode == Mode::VisualBlock && !linewise {
+            format!("block of {} lines yanked", lines)
+        } else {
+            format!("{} lines yanked", lines)
+        };
+        vim.report(lines, message, cx);
+    }
+
     let selected_register = vim.update_state(|state| state.selected_register.take());
     vim.write_registers(
         Register {
             text: text.into(),
             clipboard_selections: Some(clipboard_selections),
+            ragged_block,
         },
         selected_register,
         is_yank,
@@ -7,21 +7,20 @@ use crate::{
     visual::visual_object,
     Vim,
 };
+use collections::HashMap;
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement::{self, FindRange},
     Bias, DisplayPoint,
 };
 
-use itertools::Itertools;
-
 use gpui::{actions, impl_actions, ViewContext, WindowContext};
-use language::{char_kind, BufferSnapshot, CharKind, Point, Selection};
-use multi_buffer::MultiBufferRow;
+use language::{char_kind, BufferSnapshot, CharKind, Node, Point, Selection};
+use multi_buffer::{MultiBufferRow, MultiBufferSnapshot};
 use serde::Deserialize;
 use workspace::Workspace;
 
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Deserialize)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
 pub enum Object {
     Word { ignore_punctuation: bool },
     Sentence,
@@ -29,13 +28,21 @@ pub enum Object {
     Quotes,
     BackQuotes,
     DoubleQuotes,
+    AnyQuotes,
     VerticalBars,
+    Slashes,
     Parentheses,
     SquareBrackets,
     CurlyBrackets,
     AngleBrackets,
     Argument,
     Tag,
+    IndentObj { include_below: bool },
+    Interpolation,
+    MatchingPair,
+    FunctionCall,
+    Macro,
+    Section,
 }
 
 #[derive(Clone, Deserialize, PartialEq)]
@@ -45,7 +52,14 @@ struct Word {
     ignore_punctuation: bool,
 }
 
-impl_actions!(vim, [Word]);
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+struct IndentObj {
+    #[serde(default)]
+    include_below: bool,
+}
+
+impl_actions!(vim, [Word, IndentObj]);
 
 actions!(
     vim,
@@ -55,13 +69,20 @@ actions!(
         Quotes,
         BackQuotes,
         DoubleQuotes,
+        AnyQuotes,
         VerticalBars,
+        Slashes,
         Parentheses,
         SquareBrackets,
         CurlyBrackets,
         AngleBrackets,
         Argument,
-        Tag
+        Tag,
+        Interpolation,
+        MatchingPair,
+        FunctionCall,
+        Macro,
+        Section
     ]
 );
 
@@ -72,6 +93,11 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         },
     );
     workspace.register_action(|_: &mut Workspace, _: &Tag, cx: _| object(Object::Tag, cx));
+    workspace.register_action(
+        |_: &mut Workspace, &IndentObj { include_below }: &IndentObj, cx: _| {
+            object(Object::IndentObj { include_below }, cx)
+        },
+    );
     workspace
         .register_action(|_: &mut Workspace, _: &Sentence, cx: _| object(Object::Sentence, cx));
     workspace
@@ -82,6 +108,8 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, _: &DoubleQuotes, cx: _| {
         object(Object::DoubleQuotes, cx)
     });
+    workspace
+        .register_action(|_: &mut Workspace, _: &AnyQuotes, cx: _| object(Object::AnyQuotes, cx));
     workspace.register_action(|_: &mut Workspace, _: &Parentheses, cx: _| {
         object(Object::Parentheses, cx)
     });
@@ -97,8 +125,20 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     workspace.register_action(|_: &mut Workspace, _: &VerticalBars, cx: _| {
         object(Object::VerticalBars, cx)
     });
+    workspace.register_action(|_: &mut Workspace, _: &Slashes, cx: _| object(Object::Slashes, cx));
     workspace
         .register_action(|_: &mut Workspace, _: &Argument, cx: _| object(Object::Argument, cx));
+    workspace.register_action(|_: &mut Workspace, _: &Interpolation, cx: _| {
+        object(Object::Interpolation, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &MatchingPair, cx: _| {
+        object(Object::MatchingPair, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &FunctionCall, cx: _| {
+        object(Object::FunctionCall, cx)
+    });
+    workspace.register_action(|_: &mut Workspace, _: &Macro, cx: _| object(Object::Macro, cx));
+    workspace.register_action(|_: &mut Workspace, _: &Section, cx: _| object(Object::Section, cx));
 }
 
 fn object(object: Object, cx: &mut WindowContext) {
@@ -111,14 +151,68 @@ fn object(object: Object, cx: &mut WindowContext) {
     }
 }
 
+/// The most lookups an `ObjectCache` holds before it starts over.
+const OBJECT_CACHE_SIZE: usize = 64;
+
+/// Remembers where the objects that are found through the syntax tree were
+/// found in an editor, so that looking the same object up again from the
+/// same selection (as visual mode and dot-repeat do) doesn't walk the tree
+/// again. The cache is emptied whenever the buffer or its syntax changes.
+#[derive(Default, Clone)]
+pub struct ObjectCache {
+    version: (usize, usize),
+    ranges: HashMap<ObjectCacheKey, Option<Range<usize>>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Hash)]
+struct ObjectCacheKey {
+    object: Object,
+    around: bool,
+    times: Option<usize>,
+    selection: Range<usize>,
+    reversed: bool,
+}
+
+impl ObjectCache {
+    fn get_or_insert_with(
+        &mut self,
+        buffer: &MultiBufferSnapshot,
+        key: ObjectCacheKey,
+        range: impl FnOnce() -> Option<Range<usize>>,
+    ) -> Option<Range<usize>> {
+        let version = (buffer.edit_count(), buffer.non_text_state_update_count());
+        if self.version != version || self.ranges.len() >= OBJECT_CACHE_SIZE {
+            self.version = version;
+            self.ranges.clear();
+        }
+        self.ranges.entry(key).or_insert_with(range).clone()
+    }
+}
+
 impl Object {
+    /// Whether finding this object means querying the syntax tree, in which
+    /// case `cached_range` remembers the result.
+    fn uses_syntax(self) -> bool {
+        matches!(
+            self,
+            Object::Tag
+                | Object::Argument
+                | Object::Interpolation
+                | Object::MatchingPair
+                | Object::FunctionCall
+                | Object::Macro
+        )
+    }
+
     pub fn is_multiline(self) -> bool {
         match self {
             Object::Word { .. }
             | Object::Quotes
             | Object::BackQuotes
             | Object::VerticalBars
-            | Object::DoubleQuotes => false,
+            | Object::Slashes
+            | Object::DoubleQuotes
+            | Object::AnyQuotes => false,
             Object::Sentence
             | Object::Paragraph
             | Object::Parentheses
@@ -126,23 +220,57 @@ impl Object {
             | Object::AngleBrackets
             | Object::CurlyBrackets
             | Object::SquareBrackets
-            | Object::Argument => true,
+            | Object::Argument
+            | Object::IndentObj { .. }
+            | Object::Interpolation
+            | Object::MatchingPair
+            | Object::FunctionCall
+            | Object::Macro
+            | Object::Section => true,
         }
     }
 
     pub fn always_expands_both_ways(self) -> bool {
         match self {
-            Object::Word { .. } | Object::Sentence | Object::Paragraph | Object::Argument => false,
+            Object::Word { .. }
+            | Object::Sentence
+            | Object::Paragraph
+            | Object::Argument
+            | Object::IndentObj { .. }
+            | Object::Section => false,
             Object::Quotes
             | Object::BackQuotes
             | Object::DoubleQuotes
+            | Object::AnyQuotes
             | Object::VerticalBars
+            | Object::Slashes
             | Object::Parentheses
             | Object::SquareBrackets
             | Object::Tag
             | Object::CurlyBrackets
-            | Object::AngleBrackets => true,
+            | Object::AngleBrackets
+            | Object::Interpolation
+            | Object::MatchingPair
+            | Object::FunctionCall
+            | Object::Macro => true,
+        }
+    }
+
+    /// Whether the expanded inner object covers whole lines, which happens
+    /// to a multi-line block whose brackets end and start their lines. Vim
+    /// deletes these linewise.
+    pub(crate) fn covers_whole_lines(
+        self,
+        map: &DisplaySnapshot,
+        selection: &Selection<DisplayPoint>,
+        around: bool,
+    ) -> bool {
+        if around || !self.always_expands_both_ways() {
+            return false;
         }
+        let start = selection.start.to_point(map);
+        let end = selection.end.to_point(map);
+        start.column == 0 && end.column == 0 && end.row > start.row
     }
 
     pub fn target_visual_mode(self, current_mode: Mode) -> Mode {
@@ -151,7 +279,8 @@ impl Object {
             | Object::Sentence
             | Object::Quotes
             | Object::BackQuotes
-            | Object::DoubleQuotes => {
+            | Object::DoubleQuotes
+            | Object::AnyQuotes => {
                 if current_mode == Mode::VisualBlock {
                     Mode::VisualBlock
                 } else {
@@ -163,9 +292,14 @@ impl Object {
             | Object::CurlyBrackets
             | Object::AngleBrackets
             | Object::VerticalBars
+            | Object::Slashes
             | Object::Tag
-            | Object::Argument => Mode::Visual,
-            Object::Paragraph => Mode::VisualLine,
+            | Object::Argument
+            | Object::Interpolation
+            | Object::MatchingPair
+            | Object::FunctionCall
+            | Object::Macro => Mode::Visual,
+            Object::Paragraph | Object::IndentObj { .. } | Object::Section => Mode::VisualLine,
         }
     }
 
@@ -174,6 +308,7 @@ impl Object {
         map: &DisplaySnapshot,
         selection: Selection<DisplayPoint>,
         around: bool,
+        times: Option<usize>,
     ) -> Option<Range<DisplayPoint>> {
         let relative_to = selection.head();
         match self {
@@ -185,7 +320,7 @@ impl Object {
                 }
             }
             Object::Sentence => sentence(map, relative_to, around),
-            Object::Paragraph => paragraph(map, relative_to, around),
+            Object::Paragraph => paragraph(map, relative_to, around, times.unwrap_or(1)),
             Object::Quotes => {
                 surrounding_markers(map, relative_to, around, self.is_multiline(), '\'', '\'')
             }
@@ -195,24 +330,71 @@ impl Object {
             Object::DoubleQuotes => {
                 surrounding_markers(map, relative_to, around, self.is_multiline(), '"', '"')
             }
+            Object::AnyQuotes => any_quotes(map, relative_to, around),
             Object::VerticalBars => {
                 surrounding_markers(map, relative_to, around, self.is_multiline(), '|', '|')
             }
+            Object::Slashes => {
+                surrounding_markers(map, relative_to, around, self.is_multiline(), '/', '/')
+            }
             Object::Parentheses => {
-                surrounding_markers(map, relative_to, around, self.is_multiline(), '(', ')')
+                nested_brackets(map, relative_to, around, times, |point, around| {
+                    surrounding_markers(map, point, around, self.is_multiline(), '(', ')')
+                })
             }
             Object::Tag => surrounding_html_tag(map, selection, around),
             Object::SquareBrackets => {
-                surrounding_markers(map, relative_to, around, self.is_multiline(), '[', ']')
+                nested_brackets(map, relative_to, around, times, |point, around| {
+                    surrounding_markers(map, point, around, self.is_multiline(), '[', ']')
+                })
             }
             Object::CurlyBrackets => {
-                surrounding_markers(map, relative_to, around, self.is_multiline(), '{', '}')
+                nested_brackets(map, relative_to, around, times, |point, around| {
+                    surrounding_markers(map, point, around, self.is_multiline(), '{', '}')
+                })
             }
             Object::AngleBrackets => {
-                surrounding_markers(map, relative_to, around, self.is_multiline(), '<', '>')
+                nested_brackets(map, relative_to, around, times, |point, around| {
+                    angle_brackets(map, point, around)
+                })
             }
             Object::Argument => argument(map, relative_to, around),
+            Object::IndentObj { include_below } => indent(map, relative_to, around, include_below),
+            Object::Interpolation => interpolation(map, relative_to, around),
+            Object::MatchingPair => matching_pair(map, relative_to, around),
+            Object::FunctionCall => function_call(map, relative_to, around),
+            Object::Macro => macro_invocation(map, relative_to, around),
+            Object::Section => section(map, relative_to, around),
+        }
+    }
+
+    /// Like `range`, but reuses an earlier result from `cache` for objects
+    /// that are found through the syntax tree.
+    pub fn cached_range(
+        self,
+        map: &DisplaySnapshot,
+        selection: Selection<DisplayPoint>,
+        around: bool,
+        times: Option<usize>,
+        cache: &mut ObjectCache,
+    ) -> Option<Range<DisplayPoint>> {
+        if !self.uses_syntax() {
+            return self.range(map, selection, around, times);
         }
+        let key = ObjectCacheKey {
+            object: self,
+            around,
+            times,
+            selection: selection.start.to_offset(map, Bias::Left)
+                ..selection.end.to_offset(map, Bias::Left),
+            reversed: selection.reversed,
+        };
+        cache
+            .get_or_insert_with(&map.buffer_snapshot, key, || {
+                let range = self.range(map, selection, around, times)?;
+                Some(range.start.to_offset(map, Bias::Left)..range.end.to_offset(map, Bias::Left))
+            })
+            .map(|range| range.start.to_display_point(map)..range.end.to_display_point(map))
     }
 
     pub fn expand_selection(
@@ -220,8 +402,10 @@ impl Object {
         map: &DisplaySnapshot,
         selection: &mut Selection<DisplayPoint>,
         around: bool,
+        times: Option<usize>,
+        cache: &mut ObjectCache,
     ) -> bool {
-        if let Some(range) = self.range(map, selection.clone(), around) {
+        if let Some(range) = self.cached_range(map, selection.clone(), around, times, cache) {
             selection.start = range.start;
             selection.end = range.end;
             true
@@ -284,6 +468,55 @@ fn surrounding_html_tag(
         }
         Some(read_tag(chars))
     }
+    // Nodes whose text can't open a comment or CDATA section.
+    fn skipped_ranges(node: Node, offset: usize, ranges: &mut Vec<Range<usize>>) {
+        let mut cursor = node.walk();
+        for child in node.children(&mut cursor) {
+            if child.start_byte() >= offset {
+                break;
+            }
+            match child.kind() {
+                "comment" | "attribute_value" | "quoted_attribute_value" | "raw_text" => {
+                    ranges.push(child.byte_range())
+                }
+                _ => skipped_ranges(child, offset, ranges),
+            }
+        }
+    }
+    // Tags in comments and CDATA sections are just text, even if they were
+    // parsed as elements (CDATA isn't part of HTML, so it won't be). Only the
+    // element enclosing the tag needs to be scanned for them.
+    fn in_comment_or_cdata(buffer: &BufferSnapshot, enclosing: Node, offset: usize) -> bool {
+        let mut skipped = Vec::new();
+        skipped_ranges(enclosing, offset, &mut skipped);
+        let mut skipped = skipped.into_iter().peekable();
+
+        let mut closer = None;
+        let mut ix = enclosing.start_byte();
+        for c in buffer.chars_for_range(ix..offset) {
+            let at = ix;
+            ix += c.len_utf8();
+            while skipped.next_if(|range| range.end <= at).is_some() {}
+            if skipped.peek().is_some_and(|range| range.start <= at) {
+                continue;
+            }
+            match closer {
+                Some(end) => {
+                    if buffer.contains_str_at(at, end) {
+                        closer = None;
+                    }
+                }
+                None if c == '<' => {
+                    closer = [("<!--", "-->"), ("<![CDATA[", "]]>")]
+                        .into_iter()
+                        .find(|(start, _)| buffer.contains_str_at(at, start))
+                        .map(|(_, end)| end);
+                }
+                None => {}
+            }
+        }
+        closer.is_some()
+    }
 
     let snapshot = &map.buffer_snapshot;
     let offset = selection.head().to_offset(map, Bias::Left);
@@ -306,6 +539,7 @@ fn surrounding_html_tag(
             if let (Some(first_child), Some(last_child)) = (first_child, last_child) {
                 let open_tag = open_tag(buffer.chars_for_range(first_child.byte_range()));
                 let close_tag = close_tag(buffer.chars_for_range(last_child.byte_range()));
+                let enclosing = cur_node.parent().unwrap_or(cur_node);
                 // It needs to be handled differently according to the selection length
                 let is_valid = if selection.end.to_offset(map, Bias::Left)
                     - selection.start.to_offset(map, Bias::Left)
@@ -316,7 +550,12 @@ fn surrounding_html_tag(
                     selection.start.to_offset(map, Bias::Left) >= first_child.start_byte()
                         && selection.end.to_offset(map, Bias::Left) <= last_child.start_byte() + 1
                 };
-                if open_tag.is_some() && open_tag == close_tag && is_valid {
+                if open_tag.is_some()
+                    && open_tag == close_tag
+                    && is_valid
+                    && !in_comment_or_cdata(buffer, enclosing, first_child.start_byte())
+                    && !in_comment_or_cdata(buffer, enclosing, last_child.start_byte())
+                {
                     let range = if around {
                         first_child.byte_range().start..last_child.byte_range().end
                     } else {
@@ -711,45 +950,514 @@ fn expand_to_include_whitespace(
 /// EOF), the returned range ends at the trailing newline of the paragraph (i.e.
 /// the trailing newline is not subject to subsequent operations).
 ///
+/// With a count, inner paragraphs count each block of blank lines as a
+/// paragraph of its own, while around paragraphs always include the blank
+/// lines that follow each paragraph (this follows vim's `current_par`).
+///
 /// Edge cases:
+/// - If the count extends past the end of the file, the selection results in
+///   an error.
 /// - If `around` and if the current paragraph is the last paragraph of the
 ///   file and is blank, then the selection results in an error.
-/// - If `around` and if the current paragraph is the last paragraph of the
-///   file and is not blank, then the returned range starts at the start of the
-///   previous paragraph, if it exists.
+/// - If `around` and if the selected paragraphs are not followed by blank
+///   lines, then the returned range is extended backwards to include the blank
+///   lines before the current paragraph, if any.
 fn paragraph(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
     around: bool,
+    times: usize,
+) -> Option<Range<DisplayPoint>> {
+    // Rows are signed here, as the end of the range starts out on the row
+    // before the paragraph when that paragraph isn't blank.
+    let is_blank = |row: i64| {
+        map.buffer_snapshot
+            .is_line_blank(MultiBufferRow(row as u32))
+    };
+    let max_row = map.max_buffer_row().0 as i64;
+    let current_line_is_blank = is_blank(relative_to.to_point(map).row as i64);
+
+    let mut start_row = start_of_paragraph(map, relative_to).to_point(map).row as i64;
+    let mut end_row = if current_line_is_blank {
+        end_of_paragraph(map, relative_to).to_point(map).row as i64
+    } else {
+        start_row - 1
+    };
+
+    let mut times = times.max(1);
+    if !around && current_line_is_blank {
+        times -= 1;
+    }
+    for remaining in (0..times).rev() {
+        if end_row == max_row {
+            return None;
+        }
+        let next_is_blank = is_blank(end_row + 1);
+        if around || !next_is_blank {
+            end_row += 1;
+            while end_row < max_row && !is_blank(end_row + 1) {
+                end_row += 1;
+            }
+        }
+        if remaining == 0 && current_line_is_blank && around {
+            break;
+        }
+        if around || next_is_blank {
+            while end_row < max_row && is_blank(end_row + 1) {
+                end_row += 1;
+            }
+        }
+    }
+
+    if around && !current_line_is_blank && !is_blank(end_row) {
+        while start_row > 0 && is_blank(start_row - 1) {
+            start_row -= 1;
+        }
+    }
+
+    let (start_row, end_row) = (start_row as u32, end_row as u32);
+    let start = Point::new(start_row, 0).to_display_point(map);
+    let end = if end_row == map.max_buffer_row().0 {
+        map.max_point()
+    } else {
+        Point::new(
+            end_row,
+            map.buffer_snapshot.line_len(MultiBufferRow(end_row)),
+        )
+        .to_display_point(map)
+    };
+    Some(start..end)
+}
+
+/// Returns the lines around `relative_to` that are indented at least as far as
+/// its line, with `around` adding the line above and, if `include_below` is
+/// also set, the line below. Either is left out at the edges of the buffer.
+/// Indentation is measured in display columns, so that lines indented
+/// with tabs and with spaces to the same level belong together.
+fn indent(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+    include_below: bool,
 ) -> Option<Range<DisplayPoint>> {
-    let mut paragraph_start = start_of_paragraph(map, relative_to);
-    let mut paragraph_end = end_of_paragraph(map, relative_to);
+    let row = relative_to.to_point(map).row;
+    let max_row = map.max_buffer_row().0;
+
+    // On a blank line, use the indentation of the code that follows it.
+    let target = (row..=max_row)
+        .chain((0..row).rev())
+        .find_map(|row| indent_width(map, row))?;
+    let in_block = |row| indent_width(map, row).map_or(true, |width| width >= target);
+
+    let mut start_row = row;
+    while start_row > 0 && in_block(start_row - 1) {
+        start_row -= 1;
+    }
+    let mut end_row = row;
+    while end_row < max_row && in_block(end_row + 1) {
+        end_row += 1;
+    }
+    // Blank lines at either end separate the block from what surrounds it.
+    while start_row < row && indent_width(map, start_row).is_none() {
+        start_row += 1;
+    }
+    while end_row > row && indent_width(map, end_row).is_none() {
+        end_row -= 1;
+    }
+
+    if around && start_row > 0 {
+        start_row -= 1;
+    }
+    if around && include_below && end_row < max_row {
+        end_row += 1;
+    }
+
+    let start = Point::new(start_row, 0).to_display_point(map);
+    let end = if end_row == max_row {
+        map.max_point()
+    } else {
+        Point::new(
+            end_row,
+            map.buffer_snapshot.line_len(MultiBufferRow(end_row)),
+        )
+        .to_display_point(map)
+    };
+    Some(start..end)
+}
 
-    let paragraph_end_row = paragraph_end.row();
-    let paragraph_ends_with_eof = paragraph_end_row == map.max_point().row();
+/// Returns the lines under the markdown heading above `relative_to`, down to
+/// the next heading at the same or a higher level (or the end of the buffer),
+/// so that a section takes its subsections with it. With `around`, the
+/// heading itself is included. Only markdown buffers have sections.
+fn section(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
     let point = relative_to.to_point(map);
-    let current_line_is_empty = map.buffer_snapshot.is_line_blank(MultiBufferRow(point.row));
+    let (buffer, _) = map.buffer_snapshot.point_to_buffer_offset(point)?;
+    if buffer.language()?.name().as_ref() != "Markdown" {
+        return None;
+    }
+    let row = point.row;
+    let max_row = map.max_buffer_row().0;
+    let headings = markdown_headings(&map.buffer_snapshot);
+
+    let &(heading_row, level) = headings.iter().rev().find(|(start, _)| *start <= row)?;
+    let end_row = headings
+        .iter()
+        .find(|&&(start, other_level)| start > heading_row && other_level <= level)
+        .map_or(max_row, |(start, _)| start - 1);
+
+    let start_row = if around { heading_row } else { heading_row + 1 };
+    if start_row > end_row {
+        return None;
+    }
+    let start = Point::new(start_row, 0).to_display_point(map);
+    let end = if end_row == max_row {
+        map.max_point()
+    } else {
+        Point::new(
+            end_row,
+            map.buffer_snapshot.line_len(MultiBufferRow(end_row)),
+        )
+        .to_display_point(map)
+    };
+    Some(start..end)
+}
 
-    if around {
-        if paragraph_ends_with_eof {
-            if current_line_is_empty {
-                return None;
+/// The rows and levels of the `#` headings in `buffer`, leaving out any lines
+/// in fenced code blocks.
+fn markdown_headings(buffer: &MultiBufferSnapshot) -> Vec<(u32, usize)> {
+    let mut headings = Vec::new();
+    let mut fence: Option<&str> = None;
+    for row in 0..=buffer.max_buffer_row().0 {
+        let line = buffer
+            .text_for_range(
+                Point::new(row, 0)..Point::new(row, buffer.line_len(MultiBufferRow(row))),
+            )
+            .collect::<String>();
+        let line = line.trim_start_matches(' ');
+        if let Some(marker) = ["```", "~~~"].into_iter().find(|m| line.starts_with(m)) {
+            match fence {
+                Some(open) if open == marker => fence = None,
+                Some(_) => {}
+                None => fence = Some(marker),
             }
+            continue;
+        }
+        if fence.is_some() {
+            continue;
+        }
+        let level = line.chars().take_while(|c| *c == '#').count();
+        let rest = &line[level..];
+        if (1..=6).contains(&level) && (rest.is_empty() || rest.starts_with([' ', '\t'])) {
+            headings.push((row, level));
+        }
+    }
+    headings
+}
+
+/// Returns the display column where the text on `row` starts, or `None` if
+/// the line is blank.
+fn indent_width(map: &DisplaySnapshot, row: u32) -> Option<u32> {
+    let row = MultiBufferRow(row);
+    if map.buffer_snapshot.is_line_blank(row) {
+        return None;
+    }
+    let indent = map.buffer_snapshot.indent_size_for_line(row);
+    Some(Point::new(row.0, indent.len).to_display_point(map).column())
+}
+
+/// Returns the range of the string interpolation (`${...}` or `#{...}`)
+/// containing `relative_to`, including the `${` and `}` if `around` is set.
+///
+/// The syntax tree is used to find the interpolation where the language has
+/// one, otherwise the braces are matched in the text.
+fn interpolation(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let snapshot = &map.buffer_snapshot;
+    let offset = relative_to.to_offset(map, Bias::Left);
+    let range =
+        syntax_interpolation(snapshot, offset).or_else(|| text_interpolation(snapshot, offset))?;
+    // Both `${` and `#{` are two bytes long, and `}` is one.
+    let range = if around {
+        range
+    } else {
+        range.start + 2..range.end - 1
+    };
+    Some(range.start.to_display_point(map)..range.end.to_display_point(map))
+}
+
+/// Returns the innermost block around `relative_to` whose ends `%` jumps
+/// between, preferring those delimited by keywords (such as Ruby's `def` and
+/// `end`) to brackets. Inside a block spanning several lines is the lines
+/// between its delimiters, as with brackets.
+fn matching_pair(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let snapshot = &map.buffer_snapshot;
+    let offset = relative_to.to_offset(map, Bias::Left);
+    let is_keyword = |open: Range<usize>, _: Range<usize>| {
+        snapshot
+            .chars_at(open.start)
+            .next()
+            .map_or(false, char::is_alphabetic)
+    };
+    let (open, close) = snapshot
+        .innermost_enclosing_bracket_ranges(offset..offset, Some(&is_keyword))
+        .or_else(|| snapshot.innermost_enclosing_bracket_ranges(offset..offset, None))?;
+
+    if around {
+        return Some(open.start.to_display_point(map)..close.end.to_display_point(map));
+    }
+    let open_end = snapshot.offset_to_point(open.end);
+    let close_start = snapshot.offset_to_point(close.start);
+    if close_start.row > open_end.row {
+        Some(
+            Point::new(open_end.row + 1, 0).to_display_point(map)
+                ..Point::new(close_start.row, 0).to_display_point(map),
+        )
+    } else {
+        Some(open.end.to_display_point(map)..close.start.to_display_point(map))
+    }
+}
+
+/// Returns the innermost function call around `relative_to`, including what
+/// it's called on, or the arguments between its parentheses.
+fn function_call(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let snapshot = &map.buffer_snapshot;
+    let offset = relative_to.to_offset(map, Bias::Left);
+    let excerpt = snapshot.excerpt_containing(offset..offset)?;
+    let buffer = excerpt.buffer();
+    let offset = excerpt.map_offset_to_buffer(offset);
+
+    let mut cursor = buffer.syntax_layer_at(offset)?.node().walk();
+    let mut node = cursor.node();
+    while cursor.goto_first_child_for_byte(offset).is_some() {
+        node = cursor.node();
+    }
 
-            let paragraph_start_row = paragraph_start.row();
-            if paragraph_start_row.0 != 0 {
-                let previous_paragraph_last_line_start =
-                    Point::new(paragraph_start_row.0 - 1, 0).to_display_point(map);
-                paragraph_start = start_of_paragraph(map, previous_paragraph_last_line_start);
+    let mut node = Some(node);
+    while let Some(current) = node {
+        // Grammars name calls differently (`call_expression`, `call`,
+        // `method_invocation`, ...) but agree on the field for the arguments.
+        let is_call = current.kind().contains("call") || current.kind().contains("invocation");
+        if let Some(arguments) = current.child_by_field_name("arguments").filter(|_| is_call) {
+            let arguments = arguments.byte_range();
+            if buffer.contains_str_at(arguments.start, "(")
+                && buffer.contains_str_at(arguments.end.saturating_sub(1), ")")
+                && excerpt.contains_buffer_range(current.byte_range())
+            {
+                let range = if around {
+                    current.byte_range()
+                } else {
+                    arguments.start + 1..arguments.end - 1
+                };
+                let range = excerpt.map_range_from_buffer(range);
+                return Some(range.start.to_display_point(map)..range.end.to_display_point(map));
             }
+        }
+        node = current.parent();
+    }
+    None
+}
+
+/// The tokens of a Rust macro invocation or attribute. Macros nested in
+/// another's tokens aren't parsed as invocations, so they're recognized by the
+/// `!` before their token tree.
+fn macro_invocation(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let snapshot = &map.buffer_snapshot;
+    let offset = relative_to.to_offset(map, Bias::Left);
+    let excerpt = snapshot.excerpt_containing(offset..offset)?;
+    let buffer = excerpt.buffer();
+    let offset = excerpt.map_offset_to_buffer(offset);
+
+    let mut cursor = buffer.syntax_layer_at(offset)?.node().walk();
+    let mut node = cursor.node();
+    while cursor.goto_first_child_for_byte(offset).is_some() {
+        node = cursor.node();
+    }
+
+    let mut node = Some(node);
+    while let Some(current) = node {
+        let bang = if current.kind() == "!" {
+            Some(current)
+        } else {
+            current.next_sibling().filter(|next| next.kind() == "!")
+        };
+        let macro_tokens = bang
+            .and_then(|bang| bang.next_sibling())
+            .or_else(|| {
+                let is_after_bang = current.prev_sibling().map(|prev| prev.kind()) == Some("!");
+                is_after_bang.then_some(current)
+            })
+            .filter(|tokens| tokens.kind() == "token_tree");
+
+        let ranges = if let Some(tokens) = macro_tokens {
+            let start = match tokens.parent() {
+                Some(parent) if parent.kind() == "macro_invocation" => parent.start_byte(),
+                _ => {
+                    let bang = tokens.prev_sibling()?;
+                    bang.prev_sibling().unwrap_or(bang).start_byte()
+                }
+            };
+            let tokens = tokens.byte_range();
+            Some((start..tokens.end, tokens.start + 1..tokens.end - 1))
+        } else if current.kind() == "attribute" {
+            current.parent().map(|item| {
+                let inner = match current.child_by_field_name("arguments") {
+                    Some(arguments) => arguments.start_byte() + 1..arguments.end_byte() - 1,
+                    None => current.byte_range(),
+                };
+                (item.byte_range(), inner)
+            })
         } else {
-            let next_paragraph_start = Point::new(paragraph_end_row.0 + 1, 0).to_display_point(map);
-            paragraph_end = end_of_paragraph(map, next_paragraph_start);
+            None
+        };
+
+        if let Some((outer, inner)) = ranges {
+            if excerpt.contains_buffer_range(outer.clone()) {
+                let range = excerpt.map_range_from_buffer(if around { outer } else { inner });
+                return Some(range.start.to_display_point(map)..range.end.to_display_point(map));
+            }
         }
+        node = current.parent();
     }
+    None
+}
 
-    let range = paragraph_start..paragraph_end;
-    Some(range)
+fn syntax_interpolation(snapshot: &MultiBufferSnapshot, offset: usize) -> Option<Range<usize>> {
+    let excerpt = snapshot.excerpt_containing(offset..offset)?;
+    let buffer = excerpt.buffer();
+    let offset = excerpt.map_offset_to_buffer(offset);
+
+    let mut cursor = buffer.syntax_layer_at(offset)?.node().walk();
+    let mut node = cursor.node();
+    while cursor.goto_first_child_for_byte(offset).is_some() {
+        node = cursor.node();
+    }
+
+    let mut node = Some(node);
+    while let Some(current) = node {
+        let range = current.byte_range();
+        // Other languages use these node kinds for `{...}` interpolations, so
+        // check the delimiters too.
+        if matches!(current.kind(), "template_substitution" | "interpolation")
+            && (buffer.contains_str_at(range.start, "${")
+                || buffer.contains_str_at(range.start, "#{"))
+            && buffer.contains_str_at(range.end.saturating_sub(1), "}")
+            && excerpt.contains_buffer_range(range.clone())
+        {
+            return Some(excerpt.map_range_from_buffer(range));
+        }
+        node = current.parent();
+    }
+    None
+}
+
+fn text_interpolation(snapshot: &MultiBufferSnapshot, offset: usize) -> Option<Range<usize>> {
+    let is_sigil = |c: Option<char>| matches!(c, Some('$' | '#'));
+    let bounds = interpolation_bounds(snapshot, offset);
+
+    let mut chars = snapshot.chars_at(offset);
+    let start = match (chars.next(), chars.next()) {
+        (sigil, Some('{')) if is_sigil(sigil) => offset,
+        (Some('{'), _) if is_sigil(snapshot.reversed_chars_at(offset).next()) => offset - 1,
+        _ => {
+            // Find the nearest unmatched `{` before the cursor that follows a
+            // sigil, skipping over any plain braces nested inside it.
+            let mut depth = 0;
+            let mut ix = offset;
+            let mut reversed = snapshot.reversed_chars_at(offset).peekable();
+            loop {
+                if ix <= bounds.start {
+                    return None;
+                }
+                let c = reversed.next()?;
+                ix -= c.len_utf8();
+                match c {
+                    '}' => depth += 1,
+                    '{' if depth > 0 => depth -= 1,
+                    '{' if ix > bounds.start && is_sigil(reversed.peek().copied()) => break ix - 1,
+                    _ => {}
+                }
+            }
+        }
+    };
+    if start < bounds.start || start + 2 > bounds.end {
+        return None;
+    }
+
+    let mut depth = 0;
+    let mut end = start + 2;
+    for c in snapshot
+        .text_for_range(end..bounds.end)
+        .flat_map(str::chars)
+    {
+        end += c.len_utf8();
+        match c {
+            '{' => depth += 1,
+            '}' if depth == 0 => return Some(start..end),
+            '}' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the text that an interpolation found without the syntax tree can
+/// span around `offset`: the string it's in, or the line if it isn't in one.
+fn interpolation_bounds(snapshot: &MultiBufferSnapshot, offset: usize) -> Range<usize> {
+    let string = snapshot
+        .excerpt_containing(offset..offset)
+        .and_then(|excerpt| {
+            let buffer = excerpt.buffer();
+            let buffer_offset = excerpt.map_offset_to_buffer(offset);
+
+            let mut cursor = buffer.syntax_layer_at(buffer_offset)?.node().walk();
+            let mut node = cursor.node();
+            while cursor.goto_first_child_for_byte(buffer_offset).is_some() {
+                node = cursor.node();
+            }
+
+            // Strings can be made of several nodes, such as their contents
+            // and escapes, so take the outermost.
+            let mut node = Some(node);
+            let mut string = None;
+            while let Some(current) = node {
+                if current.kind().contains("string") {
+                    string = Some(current);
+                } else if string.is_some() {
+                    break;
+                }
+                node = current.parent();
+            }
+            let range = string?.byte_range();
+            excerpt
+                .contains_buffer_range(range.clone())
+                .then(|| excerpt.map_range_from_buffer(range))
+        });
+
+    string.unwrap_or_else(|| {
+        let row = snapshot.offset_to_point(offset).row;
+        let line_len = snapshot.line_len(MultiBufferRow(row));
+        snapshot.point_to_offset(Point::new(row, 0))
+            ..snapshot.point_to_offset(Point::new(row, line_len))
+    })
 }
 
 /// Returns a position of the start of the current paragraph, where a paragraph
@@ -798,6 +1506,44 @@ pub fn end_of_paragraph(map: &DisplaySnapshot, display_point: DisplayPoint) -> D
     map.max_point()
 }
 
+/// Finds the pair of brackets `times` levels out from `relative_to`, as in
+/// `2di(`, given `find` to look for the innermost pair around a point. Pairs
+/// that come before the previous one without containing it are skipped over.
+fn nested_brackets(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+    times: Option<usize>,
+    find: impl Fn(DisplayPoint, bool) -> Option<Range<DisplayPoint>>,
+) -> Option<Range<DisplayPoint>> {
+    let times = times.unwrap_or(1);
+    if times <= 1 {
+        return find(relative_to, around);
+    }
+
+    let mut pair = find(relative_to, true)?;
+    for _ in 1..times {
+        let mut point = pair.start;
+        pair = loop {
+            let before = movement::left(map, point);
+            if before == point {
+                return None;
+            }
+            let outer = find(before, true)?;
+            if outer.start < pair.start && outer.end > pair.end {
+                break outer;
+            }
+            if outer.start >= pair.start {
+                return None;
+            }
+            point = outer.start;
+        };
+    }
+    // Looking from the opening bracket finds this pair again, now with the
+    // inner range if that's what was asked for.
+    find(pair.start, around)
+}
+
 fn surrounding_markers(
     map: &DisplaySnapshot,
     relative_to: DisplayPoint,
@@ -807,24 +1553,20 @@ fn surrounding_markers(
     close_marker: char,
 ) -> Option<Range<DisplayPoint>> {
     let point = relative_to.to_offset(map, Bias::Left);
+    let escaped = |offset| is_escaped(&map.buffer_snapshot, offset);
 
     let mut matched_closes = 0;
     let mut opening = None;
 
-    let mut before_ch = match movement::chars_before(map, point).next() {
-        Some((ch, _)) => ch,
-        _ => '\0',
-    };
     if let Some((ch, range)) = movement::chars_after(map, point).next() {
-        if ch == open_marker && before_ch != '\\' {
+        if ch == open_marker && !escaped(range.start) {
             if open_marker == close_marker {
                 let mut total = 0;
-                for ((ch, _), (before_ch, _)) in movement::chars_before(map, point).tuple_windows()
-                {
+                for (ch, range) in movement::chars_before(map, point) {
                     if ch == '\n' {
                         break;
                     }
-                    if ch == open_marker && before_ch != '\\' {
+                    if ch == open_marker && !escaped(range.start) {
                         total += 1;
                     }
                 }
@@ -838,16 +1580,12 @@ fn surrounding_markers(
     }
 
     if opening.is_none() {
-        let mut chars_before = movement::chars_before(map, point).peekable();
-        while let Some((ch, range)) = chars_before.next() {
+        for (ch, range) in movement::chars_before(map, point) {
             if ch == '\n' && !search_across_lines {
                 break;
             }
-
-            if let Some((before_ch, _)) = chars_before.peek() {
-                if *before_ch == '\\' {
-                    continue;
-                }
+            if (ch == open_marker || ch == close_marker) && escaped(range.start) {
+                continue;
             }
 
             if ch == open_marker {
@@ -863,16 +1601,17 @@ fn surrounding_markers(
     }
     if opening.is_none() {
         for (ch, range) in movement::chars_after(map, point) {
-            if before_ch != '\\' {
-                if ch == open_marker {
+            if ch == '\n' && !search_across_lines {
+                break;
+            }
+            if (ch == open_marker || ch == close_marker) && !escaped(range.start) {
+                if ch == open_marker {
                     opening = Some(range);
                     break;
                 } else if ch == close_marker {
                     break;
                 }
             }
-
-            before_ch = ch;
         }
     }
 
@@ -882,16 +1621,12 @@ fn surrounding_markers(
 
     let mut matched_opens = 0;
     let mut closing = None;
-    before_ch = match movement::chars_before(map, opening.end).next() {
-        Some((ch, _)) => ch,
-        _ => '\0',
-    };
     for (ch, range) in movement::chars_after(map, opening.end) {
         if ch == '\n' && !search_across_lines {
             break;
         }
 
-        if before_ch != '\\' {
+        if (ch == open_marker || ch == close_marker) && !escaped(range.start) {
             if ch == close_marker {
                 if matched_opens == 0 {
                     closing = Some(range);
@@ -902,14 +1637,192 @@ fn surrounding_markers(
                 matched_opens += 1;
             }
         }
-
-        before_ch = ch;
     }
 
-    let Some(mut closing) = closing else {
+    let Some(closing) = closing else {
         return None;
     };
 
+    marker_range(map, opening, closing, around, search_across_lines)
+}
+
+/// Whether the character at `offset` is escaped, so that it doesn't start or
+/// end a string. Inside an escape sequence of the syntax tree it always is,
+/// and in a raw string like Rust's `r"\"` it never is. Otherwise it's escaped
+/// by an odd number of backslashes before it, so that `\\"` ends a string.
+fn is_escaped(snapshot: &MultiBufferSnapshot, offset: usize) -> bool {
+    syntax_escaped(snapshot, offset).unwrap_or_else(|| {
+        snapshot
+            .reversed_chars_at(offset)
+            .take_while(|ch| *ch == '\\')
+            .count()
+            % 2
+            == 1
+    })
+}
+
+fn syntax_escaped(snapshot: &MultiBufferSnapshot, offset: usize) -> Option<bool> {
+    let excerpt = snapshot.excerpt_containing(offset..offset)?;
+    let buffer = excerpt.buffer();
+    let offset = excerpt.map_offset_to_buffer(offset);
+
+    let mut cursor = buffer.syntax_layer_at(offset)?.node().walk();
+    let mut node = cursor.node();
+    while cursor.goto_first_child_for_byte(offset).is_some() {
+        node = cursor.node();
+    }
+
+    // The outermost of the string nodes around the offset, as languages
+    // split their strings into parts like `string_start` and `string_end`.
+    let mut string = None;
+    let mut node = Some(node);
+    while let Some(current) = node {
+        if current.kind().contains("escape") {
+            return Some(true);
+        }
+        if current.kind().contains("string") {
+            string = Some(current);
+        } else if string.is_some() {
+            break;
+        }
+        node = current.parent();
+    }
+
+    let string = string?;
+    // Like Python's `r"..."`, where raw strings aren't a node of their own.
+    let has_raw_prefix = buffer
+        .chars_at(string.start_byte())
+        .take_while(|ch| ch.is_ascii_alphabetic())
+        .any(|ch| ch == 'r' || ch == 'R');
+    (string.kind().contains("raw") || has_raw_prefix).then_some(false)
+}
+
+/// The innermost of the strings in `'`, `"` or `` ` `` around `relative_to`,
+/// or else the nearest one after it on the line.
+fn any_quotes(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let point = relative_to.to_offset(map, Bias::Left);
+    ['\'', '"', '`']
+        .into_iter()
+        .filter_map(|quote| surrounding_markers(map, relative_to, around, false, quote, quote))
+        .min_by_key(|range| {
+            let start = range.start.to_offset(map, Bias::Left);
+            let end = range.end.to_offset(map, Bias::Left);
+            if start <= point && point < end {
+                (false, end - start)
+            } else {
+                (true, start.saturating_sub(point))
+            }
+        })
+}
+
+/// Angle brackets double as comparison and shift operators, so a `<` or `>`
+/// only counts as a bracket when it isn't part of `<=`, `>=`, `<<`, `->`, `=>`
+/// and isn't surrounded by whitespace like the `<` in `a < b`.
+fn angle_brackets(
+    map: &DisplaySnapshot,
+    relative_to: DisplayPoint,
+    around: bool,
+) -> Option<Range<DisplayPoint>> {
+    let point = relative_to.to_offset(map, Bias::Left);
+    let is_bracket = |ch: char, range: &Range<usize>| {
+        matches!(ch, '<' | '>') && is_angle_bracket(map, ch, range)
+    };
+
+    let mut opening = movement::chars_after(map, point)
+        .next()
+        .filter(|(ch, range)| *ch == '<' && is_bracket(*ch, range))
+        .map(|(_, range)| range);
+
+    if opening.is_none() {
+        let mut matched_closes = 0;
+        for (ch, range) in movement::chars_before(map, point) {
+            if !is_bracket(ch, &range) {
+                continue;
+            }
+            if ch == '<' {
+                if matched_closes == 0 {
+                    opening = Some(range);
+                    break;
+                }
+                matched_closes -= 1;
+            } else {
+                matched_closes += 1;
+            }
+        }
+    }
+    if opening.is_none() {
+        for (ch, range) in movement::chars_after(map, point) {
+            if ch == '\n' {
+                break;
+            }
+            if is_bracket(ch, &range) {
+                if ch == '<' {
+                    opening = Some(range);
+                }
+                break;
+            }
+        }
+    }
+    let opening = opening?;
+
+    let mut matched_opens = 0;
+    let mut closing = None;
+    for (ch, range) in movement::chars_after(map, opening.end) {
+        if !is_bracket(ch, &range) {
+            continue;
+        }
+        if ch == '>' {
+            if matched_opens == 0 {
+                closing = Some(range);
+                break;
+            }
+            matched_opens -= 1;
+        } else {
+            matched_opens += 1;
+        }
+    }
+
+    marker_range(map, opening, closing?, around, true)
+}
+
+fn is_angle_bracket(map: &DisplaySnapshot, ch: char, range: &Range<usize>) -> bool {
+    let is_operator = |ch: char| matches!(ch, '<' | '>' | '=' | '-');
+
+    let mut run = vec![ch];
+    let mut before = '\n';
+    for (ch, _) in movement::chars_before(map, range.start) {
+        if !is_operator(ch) {
+            before = ch;
+            break;
+        }
+        run.insert(0, ch);
+    }
+    let mut after = '\n';
+    for (ch, _) in movement::chars_after(map, range.end) {
+        if !is_operator(ch) {
+            after = ch;
+            break;
+        }
+        run.push(ch);
+    }
+
+    let is_comparison = run.iter().any(|ch| matches!(ch, '=' | '-'));
+    let is_shift = run.windows(2).any(|pair| pair == ['<', '<']);
+    let is_spaced = before.is_whitespace() && after.is_whitespace();
+    !(is_comparison || is_shift || is_spaced)
+}
+
+fn marker_range(
+    map: &DisplaySnapshot,
+    mut opening: Range<usize>,
+    mut closing: Range<usize>,
+    around: bool,
+    search_across_lines: bool,
+) -> Option<Range<DisplayPoint>> {
     if around && !search_across_lines {
         let mut found = false;
 
@@ -940,13 +1853,18 @@ fn surrounding_markers(
             }
         }
 
+        // If the closing marker is only preceded by indentation, the object
+        // ends at the start of its line so that it covers whole lines.
+        let mut line_start = closing.start;
         for (ch, range) in movement::chars_before(map, closing.start) {
-            if !ch.is_whitespace() {
+            if ch == '\n' {
+                closing.start = line_start;
                 break;
             }
-            if ch != '\n' {
-                closing.start = range.start
+            if !ch.is_whitespace() {
+                break;
             }
+            line_start = range.start;
         }
     }
 
@@ -964,11 +1882,13 @@ fn surrounding_markers(
 
 #[cfg(test)]
 mod test {
+    use gpui::UpdateGlobal;
     use indoc::indoc;
 
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim,
     };
 
     const WORD_LOCATIONS: &str = indoc! {"
@@ -1023,6 +1943,40 @@ mod test {
             .assert_matches();
     }
 
+    #[gpui::test]
+    async fn test_delete_around_word_next_to_punctuation(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // with no whitespace on either side, only the word is deleted
+        cx.simulate("d a w", "fˇoo.").await.assert_matches();
+        cx.simulate("d a w", ".fˇoo").await.assert_matches();
+        // trailing whitespace is preferred, and leading whitespace otherwise
+        cx.simulate("d a w", "fˇoo ").await.assert_matches();
+        cx.simulate("d a w", "bar fˇoo.").await.assert_matches();
+        cx.simulate("d a shift-w", "fˇoo.").await.assert_matches();
+    }
+
+    #[gpui::test]
+    async fn test_word_object_on_punctuation(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // a run of punctuation is a word of its own
+        cx.simulate("d i w", "foo=ˇ==bar").await.assert_matches();
+        cx.simulate("d i w", "a ==ˇ= b").await.assert_matches();
+        cx.simulate("d i w", "foo.ˇ(bar)").await.assert_matches();
+        cx.simulate("d i w", "x+=ˇy;").await.assert_matches();
+
+        // with the whitespace after it, or before it at the end of the line
+        cx.simulate("d a w", "foo ˇ=== bar").await.assert_matches();
+        cx.simulate("d a w", "foo ==ˇ=").await.assert_matches();
+        cx.simulate("d a w", "foo=ˇ==bar").await.assert_matches();
+
+        // unless punctuation is ignored
+        cx.simulate("d i shift-w", "foo=ˇ==bar baz")
+            .await
+            .assert_matches();
+    }
+
     #[gpui::test]
     async fn test_visual_word_object(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1173,6 +2127,85 @@ mod test {
         }
     }
 
+    #[gpui::test]
+    async fn test_paragraph_object_on_blank_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // On a run of blank lines `ip` is the run, and `ap` the run and the
+        // paragraph after it.
+        cx.set_shared_state("a\nb\n\nˇ\n\nc\nd\n\ne").await;
+        cx.simulate_shared_keystrokes("d i p").await;
+        cx.shared_state().await.assert_eq("a\nb\nˇc\nd\n\ne");
+
+        cx.set_shared_state("a\nb\n\nˇ\n\nc\nd\n\ne").await;
+        cx.simulate_shared_keystrokes("d a p").await;
+        cx.shared_state().await.assert_eq("a\nb\nˇ\ne");
+
+        cx.set_shared_state("a\nˇ\nb").await;
+        cx.simulate_shared_keystrokes("d i p").await;
+        cx.shared_state().await.assert_eq("a\nˇb");
+    }
+
+    #[gpui::test]
+    async fn test_paragraph_object_with_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇa\nb\n\nc\n\nd").await;
+        cx.simulate_shared_keystrokes("2 d i p").await;
+        cx.shared_state().await.assert_eq("ˇc\n\nd");
+
+        cx.set_shared_state("ˇa\n\n\nb").await;
+        cx.simulate_shared_keystrokes("d a p").await;
+        cx.shared_state().await.assert_eq("ˇb");
+
+        cx.set_shared_state("ˇa\n\nb\n\nc").await;
+        cx.simulate_shared_keystrokes("2 d a p").await;
+        cx.shared_state().await.assert_eq("ˇc");
+
+        cx.set_shared_state("a\nˇ\n\nb\nc\n\nd").await;
+        cx.simulate_shared_keystrokes("d a p").await;
+        cx.shared_state().await.assert_eq("a\nˇ\nd");
+
+        cx.set_shared_state("a\nˇb\n\n\nc").await;
+        cx.simulate_shared_keystrokes("v i p").await;
+        cx.shared_state().await.assert_eq("«a\nbˇ»\n\n\nc");
+
+        cx.set_shared_state("a\nb\nˇ\n\nc").await;
+        cx.simulate_shared_keystrokes("v i p").await;
+        cx.shared_state().await.assert_eq("a\nb\n«\n\nˇ»c");
+
+        cx.set_shared_state("a\nˇb\n\n\nc").await;
+        cx.simulate_shared_keystrokes("v 2 i p").await;
+        cx.shared_state().await.assert_eq("«a\nb\n\n\nˇ»c");
+    }
+
+    #[gpui::test]
+    async fn test_paragraph_object_count_across_separators(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // each blank line between paragraphs counts as one for `ip`
+        cx.set_shared_state("ˇa\nb\n\nc\n\nd\ne\n\nf").await;
+        cx.simulate_shared_keystrokes("v 2 i p").await;
+        cx.shared_state()
+            .await
+            .assert_eq("«a\nb\n\nˇ»c\n\nd\ne\n\nf");
+
+        cx.set_shared_state("a\nb\nˇ\nc\n\nd\ne\n\nf").await;
+        cx.simulate_shared_keystrokes("v 2 i p").await;
+        cx.shared_state()
+            .await
+            .assert_eq("a\nb\n«\ncˇ»\n\nd\ne\n\nf");
+
+        // while `ap` takes its separator with it
+        cx.set_shared_state("ˇa\nb\n\nc\n\nd\ne\n\nf").await;
+        cx.simulate_shared_keystrokes("3 d a p").await;
+        cx.shared_state().await.assert_eq("ˇf");
+
+        cx.set_shared_state("a\nb\n\nˇc\n\nd\ne\n\nf").await;
+        cx.simulate_shared_keystrokes("2 d i p").await;
+        cx.shared_state().await.assert_eq("a\nb\n\nˇd\ne\n\nf");
+    }
+
     // Test string with "`" for opening surrounders and "'" for closing surrounders
     const SURROUNDING_MARKER_STRING: &str = indoc! {"
         ˇTh'ˇe ˇ`ˇ'ˇquˇi`ˇck broˇ'wn`
@@ -1184,6 +2217,25 @@ mod test {
         ('(', ')'), // Parentheses
     ];
 
+    #[gpui::test]
+    async fn test_change_quote_object_single_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // no quotes, or only one
+        cx.simulate("c i \"", "aˇb c").await.assert_matches();
+        cx.simulate("c i \"", "a \"ˇb").await.assert_matches();
+        cx.simulate("c i \"", "ˇa \"b").await.assert_matches();
+        // two quotes, inside and after the pair
+        cx.simulate("c i \"", "a \"ˇb\" c").await.assert_matches();
+        cx.simulate("c i \"", "a \"b\" ˇc").await.assert_matches();
+        // three quotes: between the second and third counts as quoted, but
+        // on the third there is no closing quote
+        cx.simulate("c i \"", "\"a\" ˇb \"c").await.assert_matches();
+        cx.simulate("c i \"", "\"a\" b ˇ\"c").await.assert_matches();
+        // quotes on a later line are not found
+        cx.simulate("c i \"", "ˇa b\n\"c\"").await.assert_matches();
+    }
+
     #[gpui::test]
     async fn test_change_surrounding_character_objects(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1336,6 +2388,256 @@ mod test {
             }"});
     }
 
+    #[gpui::test]
+    async fn test_inner_block_objects(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // Multi-line blocks cover the whole lines between the brackets
+        cx.set_shared_state("fn a() {\n    ˇb  \n    c\n}").await;
+        cx.simulate_shared_keystrokes("d i {").await;
+        cx.shared_state().await.assert_eq("fn a() {\nˇ}");
+
+        // Single-line blocks cover everything between the brackets
+        cx.set_shared_state("a { ˇb } c").await;
+        cx.simulate_shared_keystrokes("c i {").await;
+        cx.shared_state().await.assert_eq("a {ˇ} c");
+    }
+
+    #[gpui::test]
+    async fn test_indent_inner_block_objects(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("fn a() {\n    ˇb\n    c\n}", Mode::Normal);
+        cx.simulate_keystrokes("> i {");
+        cx.assert_state("fn a() {\n        ˇb\n        c\n}", Mode::Normal);
+        cx.simulate_keystrokes("< i {");
+        cx.assert_state("fn a() {\n    ˇb\n    c\n}", Mode::Normal);
+
+        cx.set_state("fn a() {\n    ˇb\n    }", Mode::Normal);
+        cx.simulate_keystrokes("> i {");
+        cx.assert_state("fn a() {\n        ˇb\n    }", Mode::Normal);
+
+        // An empty block has an empty inner object
+        cx.set_state("a {ˇ} c", Mode::Normal);
+        cx.simulate_keystrokes("c i {");
+        cx.assert_state("a {ˇ} c", Mode::Insert);
+    }
+
+    #[gpui::test]
+    async fn test_inner_block_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let block = indoc! {"
+            fn a() {
+                if b {
+                    ˇc
+                    d
+                }
+            }"};
+
+        // the lines are deleted (and put) linewise
+        cx.set_state(block, Mode::Normal);
+        cx.simulate_keystrokes("d i {");
+        cx.assert_state(
+            indoc! {"
+            fn a() {
+                if b {
+                ˇ}
+            }"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("p");
+        cx.assert_state(
+            indoc! {"
+            fn a() {
+                if b {
+                }
+                    ˇc
+                    d
+            }"},
+            Mode::Normal,
+        );
+
+        // but changing them leaves an indented line to type on
+        cx.set_state(block, Mode::Normal);
+        cx.simulate_keystrokes("c i {");
+        cx.assert_state(
+            indoc! {"
+            fn a() {
+                if b {
+                    ˇ
+                }
+            }"},
+            Mode::Insert,
+        );
+        cx.simulate_keystrokes("e escape");
+        cx.assert_state(
+            indoc! {"
+            fn a() {
+                if b {
+                    ˇe
+                }
+            }"},
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_inner_array_lines(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let array = indoc! {"
+            let a = [
+                ˇ1,
+                2,
+            ];"};
+
+        // a multi-line array is operated on linewise, like a block
+        cx.set_state(array, Mode::Normal);
+        cx.simulate_keystrokes("> i [");
+        cx.assert_state(
+            indoc! {"
+            let a = [
+                    ˇ1,
+                    2,
+            ];"},
+            Mode::Normal,
+        );
+        cx.set_state(array, Mode::Normal);
+        cx.simulate_keystrokes("d i [");
+        cx.assert_state("let a = [\nˇ];", Mode::Normal);
+
+        // while one on a single line is charwise
+        cx.set_state("let a = [1, ˇ2];", Mode::Normal);
+        cx.simulate_keystrokes("d i [");
+        cx.assert_state("let a = [ˇ];", Mode::Normal);
+        cx.set_state("let a = [ˇ1];", Mode::Normal);
+        cx.simulate_keystrokes("d i [");
+        cx.assert_state("let a = [ˇ];", Mode::Normal);
+        cx.simulate_keystrokes("shift-p");
+        cx.assert_state("let a = [ˇ1];", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_indent_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // blank lines inside the block are part of it
+        cx.set_state(
+            indoc! {"
+            def f():
+                if x:
+                    ˇreturn 1
+
+                    y = 2
+                return 0
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d i i");
+        cx.assert_state(
+            indoc! {"
+            def f():
+                if x:
+            ˇ    return 0
+            "},
+            Mode::Normal,
+        );
+
+        cx.set_state(
+            indoc! {"
+            def f():
+                if x:
+                    ˇreturn 1
+                return 0
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d a i");
+        cx.assert_state(
+            indoc! {"
+            def f():
+            ˇ    return 0
+            "},
+            Mode::Normal,
+        );
+
+        cx.set_state(
+            indoc! {"
+            fn f() {
+                ˇlet a = 1;
+                let b = 2;
+            }
+            z();
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d a shift-i");
+        cx.assert_state("ˇz();\n", Mode::Normal);
+
+        // `iI` is the same as `ii`
+        cx.set_state(
+            indoc! {"
+            fn f() {
+                ˇlet a = 1;
+            }
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d i shift-i");
+        cx.assert_state("fn f() {\nˇ}\n", Mode::Normal);
+
+        // at the top of the file there's no line above to include
+        cx.set_state(
+            indoc! {"
+                ˇlet a = 1;
+                let b = 2;
+            }
+            "},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d a shift-i");
+        cx.assert_state("ˇ", Mode::Normal);
+
+        // and at the bottom there's no line below
+        cx.set_state(
+            indoc! {"
+            fn f() {
+                ˇlet a = 1;"},
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d a shift-i");
+        cx.assert_state("ˇ", Mode::Normal);
+
+        cx.set_state(
+            "def f():\n\tif x:\n\t\tˇreturn 1\n\treturn 0\n",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d i i");
+        cx.assert_state("def f():\n\tif x:\nˇ\treturn 0\n", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_indent_object_mixed_tabs_and_spaces(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // a tab and four spaces are the same level of indentation
+        cx.set_state(
+            "def f():\n\tif x:\n        return 1\n    ˇreturn 0\nz = 1\n",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d i i");
+        cx.assert_state("def f():\nˇz = 1\n", Mode::Normal);
+
+        cx.set_state(
+            "def f():\n\tif x:\n        ˇreturn 1\n\t\ty = 2\n    return 0\n",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("v i i");
+        cx.assert_state(
+            "def f():\n\tif x:\n«        return 1\n\tˇ»\ty = 2\n    return 0\n",
+            Mode::VisualLine,
+        );
+    }
+
     #[gpui::test]
     async fn test_singleline_surrounding_character_objects_with_escape(
         cx: &mut gpui::TestAppContext,
@@ -1360,6 +2662,57 @@ mod test {
         });
     }
 
+    #[gpui::test]
+    async fn test_quote_objects_with_escapes(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // an escaped quote doesn't end the string
+        cx.set_state(r#"let a = "x \"ˇy\" z";"#, Mode::Normal);
+        cx.simulate_keystrokes("c i \"");
+        cx.assert_state(r#"let a = "ˇ";"#, Mode::Insert);
+
+        // but a quote after an escaped backslash does
+        cx.simulate_keystrokes("escape");
+        cx.set_state(r#"let a = "ˇx\\"; let b = "y";"#, Mode::Normal);
+        cx.simulate_keystrokes("c i \"");
+        cx.assert_state(r#"let a = "ˇ"; let b = "y";"#, Mode::Insert);
+
+        // and nothing is escaped in a raw string
+        cx.simulate_keystrokes("escape");
+        cx.set_state(r#"let a = r"ˇx\"; let b = 1;"#, Mode::Normal);
+        cx.simulate_keystrokes("c i \"");
+        cx.assert_state(r#"let a = r"ˇ"; let b = 1;"#, Mode::Insert);
+
+        // without the syntax tree, backslashes are counted
+        cx.simulate_keystrokes("escape");
+        cx.update_buffer(|buffer, cx| buffer.set_language(None, cx));
+        cx.set_state(r#"ˇa "b \\" c \" d""#, Mode::Normal);
+        cx.simulate_keystrokes("f b d a \"");
+        cx.assert_state(r#"a ˇc \" d""#, Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_any_quotes_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("foo(\"a\", 'bˇc', `d`)", Mode::Normal);
+        cx.simulate_keystrokes("c i q");
+        cx.assert_state("foo(\"a\", 'ˇ', `d`)", Mode::Insert);
+
+        // the innermost string around the cursor
+        cx.simulate_keystrokes("escape");
+        cx.set_state("\"say `hˇi` now\"", Mode::Normal);
+        cx.simulate_keystrokes("d i q");
+        cx.assert_state("\"say `ˇ` now\"", Mode::Normal);
+        cx.simulate_keystrokes("d a q");
+        cx.assert_state("\"say ˇnow\"", Mode::Normal);
+
+        // or the next one on the line
+        cx.set_state("ˇx = \"abc\" + 'd'", Mode::Normal);
+        cx.simulate_keystrokes("d i q");
+        cx.assert_state("x = \"ˇ\" + 'd'", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_vertical_bars(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -1401,6 +2754,24 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_slashes(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("let re = /foo\\/bˇar/;", Mode::Normal);
+        cx.simulate_keystrokes("c i /");
+        cx.assert_state("let re = /ˇ/;", Mode::Insert);
+
+        cx.set_state("let re = /fˇoo\\/bar/;", Mode::Normal);
+        cx.simulate_keystrokes("d a /");
+        cx.assert_state("let re =ˇ;", Mode::Normal);
+
+        // slashes on other lines are not considered
+        cx.set_state("/a\nbˇ/c\n", Mode::Normal);
+        cx.simulate_keystrokes("d i /");
+        cx.assert_state("/a\nbˇ/c\n", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_argument_object(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
@@ -1561,4 +2932,392 @@ mod test {
             Mode::Visual,
         );
     }
+
+    #[gpui::test]
+    async fn test_tags_in_comments_and_attributes(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_html(cx).await;
+
+        // Commented out tags are not matched
+        cx.set_state("<div><!-- <div> -->hˇi</div>", Mode::Normal);
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state("<div>ˇ</div>", Mode::Insert);
+
+        cx.set_state("<div><b>hˇi<!-- </b> --></b></div>", Mode::Normal);
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state("<div><b>ˇ</b></div>", Mode::Insert);
+
+        // Neither are tags in CDATA sections
+        cx.set_state("<div><![CDATA[<b>]]>hˇi</b></div>", Mode::Normal);
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state("<div>ˇ</div>", Mode::Insert);
+
+        // A `<` in an attribute value doesn't start a tag
+        cx.set_state("<div><a title=\"a<b\">hˇi</a></div>", Mode::Normal);
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state("<div><a title=\"a<b\">ˇ</a></div>", Mode::Insert);
+
+        // nor does a `<!--` in an attribute value or a script open a comment
+        cx.set_state("<div title=\"<!--\"><b>hˇi</b></div>", Mode::Normal);
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state("<div title=\"<!--\"><b>ˇ</b></div>", Mode::Insert);
+
+        cx.set_state(
+            "<div><script>let s = \"<!--\";</script><b>hˇi</b></div>",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("c i t");
+        cx.assert_state(
+            "<div><script>let s = \"<!--\";</script><b>ˇ</b></div>",
+            Mode::Insert,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_object_cache(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_html(cx).await;
+
+        cx.set_state("<b>hˇi!</b>\nabc", Mode::Normal);
+        cx.simulate_keystrokes("v i t");
+        cx.assert_state("<b>«hi!ˇ»</b>\nabc", Mode::Visual);
+        cx.update(|cx| assert_eq!(Vim::read(cx).state().object_cache.ranges.len(), 1));
+
+        // Looking the tag up again from the same place uses the cached range
+        // rather than the syntax tree, so tamper with it to show that.
+        cx.update(|cx| {
+            Vim::update_global(cx, |vim, _| {
+                vim.update_state(|state| {
+                    for range in state.object_cache.ranges.values_mut() {
+                        *range = Some(0..3);
+                    }
+                })
+            })
+        });
+        cx.simulate_keystrokes("escape h v i t");
+        cx.assert_state("«<b>ˇ»hi!</b>\nabc", Mode::Visual);
+
+        // Any edit to the buffer empties the cache.
+        cx.simulate_keystrokes("escape j x k f i v i t");
+        cx.assert_state("<b>«hi!ˇ»</b>\nab", Mode::Visual);
+    }
+
+    #[gpui::test]
+    async fn test_object_cache_function_call(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("fn f() { foo(bˇar); }\nabc", Mode::Normal);
+        cx.simulate_keystrokes("v i f");
+        cx.assert_state("fn f() { foo(«barˇ»); }\nabc", Mode::Visual);
+        cx.update(|cx| assert_eq!(Vim::read(cx).state().object_cache.ranges.len(), 1));
+
+        cx.update(|cx| {
+            Vim::update_global(cx, |vim, _| {
+                vim.update_state(|state| {
+                    for range in state.object_cache.ranges.values_mut() {
+                        *range = Some(0..2);
+                    }
+                })
+            })
+        });
+        cx.simulate_keystrokes("escape h v i f");
+        cx.assert_state("«fnˇ» f() { foo(bar); }\nabc", Mode::Visual);
+
+        cx.simulate_keystrokes("escape j $ x k 0 f a v i f");
+        cx.assert_state("fn f() { foo(«barˇ»); }\nab", Mode::Visual);
+    }
+
+    #[gpui::test]
+    async fn test_interpolation_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new_typescript(cx).await;
+
+        cx.set_state("const s = `foo ${bˇar} baz`;", Mode::Normal);
+        cx.simulate_keystrokes("c i $");
+        cx.assert_state("const s = `foo ${ˇ} baz`;", Mode::Insert);
+
+        // braces nested inside the interpolation are part of it
+        cx.set_state("const s = `foo ${ {a: ˇb}.a } baz`;", Mode::Normal);
+        cx.simulate_keystrokes("d a $");
+        cx.assert_state("const s = `foo ˇ baz`;", Mode::Normal);
+
+        cx.set_state("const s = `foo ${ˇ{a: b}.a} baz`;", Mode::Normal);
+        cx.simulate_keystrokes("d i $");
+        cx.assert_state("const s = `foo ${ˇ} baz`;", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_interpolation_object_without_syntax(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // rust has no `#{}` interpolation, so the braces are matched in the text
+        cx.set_state(r#"puts "hello #{naˇme.upcase} !""#, Mode::Normal);
+        cx.simulate_keystrokes("c i $");
+        cx.assert_state(r#"puts "hello #{ˇ} !""#, Mode::Insert);
+
+        cx.set_state(r#"puts "hello #{ {a: ˇ1}[:a] } !""#, Mode::Normal);
+        cx.simulate_keystrokes("v a $");
+        cx.assert_state(r#"puts "hello «#{ {a: 1}[:a] }ˇ» !""#, Mode::Visual);
+
+        cx.set_state(r#"puts "hello ˇ!""#, Mode::Normal);
+        cx.simulate_keystrokes("c i $");
+        cx.assert_state(r#"puts "hello ˇ!""#, Mode::Normal);
+
+        // the braces are only matched within the string
+        cx.set_state(r##"let a = "#{a"; let b = "hˇi} b";"##, Mode::Normal);
+        cx.simulate_keystrokes("c i $");
+        cx.assert_state(r##"let a = "#{a"; let b = "hˇi} b";"##, Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_bracket_object_with_count(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // `B` is `{`
+        cx.set_state("a { b { cˇ } d } e", Mode::Normal);
+        cx.simulate_keystrokes("2 d i shift-b");
+        cx.assert_state("a {ˇ} e", Mode::Normal);
+
+        // earlier pairs that don't contain the cursor are skipped
+        cx.set_state("{ {x} {yˇ} }", Mode::Normal);
+        cx.simulate_keystrokes("2 d i shift-b");
+        cx.assert_state("{ˇ}", Mode::Normal);
+
+        // `b` is `(`
+        cx.set_state("foo(a, (bˇ)) x", Mode::Normal);
+        cx.simulate_keystrokes("d a b");
+        cx.assert_state("foo(a, ˇ) x", Mode::Normal);
+
+        cx.set_state("foo(a, (bˇ)) x", Mode::Normal);
+        cx.simulate_keystrokes("2 d a b");
+        cx.assert_state("fooˇ x", Mode::Normal);
+
+        cx.set_state("foo(a, (bˇ)) x", Mode::Normal);
+        cx.simulate_keystrokes("d 2 a (");
+        cx.assert_state("fooˇ x", Mode::Normal);
+
+        cx.set_state("let a: Vec<HashMap<ˇK, V>> = x;", Mode::Normal);
+        cx.simulate_keystrokes("2 c i <");
+        cx.assert_state("let a: Vec<ˇ> = x;", Mode::Insert);
+
+        // there aren't enough pairs, so nothing happens
+        cx.set_state("(aˇ)", Mode::Normal);
+        cx.simulate_keystrokes("2 d i b");
+        cx.assert_state("(aˇ)", Mode::Normal);
+
+        // outside of an object, `b` is still a motion
+        cx.set_state("foo barˇ baz", Mode::Normal);
+        cx.simulate_keystrokes("d b");
+        cx.assert_state("foo ˇ baz", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_angle_bracket_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("let a: Vec<HashMap<ˇK, V>> = x;", Mode::Normal);
+        cx.simulate_keystrokes("c i <");
+        cx.assert_state("let a: Vec<HashMap<ˇ>> = x;", Mode::Insert);
+
+        cx.set_state("let a: Vec<HashMaˇp<K, V>> = x;", Mode::Normal);
+        cx.simulate_keystrokes("c i <");
+        cx.assert_state("let a: Vec<ˇ> = x;", Mode::Insert);
+
+        cx.set_state("fn f() -> Option<ˇT> { x <= y }", Mode::Normal);
+        cx.simulate_keystrokes("d a <");
+        cx.assert_state("fn f() -> Optionˇ { x <= y }", Mode::Normal);
+
+        cx.set_state("if a ˇ< b { c >> 2 }", Mode::Normal);
+        cx.simulate_keystrokes("c i <");
+        cx.assert_state("if a ˇ< b { c >> 2 }", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_macro_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("let a = vec![1, ˇ2, 3];", Mode::Normal);
+        cx.simulate_keystrokes("c i !");
+        cx.assert_state("let a = vec![ˇ];", Mode::Insert);
+
+        cx.set_state("#[derive(Clone, ˇDebug)]\nstruct A;", Mode::Normal);
+        cx.simulate_keystrokes("c i !");
+        cx.assert_state("#[derive(ˇ)]\nstruct A;", Mode::Insert);
+
+        // nested macros are only tokens, but the innermost is still found
+        cx.set_state("let a = vec![vec![ˇ1], vec![2]];", Mode::Normal);
+        cx.simulate_keystrokes("c i !");
+        cx.assert_state("let a = vec![vec![ˇ], vec![2]];", Mode::Insert);
+
+        cx.set_state("fn a() { ˇprintln!(\"{}\", 1); }", Mode::Normal);
+        cx.simulate_keystrokes("d a !");
+        cx.assert_state("fn a() { ˇ; }", Mode::Normal);
+
+        cx.set_state("thread_local! { static ˇA: u8 = 1; }", Mode::Normal);
+        cx.simulate_keystrokes("d i !");
+        cx.assert_state("thread_local! {ˇ}", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_section_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let markdown = std::sync::Arc::new(language::Language::new(
+            language::LanguageConfig {
+                name: "Markdown".into(),
+                ..Default::default()
+            },
+            None,
+        ));
+        cx.update_buffer(|buffer, cx| buffer.set_language(Some(markdown), cx));
+
+        const DOCUMENT: &str = indoc! {"
+            # A
+            a
+            ## B
+            b
+            # C
+            c"};
+
+        cx.set_state(&DOCUMENT.replace("b\n", "ˇb\n"), Mode::Normal);
+        cx.simulate_keystrokes("d a h");
+        cx.assert_state("# A\na\nˇ# C\nc", Mode::Normal);
+
+        // a section takes its subsections with it
+        cx.set_state(&DOCUMENT.replace("a\n", "ˇa\n"), Mode::Normal);
+        cx.simulate_keystrokes("d a h");
+        cx.assert_state("ˇ# C\nc", Mode::Normal);
+
+        cx.set_state(&DOCUMENT.replace("# A", "ˇ# A"), Mode::Normal);
+        cx.simulate_keystrokes("c i h");
+        cx.assert_state("# A\nˇ\n# C\nc", Mode::Insert);
+
+        // the last section runs to the end of the buffer
+        cx.set_state(&DOCUMENT.replace("\nc", "\nˇc"), Mode::Normal);
+        cx.simulate_keystrokes("c i h");
+        cx.assert_state("# A\na\n## B\nb\n# C\nˇ", Mode::Insert);
+
+        cx.set_state(&DOCUMENT.replace("\nc", "\nˇc"), Mode::Normal);
+        cx.simulate_keystrokes("d a h");
+        cx.assert_state("# A\na\n## B\nˇb", Mode::Normal);
+
+        // headings in code blocks don't count
+        cx.set_state("# A\n```\n# no\n```\nˇx\n# B", Mode::Normal);
+        cx.simulate_keystrokes("d i h");
+        cx.assert_state("# A\nˇ# B", Mode::Normal);
+
+        cx.set_state("ˇtext\n# A", Mode::Normal);
+        cx.simulate_keystrokes("d a h");
+        cx.assert_state("ˇtext\n# A", Mode::Normal);
+
+        // other languages have no sections, whatever their comments look like
+        cx.update_buffer(|buffer, cx| buffer.set_language(None, cx));
+        cx.set_state(
+            "# A
+ˇa",
+            Mode::Normal,
+        );
+        cx.simulate_keystrokes("d a h");
+        cx.assert_state(
+            "# A
+ˇa",
+            Mode::Normal,
+        );
+    }
+
+    #[gpui::test]
+    async fn test_matching_pair_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let language = std::sync::Arc::new(
+            language::Language::new(
+                language::LanguageConfig {
+                    name: "Ruby".into(),
+                    ..Default::default()
+                },
+                Some(tree_sitter_ruby::language()),
+            )
+            .with_brackets_query(
+                r#"
+                    ("(" @open ")" @close)
+                    (if "if" @open "end" @close)
+                    (_ . "def" @open "end" @close)
+                "#,
+            )
+            .unwrap(),
+        );
+        cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+
+        const METHOD: &str = indoc! {"
+            def foo(x)
+              if x
+                bar
+              end
+              baz
+            end"};
+
+        cx.set_state(&METHOD.replace("baz", "bˇaz"), Mode::Normal);
+        cx.simulate_keystrokes("d i m");
+        cx.assert_state("def foo(x)\nˇend", Mode::Normal);
+
+        cx.set_state(&METHOD.replace("baz", "bˇaz"), Mode::Normal);
+        cx.simulate_keystrokes("d a m");
+        cx.assert_state("ˇ", Mode::Normal);
+
+        // the innermost block is used
+        cx.set_state(&METHOD.replace("bar", "bˇar"), Mode::Normal);
+        cx.simulate_keystrokes("d i m");
+        cx.assert_state(
+            indoc! {"
+                def foo(x)
+                  if x
+                  ˇend
+                  baz
+                end"},
+            Mode::Normal,
+        );
+
+        cx.set_state(&METHOD.replace("bar", "bˇar"), Mode::Normal);
+        cx.simulate_keystrokes("v a m");
+        cx.assert_state(
+            indoc! {"
+                def foo(x)
+                  «if x
+                    bar
+                  endˇ»
+                  baz
+                end"},
+            Mode::Visual,
+        );
+
+        // brackets are used outside of any keyword pair
+        cx.set_state("foo(aˇb)", Mode::Normal);
+        cx.simulate_keystrokes("d i m");
+        cx.assert_state("foo(ˇ)", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_function_call_object(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("fn f() { foo(bar(ˇx), y); }", Mode::Normal);
+        cx.simulate_keystrokes("d a f");
+        cx.assert_state("fn f() { foo(ˇ, y); }", Mode::Normal);
+
+        cx.set_state("fn f() { foo(bar(x), ˇy); }", Mode::Normal);
+        cx.simulate_keystrokes("c i f");
+        cx.assert_state("fn f() { foo(ˇ); }", Mode::Insert);
+
+        // chained calls use the innermost call at the cursor
+        cx.set_state("fn f() { a.b(1).cˇ(2); }", Mode::Normal);
+        cx.simulate_keystrokes("c i f");
+        cx.assert_state("fn f() { a.b(1).c(ˇ); }", Mode::Insert);
+
+        cx.set_state("fn f() { a.ˇb(1).c(2); }", Mode::Normal);
+        cx.simulate_keystrokes("d a f");
+        cx.assert_state("fn f() { ˇ.c(2); }", Mode::Normal);
+
+        cx.set_state("fn f() { let x = ˇfoo(); }", Mode::Normal);
+        cx.simulate_keystrokes("d a f");
+        cx.assert_state("fn f() { let x = ˇ; }", Mode::Normal);
+
+        cx.set_state("fn f() { let ˇx = 1; }", Mode::Normal);
+        cx.simulate_keystrokes("d a f");
+        cx.assert_state("fn f() { let ˇx = 1; }", Mode::Normal);
+    }
 }
@@ -0,0 +1,222 @@
+//! `ctrl-r` in the `:` and `/` prompts, which inserts the contents of a
+//! register (or the word under the cursor) into the prompt being edited, and
+//! `up` and `down` in the `:` prompt, which recall earlier commands. Typing
+//! `:s` also previews the substitution in the editor behind the prompt.
+
+use editor::{actions::MoveToEnd, Editor, EditorEvent, EditorMode};
+use gpui::{impl_actions, EntityId, View, ViewContext, WindowContext};
+use serde::Deserialize;
+
+use crate::{
+    normal::search::{clear_substitution_preview, preview_substitution, word_under_cursor},
+    Vim,
+};
+
+/// Waits for a register name and inserts that register into the prompt.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptRegister {
+    /// Whether the prompt is a search, in which case line breaks are
+    /// inserted as `\n` rather than literally.
+    #[serde(default)]
+    search: bool,
+}
+
+/// Inserts the word (or WORD) under the cursor into the prompt.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptWord {
+    #[serde(default)]
+    ignore_punctuation: bool,
+}
+
+/// Replaces the prompt with an older (or newer) entry from the command
+/// history. Only entries that start with the text typed before browsing began
+/// are visited.
+#[derive(Clone, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PromptHistory {
+    #[serde(default)]
+    previous: bool,
+}
+
+impl_actions!(vim, [PromptRegister, PromptWord, PromptHistory]);
+
+/// Where `up` and `down` have got to in the history of a prompt.
+pub(crate) struct HistoryPosition {
+    prompt: EntityId,
+    prefix: String,
+    index: usize,
+    /// The entry that was put into the prompt, so that browsing starts over
+    /// once it has been edited.
+    text: String,
+}
+
+pub(crate) fn register(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+    if editor.mode() == EditorMode::Full {
+        return;
+    }
+
+    let prompt = cx.view().downgrade();
+    editor
+        .register_action(move |action: &PromptRegister, cx| {
+            if let Some(prompt) = prompt.upgrade() {
+                await_register(prompt, action.search, cx)
+            }
+        })
+        .detach();
+
+    let prompt = cx.view().downgrade();
+    editor
+        .register_action(move |action: &PromptWord, cx| {
+            let Some(prompt) = prompt.upgrade() else {
+                return;
+            };
+            let word = Vim::update(cx, |vim, cx| {
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    let snapshot = editor.buffer().read(cx).snapshot(cx);
+                    let head = editor.selections.newest::<usize>(cx).head();
+                    word_under_cursor(&snapshot, head, action.ignore_punctuation)
+                })
+                .flatten()
+            });
+            if let Some(word) = word {
+                prompt.update(cx, |prompt, cx| prompt.insert(&word, cx));
+            }
+        })
+        .detach();
+
+    let prompt = cx.view().downgrade();
+    editor
+        .register_action(move |action: &PromptHistory, cx| {
+            if let Some(prompt) = prompt.upgrade() {
+                browse_history(prompt, action, cx)
+            }
+        })
+        .detach();
+
+    let prompt = cx.view().clone();
+    cx.subscribe(&prompt, |prompt, _, event: &EditorEvent, cx| match event {
+        EditorEvent::BufferEdited => {
+            let query = prompt.text(cx);
+            preview_substitution(&query, cx);
+        }
+        EditorEvent::Blurred => {
+            Vim::update(cx, |vim, cx| clear_substitution_preview(vim, cx));
+        }
+        _ => {}
+    })
+    .detach();
+    cx.on_release(|_, window, cx| {
+        window
+            .update(cx, |_, cx| {
+                Vim::update(cx, |vim, cx| clear_substitution_preview(vim, cx))
+            })
+            .ok();
+    })
+    .detach();
+}
+
+/// Moves through the history, leaving the key to the prompt's own bindings
+/// (such as selecting the next command in the palette) when there is no
+/// history to browse.
+fn browse_history(prompt: View<Editor>, action: &PromptHistory, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        let (text, typed) = {
+            let prompt = prompt.read(cx);
+            let text = prompt.text(cx);
+            let selection = prompt.selections.newest::<usize>(cx);
+            // Text that was filled in and selected for us hasn't been typed.
+            let typed = if selection.start == 0 && selection.end == text.len() {
+                String::new()
+            } else {
+                text.clone()
+            };
+            (text, typed)
+        };
+        let position = vim
+            .prompt_history
+            .take()
+            .filter(|position| position.prompt == prompt.entity_id() && position.text == text);
+        let history = &vim.workspace_state.command_history;
+        let (prefix, start) = match &position {
+            Some(position) => (position.prefix.clone(), position.index),
+            None => (typed, history.len()),
+        };
+
+        let mut entries = history
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| entry.starts_with(&prefix));
+        let entry = if action.previous {
+            entries.filter(|(index, _)| *index < start).last()
+        } else {
+            entries.find(|(index, _)| *index > start)
+        };
+        let text = match (entry, position) {
+            (Some((index, entry)), _) => {
+                vim.prompt_history = Some(HistoryPosition {
+                    prompt: prompt.entity_id(),
+                    prefix,
+                    index,
+                    text: entry.clone(),
+                });
+                entry.clone()
+            }
+            // As in vim, going past the newest entry gets back what was typed.
+            (None, Some(_)) if !action.previous => prefix,
+            (None, Some(position)) => {
+                vim.prompt_history = Some(position);
+                return;
+            }
+            (None, None) => {
+                cx.propagate();
+                return;
+            }
+        };
+        prompt.update(cx, |prompt, cx| {
+            prompt.set_text(text, cx);
+            prompt.move_to_end(&MoveToEnd, cx);
+        });
+    })
+}
+
+/// Stops the prompt from accepting input until the next character has been
+/// typed, and then inserts the register that it names.
+fn await_register(prompt: View<Editor>, search: bool, cx: &mut WindowContext) {
+    Vim::update(cx, |vim, cx| {
+        if !vim.enabled {
+            return;
+        }
+        prompt.update(cx, |prompt, _| prompt.set_input_enabled(false));
+        vim.prompt_subscription = Some(cx.subscribe(&prompt, move |prompt, event, cx| {
+            let register = match event {
+                EditorEvent::InputIgnored { text } => text.chars().next(),
+                EditorEvent::Blurred => None,
+                _ => return,
+            };
+            Vim::update(cx, |vim, cx| {
+                vim.prompt_subscription.take();
+                prompt.update(cx, |prompt, _| prompt.set_input_enabled(true));
+
+                let Some(register) = register else {
+                    return;
+                };
+                let contents = vim
+                    .update_active_editor(cx, |vim, editor, cx| {
+                        vim.read_register(Some(register), Some(editor), cx)
+                    })
+                    .unwrap_or_else(|| vim.read_register(Some(register), None, cx));
+                let Some(contents) = contents else {
+                    return;
+                };
+                let text = if search {
+                    contents.text.replace('\n', "\\n")
+                } else {
+                    contents.text.to_string()
+                };
+                prompt.update(cx, |prompt, cx| prompt.insert(&text, cx));
+            })
+        }));
+    })
+}
@@ -1,15 +1,21 @@
-use std::{fmt::Display, ops::Range, sync::Arc};
+use std::{collections::VecDeque, fmt::Display, ops::Range, sync::Arc};
 
+use crate::mapping::{MapMode, Mapping};
+use crate::normal::operator_func::OperatorFunc;
 use crate::normal::repeat::Replayer;
+use crate::normal::search::Replacement;
 use crate::surrounds::SurroundsType;
-use crate::{motion::Motion, object::Object};
+use crate::{
+    motion::Motion,
+    object::{Object, ObjectCache},
+};
 use collections::HashMap;
-use editor::{Anchor, ClipboardSelection};
-use gpui::{Action, ClipboardEntry, ClipboardItem, KeyContext};
-use language::{CursorShape, Selection, TransactionId};
+use editor::{Anchor, ClipboardSelection, Editor};
+use gpui::{Action, ClipboardEntry, ClipboardItem, KeyContext, Keystroke, WeakView};
+use language::{CursorShape, Selection, SelectionGoal, TransactionId};
 use serde::{Deserialize, Serialize};
 use ui::SharedString;
-use workspace::searchable::Direction;
+use workspace::{searchable::Direction, Pane};
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
 pub enum Mode {
@@ -65,14 +71,20 @@ pub enum Operator {
     Jump { line: bool },
     Indent,
     Outdent,
+    AutoIndent,
     Lowercase,
     Uppercase,
     OppositeCase,
     Digraph { first_char: Option<char> },
+    Literal { prefix: Option<String> },
     Register,
     RecordRegister,
     ReplayRegister,
     ToggleComments,
+    Format { keep_cursor: bool },
+    Filter,
+    CreateFold,
+    OperatorFunc,
 }
 
 #[derive(Default, Clone)]
@@ -89,16 +101,27 @@ pub struct EditorState {
     pub replacements: Vec<(Range<editor::Anchor>, String)>,
 
     pub marks: HashMap<String, Vec<Anchor>>,
-    pub stored_visual_mode: Option<(Mode, Vec<bool>)>,
+    /// The mode of the last visual selection, and for each of its
+    /// selections whether it was reversed and the goal to restore with `gv`.
+    pub stored_visual_mode: Option<(Mode, Vec<(bool, SelectionGoal)>)>,
     pub change_list: Vec<Vec<Anchor>>,
     pub change_list_position: Option<usize>,
 
     pub current_tx: Option<TransactionId>,
     pub current_anchor: Option<Selection<Anchor>>,
     pub undo_modes: HashMap<TransactionId, Mode>,
+    /// Where each cursor was when insert mode was entered.
+    pub insert_start: Option<Vec<Anchor>>,
+    /// Set while running the single normal-mode command allowed by `ctrl-o`
+    /// in insert mode, after which insert mode resumes.
+    pub temp_mode: bool,
+    /// The top left corner of the visual block that insert mode was entered
+    /// from, which the cursor returns to when insert mode is left.
+    pub block_start: Option<Anchor>,
 
     pub selected_register: Option<char>,
     pub search: SearchState,
+    pub object_cache: ObjectCache,
 }
 
 #[derive(Default, Clone, Debug)]
@@ -115,6 +138,7 @@ pub enum RecordedSelection {
     VisualBlock {
         rows: u32,
         cols: u32,
+        to_end_of_line: bool,
     },
     VisualLine {
         rows: u32,
@@ -125,6 +149,9 @@ pub enum RecordedSelection {
 pub struct Register {
     pub(crate) text: SharedString,
     pub(crate) clipboard_selections: Option<Vec<ClipboardSelection>>,
+    /// Set when the text was yanked from a visual block extended with `$`,
+    /// in which case each row holds that line's text up to its own end.
+    pub(crate) ragged_block: bool,
 }
 
 impl From<Register> for ClipboardItem {
@@ -144,6 +171,7 @@ impl From<ClipboardItem> for Register {
             Some(ClipboardEntry::String(value)) if item.entries().len() == 1 => Register {
                 text: value.text().to_owned().into(),
                 clipboard_selections: value.metadata_json::<Vec<ClipboardSelection>>(),
+                ragged_block: false,
             },
             // For now, registers can't store images. This could change in the future.
             _ => Register::default(),
@@ -156,10 +184,38 @@ impl From<String> for Register {
         Register {
             text: text.into(),
             clipboard_selections: None,
+            ragged_block: false,
         }
     }
 }
 
+/// The paste that the next `CyclePaste` replaces.
+#[derive(Clone, Debug)]
+pub struct LastPaste {
+    pub before: bool,
+    pub cursor_after: bool,
+    pub count: usize,
+    /// The yank ring entry that was pasted, if it came from the ring.
+    pub ring_index: Option<usize>,
+}
+
+/// The window opened by `q:`, `q/` or `q?` to edit and rerun an entry from
+/// the command or search history.
+#[derive(Clone)]
+pub struct CommandWindow {
+    pub editor: WeakView<Editor>,
+    /// The pane that was active when the window opened, where the chosen
+    /// entry runs.
+    pub origin: WeakView<Pane>,
+    pub kind: CommandWindowKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CommandWindowKind {
+    Command,
+    Search { backwards: bool },
+}
+
 #[derive(Default, Clone)]
 pub struct WorkspaceState {
     pub last_find: Option<Motion>,
@@ -172,6 +228,7 @@ pub struct WorkspaceState {
     pub recorded_count: Option<usize>,
     pub recorded_actions: Vec<ReplayableAction>,
     pub recorded_selection: RecordedSelection,
+    pub recorded_register: Option<char>,
 
     pub recording_register: Option<char>,
     pub last_recorded_register: Option<char>,
@@ -179,8 +236,37 @@ pub struct WorkspaceState {
     pub replayer: Option<Replayer>,
 
     pub last_yank: Option<SharedString>,
+    pub last_replacement: Option<Replacement>,
+    pub last_inserted_text: Option<SharedString>,
     pub registers: HashMap<char, Register>,
+    /// The most recent yanks and deletes, newest first.
+    pub yank_ring: Vec<Register>,
+    pub last_paste: Option<LastPaste>,
     pub recordings: HashMap<char, Vec<ReplayableAction>>,
+    /// The commands and searches run from the command line, oldest first.
+    pub command_history: Vec<String>,
+    pub search_history: Vec<String>,
+    pub command_window: Option<CommandWindow>,
+    /// Whether the command line is shown again after each command, as in
+    /// vim's Ex mode, and whether a command was run from the one shown last.
+    pub ex_mode: bool,
+    pub ex_command_ran: bool,
+    /// Insert-mode abbreviations defined with `:iabbrev`, keyed by the word
+    /// that triggers them.
+    pub abbreviations: HashMap<String, String>,
+    /// Mappings defined with `:map` and its variants, keyed by the mode they
+    /// apply in and their keys as they're written in the keymap.
+    pub mappings: HashMap<(MapMode, String), Mapping>,
+    /// Keys that mappings have expanded to, waiting to be dispatched, with
+    /// whether each may be mapped in turn.
+    pub typeahead: VecDeque<(Keystroke, bool)>,
+    pub noremap_typeahead: bool,
+    pub mapping_depth: usize,
+    /// The function `g@` calls, set by extensions that define operators.
+    pub operator_func: Option<OperatorFunc>,
+
+    pub status_message: Option<SharedString>,
+    pub keep_status_message: bool,
 }
 
 #[derive(Debug)]
@@ -216,6 +302,39 @@ pub struct SearchState {
     pub prior_selections: Vec<Range<Anchor>>,
     pub prior_operator: Option<Operator>,
     pub prior_mode: Mode,
+    pub offset: SearchOffset,
+}
+
+/// Where the cursor lands relative to a match, as in `/foo/e-1`.
+#[derive(Clone, Copy, Default, Debug, PartialEq)]
+pub enum SearchOffset {
+    #[default]
+    None,
+    Lines(i32),
+    Start(i32),
+    End(i32),
+}
+
+impl SearchOffset {
+    /// Parses the text after the search delimiter: `e`, `s` or `b`
+    /// optionally followed by `+n` or `-n`, or just a line count.
+    pub fn parse(offset: &str) -> Option<Self> {
+        if offset.is_empty() {
+            return Some(Self::None);
+        }
+        let (kind, count): (fn(i32) -> Self, _) = match offset.chars().next() {
+            Some('e') => (Self::End, &offset[1..]),
+            Some('s' | 'b') => (Self::Start, &offset[1..]),
+            _ => (Self::Lines, offset),
+        };
+        let count = match count {
+            "" => 0,
+            "+" => 1,
+            "-" => -1,
+            count => count.parse().ok()?,
+        };
+        Some(kind(count))
+    }
 }
 
 impl EditorState {
@@ -258,7 +377,7 @@ impl EditorState {
             Mode::Insert | Mode::Visual | Mode::VisualLine | Mode::VisualBlock | Mode::Replace => {
                 false
             }
-            Mode::Normal => true,
+            Mode::Normal => !self.temp_mode,
         }
     }
 
@@ -315,6 +434,7 @@ impl Operator {
             Operator::Yank => "y",
             Operator::Replace => "r",
             Operator::Digraph { .. } => "^K",
+            Operator::Literal { .. } => "^V",
             Operator::FindForward { before: false } => "f",
             Operator::FindForward { before: true } => "t",
             Operator::FindBackward { after: false } => "F",
@@ -327,6 +447,7 @@ impl Operator {
             Operator::Jump { line: false } => "`",
             Operator::Indent => ">",
             Operator::Outdent => "<",
+            Operator::AutoIndent => "=",
             Operator::Uppercase => "gU",
             Operator::Lowercase => "gu",
             Operator::OppositeCase => "g~",
@@ -334,6 +455,11 @@ impl Operator {
             Operator::RecordRegister => "q",
             Operator::ReplayRegister => "@",
             Operator::ToggleComments => "gc",
+            Operator::Format { keep_cursor: false } => "gq",
+            Operator::Format { keep_cursor: true } => "gw",
+            Operator::Filter => "!",
+            Operator::CreateFold => "zf",
+            Operator::OperatorFunc => "g@",
         }
     }
 
@@ -349,6 +475,7 @@ impl Operator {
             | Operator::ReplayRegister
             | Operator::Replace
             | Operator::Digraph { .. }
+            | Operator::Literal { .. }
             | Operator::ChangeSurrounds { target: Some(_) }
             | Operator::DeleteSurrounds => true,
             Operator::Change
@@ -356,12 +483,17 @@ impl Operator {
             | Operator::Yank
             | Operator::Indent
             | Operator::Outdent
+            | Operator::AutoIndent
             | Operator::Lowercase
             | Operator::Uppercase
             | Operator::Object { .. }
             | Operator::ChangeSurrounds { target: None }
             | Operator::OppositeCase
-            | Operator::ToggleComments => false,
+            | Operator::ToggleComments
+            | Operator::Format { .. }
+            | Operator::Filter
+            | Operator::CreateFold
+            | Operator::OperatorFunc => false,
         }
     }
 }
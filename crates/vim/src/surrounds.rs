@@ -55,7 +55,7 @@ pub fn add_surrounds(text: Arc<str>, target: SurroundsType, cx: &mut WindowConte
                 for selection in &display_selections {
                     let range = match &target {
                         SurroundsType::Object(object) => {
-                            object.range(&display_map, selection.clone(), false)
+                            object.range(&display_map, selection.clone(), false, None)
                         }
                         SurroundsType::Motion(motion) => {
                             let range = motion
@@ -157,7 +157,9 @@ pub fn delete_surrounds(text: Arc<str>, cx: &mut WindowContext) {
 
                 for selection in &display_selections {
                     let start = selection.start.to_offset(&display_map, Bias::Left);
-                    if let Some(range) = pair_object.range(&display_map, selection.clone(), true) {
+                    if let Some(range) =
+                        pair_object.range(&display_map, selection.clone(), true, None)
+                    {
                         // If the current parenthesis object is single-line,
                         // then we need to filter whether it is the current line or not
                         if !pair_object.is_multiline() {
@@ -358,7 +360,7 @@ pub fn check_and_move_to_valid_bracket_pair(
 
                 for selection in &selections {
                     let start = selection.start.to_offset(&display_map, Bias::Left);
-                    if let Some(range) = object.range(&display_map, selection.clone(), true) {
+                    if let Some(range) = object.range(&display_map, selection.clone(), true, None) {
                         // If the current parenthesis object is single-line,
                         // then we need to filter whether it is the current line or not
                         if object.is_multiline()
@@ -1091,6 +1093,36 @@ mod test {
         );
     }
 
+    #[gpui::test]
+    async fn test_repeat_surrounds(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("\"oˇne\"\n\"two\"", Mode::Normal);
+        cx.simulate_keystrokes("c s \" '");
+        cx.assert_state("ˇ'one'\n\"two\"", Mode::Normal);
+        cx.simulate_keystrokes("j l .");
+        cx.assert_state("'one'\nˇ'two'", Mode::Normal);
+
+        cx.set_state("(oˇne) (two)", Mode::Normal);
+        cx.simulate_keystrokes("d s (");
+        cx.assert_state("ˇone (two)", Mode::Normal);
+        cx.simulate_keystrokes("f t .");
+        cx.assert_state("one ˇtwo", Mode::Normal);
+
+        cx.update(|cx| {
+            cx.bind_keys([KeyBinding::new(
+                "shift-s",
+                PushOperator(Operator::AddSurrounds { target: None }),
+                Some("vim_mode == visual"),
+            )])
+        });
+        cx.set_state("oˇne two", Mode::Normal);
+        cx.simulate_keystrokes("v i w shift-s )");
+        cx.assert_state("ˇ(one) two", Mode::Normal);
+        cx.simulate_keystrokes("f t .");
+        cx.assert_state("(one) ˇ(two)", Mode::Normal);
+    }
+
     #[gpui::test]
     async fn test_surrounds(cx: &mut gpui::TestAppContext) {
         let mut cx = VimTestContext::new(cx, true).await;
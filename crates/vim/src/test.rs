@@ -14,10 +14,11 @@ use settings::SettingsStore;
 pub use vim_test_context::*;
 
 use indoc::indoc;
+use outline::OutlineView;
 use search::BufferSearchBar;
 use workspace::WorkspaceSettings;
 
-use crate::{insert::NormalBefore, motion, state::Mode, ModeIndicator};
+use crate::{insert::NormalBefore, motion, state::Mode, ModeIndicator, VimSettings};
 
 #[gpui::test]
 async fn test_initially_disabled(cx: &mut gpui::TestAppContext) {
@@ -231,6 +232,38 @@ async fn test_escape_command_palette(cx: &mut gpui::TestAppContext) {
     cx.assert_state("aˇbc\n", Mode::Insert);
 }
 
+#[gpui::test]
+async fn test_paste_register_in_prompts(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state("ˇhello foo.bar\n", Mode::Normal);
+    cx.simulate_keystrokes("y w : ctrl-r \"");
+    let query = cx.workspace(|workspace, cx| {
+        workspace
+            .active_modal::<CommandPalette>(cx)
+            .expect("command palette should be open")
+            .read(cx)
+            .query(cx)
+    });
+    assert_eq!(query, "hello ");
+    cx.simulate_keystrokes("escape");
+    cx.run_until_parked();
+
+    cx.simulate_keystrokes("w / ctrl-r ctrl-a");
+    let search_bar = cx.workspace(|workspace, cx| {
+        workspace
+            .active_pane()
+            .read(cx)
+            .toolbar()
+            .read(cx)
+            .item_of_type::<BufferSearchBar>()
+            .expect("Buffer search bar should be deployed")
+    });
+    cx.update_view(search_bar, |bar, cx| {
+        assert_eq!(bar.query(cx), "foo.bar");
+    });
+}
+
 #[gpui::test]
 async fn test_escape_cancels(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new(cx, true).await;
@@ -332,6 +365,44 @@ async fn test_status_indicator(cx: &mut gpui::TestAppContext) {
     });
 }
 
+#[gpui::test]
+async fn test_showmode(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    let mode_indicator = cx.workspace(|workspace, cx| {
+        let status_bar = workspace.status_bar().read(cx);
+        status_bar.item_of_type::<ModeIndicator>().unwrap()
+    });
+    let label = |cx: &mut VimTestContext| cx.workspace(|_, cx| mode_indicator.read(cx).label(cx));
+
+    // normal mode isn't named
+    assert_eq!(label(&mut cx), "");
+    cx.simulate_keystrokes("i");
+    assert_eq!(label(&mut cx), "-- INSERT --");
+    cx.simulate_keystrokes("escape shift-r");
+    assert_eq!(label(&mut cx), "-- REPLACE --");
+    cx.simulate_keystrokes("escape v");
+    assert_eq!(label(&mut cx), "-- VISUAL --");
+    cx.simulate_keystrokes("escape shift-v");
+    assert_eq!(label(&mut cx), "-- VISUAL LINE --");
+    cx.simulate_keystrokes("escape ctrl-v");
+    assert_eq!(label(&mut cx), "-- VISUAL BLOCK --");
+
+    // pending operators are shown too
+    cx.simulate_keystrokes("escape d");
+    assert_eq!(label(&mut cx), "d");
+    cx.simulate_keystrokes("escape v d");
+    assert_eq!(label(&mut cx), "");
+
+    cx.update_global(|store: &mut SettingsStore, cx| {
+        store.update_user_settings::<VimSettings>(cx, |s| s.showmode = Some(false));
+    });
+    cx.simulate_keystrokes("i");
+    assert_eq!(label(&mut cx), "");
+    cx.simulate_keystrokes("escape d");
+    assert_eq!(label(&mut cx), "");
+}
+
 #[gpui::test]
 async fn test_word_characters(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new_typescript(cx).await;
@@ -403,6 +474,46 @@ async fn test_join_lines(cx: &mut gpui::TestAppContext) {
       "});
 }
 
+#[gpui::test]
+async fn test_join_lines_without_whitespace(cx: &mut gpui::TestAppContext) {
+    let mut cx = NeovimBackedTestContext::new(cx).await;
+
+    cx.set_shared_state(indoc! {"
+      ˇone
+          two
+      three
+      four
+      five
+      "})
+        .await;
+    cx.simulate_shared_keystrokes("g shift-j").await;
+    cx.shared_state().await.assert_eq(indoc! {"
+          oneˇ    two
+          three
+          four
+          five
+          "});
+    cx.simulate_shared_keystrokes("3 g shift-j").await;
+    cx.shared_state().await.assert_eq(indoc! {"
+          one    twothreeˇfour
+          five
+          "});
+
+    cx.set_shared_state(indoc! {"
+      ˇone
+      two
+      three
+      four
+      "})
+        .await;
+    cx.simulate_shared_keystrokes("j v j g shift-j").await;
+    cx.shared_state().await.assert_eq(indoc! {"
+      one
+      twoˇthree
+      four
+      "});
+}
+
 #[cfg(target_os = "macos")]
 #[gpui::test]
 async fn test_wrapped_lines(cx: &mut gpui::TestAppContext) {
@@ -620,6 +731,102 @@ async fn test_folds(cx: &mut gpui::TestAppContext) {
     "});
 }
 
+#[gpui::test]
+async fn test_fold_commands(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state(
+        indoc! {"
+            fn a() {
+                ˇb();
+            }
+
+            fn c() {
+                d();
+            }
+        "},
+        Mode::Normal,
+    );
+    let unfolded = "fn a() {\n    b();\n}\n\nfn c() {\n    d();\n}\n";
+
+    cx.simulate_keystrokes("z f i p");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "⋯\n\nfn c() {\n    d();\n}\n"
+    );
+    cx.assert_state(
+        indoc! {"
+            ˇfn a() {
+                b();
+            }
+
+            fn c() {
+                d();
+            }
+        "},
+        Mode::Normal,
+    );
+
+    cx.simulate_keystrokes("z a");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        unfolded
+    );
+    cx.simulate_keystrokes("z a");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "fn a() {⋯\n}\n\nfn c() {\n    d();\n}\n"
+    );
+
+    cx.simulate_keystrokes("z shift-r");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        unfolded
+    );
+
+    cx.simulate_keystrokes("z shift-m");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "fn a() {⋯\n}\n\nfn c() {⋯\n}\n"
+    );
+    cx.simulate_keystrokes("z d");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "fn a() {\n    b();\n}\n\nfn c() {⋯\n}\n"
+    );
+
+    // counts apply to the motion
+    cx.simulate_keystrokes("z shift-r g g z f 2 j");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "⋯\n\nfn c() {\n    d();\n}\n"
+    );
+
+    // but there are no fold levels for them to apply to otherwise
+    cx.simulate_keystrokes("z shift-r 3 z shift-m");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        "fn a() {⋯\n}\n\nfn c() {⋯\n}\n"
+    );
+    cx.simulate_keystrokes("2 z shift-r j");
+    assert_eq!(
+        cx.update_editor(|editor, cx| editor.display_text(cx)),
+        unfolded
+    );
+    cx.assert_state(
+        indoc! {"
+            fn a() {
+            ˇ    b();
+            }
+
+            fn c() {
+                d();
+            }
+        "},
+        Mode::Normal,
+    );
+}
+
 #[gpui::test]
 async fn test_folds_panic(cx: &mut gpui::TestAppContext) {
     let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -799,7 +1006,7 @@ async fn test_select_all_issue_2170(cx: &mut gpui::TestAppContext) {
     "},
         Mode::Normal,
     );
-    cx.simulate_keystrokes("g a");
+    cx.simulate_keystrokes("v g a");
     cx.assert_state(
         indoc! {"
         defmodule Test do
@@ -1439,6 +1646,53 @@ async fn test_ctrl_w_override(cx: &mut gpui::TestAppContext) {
     cx.shared_state().await.assert_eq("ˇ");
 }
 
+#[gpui::test]
+async fn test_autoindent(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+
+    cx.set_state(
+        indoc! {"
+            fn a() {
+                    if b {
+            c();
+
+                      }
+            ˇd();
+            }
+        "},
+        Mode::Normal,
+    );
+    cx.simulate_keystrokes("= a shift-b");
+    cx.assert_state(
+        indoc! {"
+            fn a() {
+                if b {
+                    c();
+
+                }
+                ˇd();
+            }
+        "},
+        Mode::Normal,
+    );
+
+    cx.set_state("fn a() {\nb();\n        ˇc();\n}\n", Mode::Normal);
+    cx.simulate_keystrokes("= i {");
+    cx.assert_state("fn a() {\n    b();\n    ˇc();\n}\n", Mode::Normal);
+
+    cx.set_state("let a = [\n1,\n        ˇ2,\n];\n", Mode::Normal);
+    cx.simulate_keystrokes("= i [");
+    cx.assert_state("let a = [\n    1,\n    ˇ2,\n];\n", Mode::Normal);
+
+    cx.set_state("fn a() {\nˇb();\n}\n", Mode::Normal);
+    cx.simulate_keystrokes("= =");
+    cx.assert_state("fn a() {\n    ˇb();\n}\n", Mode::Normal);
+
+    cx.set_state("fn a() {\nˇb();\n  c();\n}\n", Mode::Normal);
+    cx.simulate_keystrokes("shift-v j =");
+    cx.assert_state("fn a() {\n    ˇb();\n    c();\n}\n", Mode::Normal);
+}
+
 #[gpui::test]
 async fn test_visual_indent_count(cx: &mut gpui::TestAppContext) {
     let mut cx = VimTestContext::new(cx, true).await;
@@ -1460,3 +1714,52 @@ async fn test_record_replay_recursion(cx: &mut gpui::TestAppContext) {
     cx.simulate_shared_keystrokes(".").await;
     cx.shared_state().await.assert_eq("ˇhello world"); // takes a _long_ time
 }
+
+#[gpui::test]
+async fn test_outline(cx: &mut gpui::TestAppContext) {
+    let mut cx = VimTestContext::new(cx, true).await;
+    cx.update(outline::init);
+    let has_outline = |cx: &mut VimTestContext| {
+        cx.workspace(|workspace, cx| workspace.active_modal::<OutlineView>(cx).is_some())
+    };
+
+    // nothing to show without an outline
+    cx.set_state("ˇfn a() {}", Mode::Normal);
+    cx.simulate_keystrokes("g shift-o");
+    assert!(!has_outline(&mut cx));
+
+    let language = std::sync::Arc::new(
+        language::Language::new(
+            language::LanguageConfig {
+                name: "Ruby".into(),
+                ..Default::default()
+            },
+            Some(tree_sitter_ruby::language()),
+        )
+        .with_outline_query(r#"(method "def" @context name: (_) @name) @item"#)
+        .unwrap(),
+    );
+    cx.update_buffer(|buffer, cx| buffer.set_language(Some(language), cx));
+    cx.set_state(
+        &format!("ˇdef foo\nend\n{}def bar\nend\n", "\n".repeat(20)),
+        Mode::Normal,
+    );
+    cx.simulate_keystrokes("g shift-o");
+    assert!(has_outline(&mut cx));
+    cx.simulate_input("bar");
+    cx.run_until_parked();
+    cx.dispatch_action(menu::Confirm);
+    assert!(!has_outline(&mut cx));
+    cx.assert_state(
+        &format!("def foo\nend\n{}ˇdef bar\nend\n", "\n".repeat(20)),
+        Mode::Normal,
+    );
+
+    // the jump can be undone with the jumplist
+    cx.simulate_keystrokes("ctrl-o");
+    cx.run_until_parked();
+    cx.assert_state(
+        &format!("ˇdef foo\nend\n{}def bar\nend\n", "\n".repeat(20)),
+        Mode::Normal,
+    );
+}
@@ -1,10 +1,13 @@
-use std::ops::{Deref, DerefMut};
+use std::{
+    ops::{Deref, DerefMut},
+    rc::Rc,
+};
 
 use editor::test::editor_lsp_test_context::EditorLspTestContext;
-use gpui::{Context, SemanticVersion, View, VisualContext};
+use gpui::{px, size, Context, SemanticVersion, View, VisualContext};
 use search::{project_search::ProjectSearchBar, BufferSearchBar};
 
-use crate::{state::Operator, *};
+use crate::{clipboard::TestClipboard, state::Operator, *};
 
 pub struct VimTestContext {
     cx: EditorLspTestContext,
@@ -95,6 +98,34 @@ impl VimTestContext {
             .unwrap()
     }
 
+    /// Resizes the window so that `rows` whole lines of the editor are
+    /// visible, along with part of the next.
+    pub fn set_scroll_height(&mut self, rows: u32) {
+        let (line_height, visible_line_count) = self.editor(|editor, cx| {
+            (
+                editor
+                    .style()
+                    .unwrap()
+                    .text
+                    .line_height_in_pixels(cx.rem_size()),
+                editor.visible_line_count().unwrap(),
+            )
+        });
+        let window = self.window;
+        let margin = self
+            .update_window(window, |_, cx| {
+                cx.viewport_size().height - line_height * visible_line_count
+            })
+            .unwrap();
+        self.simulate_window_resize(
+            window,
+            size(
+                px(1000.),
+                margin + (rows + 1) as f32 * line_height - px(1.0),
+            ),
+        );
+    }
+
     pub fn workspace<F, T>(&mut self, update: F) -> T
     where
         F: FnOnce(&mut Workspace, &mut ViewContext<Workspace>) -> T,
@@ -118,6 +149,17 @@ impl VimTestContext {
         })
     }
 
+    /// Gives vim clipboards of its own, which the test can see and change
+    /// through the returned [`TestClipboard`].
+    pub fn use_test_clipboard(&mut self) -> Rc<TestClipboard> {
+        let clipboard = Rc::new(TestClipboard::default());
+        let provider = clipboard.clone();
+        self.cx.update(|cx| {
+            Vim::update_global(cx, |vim, _| vim.clipboard = Some(provider));
+        });
+        clipboard
+    }
+
     pub fn mode(&mut self) -> Mode {
         self.cx.read(|cx| cx.global::<Vim>().state().mode)
     }
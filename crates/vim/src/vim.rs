@@ -3,15 +3,21 @@
 #[cfg(test)]
 mod test;
 
+mod abbreviation;
 mod change_list;
+mod clipboard;
 mod command;
+mod command_window;
 mod digraph;
 mod editor_events;
+mod expression;
 mod insert;
+mod mapping;
 mod mode_indicator;
 mod motion;
 mod normal;
 mod object;
+mod prompt;
 mod replace;
 mod state;
 mod surrounds;
@@ -19,11 +25,15 @@ mod visual;
 
 use anyhow::Result;
 use change_list::push_to_change_list;
+use clipboard::{
+    ClipboardBackend, ClipboardKind, ClipboardProvider, Osc52Clipboard, SystemClipboard,
+};
 use collections::HashMap;
 use command_palette_hooks::{CommandPaletteFilter, CommandPaletteInterceptor};
 use editor::{
     movement::{self, FindRange},
-    Anchor, Bias, Editor, EditorEvent, EditorMode, ToPoint,
+    scroll::DEFAULT_HORIZONTAL_SCROLL_MARGIN,
+    Anchor, Bias, Editor, EditorEvent, EditorMode, EditorSettings, ToPoint,
 };
 use gpui::{
     actions, impl_actions, Action, AppContext, EntityId, FocusableView, Global, KeystrokeEvent,
@@ -32,18 +42,20 @@ use gpui::{
 use language::{CursorShape, Point, SelectionGoal, TransactionId};
 pub use mode_indicator::ModeIndicator;
 use motion::Motion;
+pub use normal::operator_func::{set_operator_func, OperatorFuncType};
 use normal::{
     mark::create_visual_marks,
     normal_replace,
-    repeat::{observe_action, observe_insertion, record_register, replay_register},
+    repeat::{observe_action, observe_insertion, record_register, recorded_block, replay_register},
 };
+use object::ObjectCache;
 use replace::multi_replace;
 use schemars::JsonSchema;
 use serde::Deserialize;
 use serde_derive::Serialize;
 use settings::{update_settings_file, Settings, SettingsSources, SettingsStore};
 use state::{EditorState, Mode, Operator, RecordedSelection, Register, WorkspaceState};
-use std::{ops::Range, sync::Arc};
+use std::{ops::Range, rc::Rc, sync::Arc};
 use surrounds::{add_surrounds, change_surrounds, delete_surrounds, SurroundsType};
 use ui::BorrowAppContext;
 use visual::{visual_block_motion, visual_replace};
@@ -51,6 +63,9 @@ use workspace::{self, Workspace};
 
 use crate::state::ReplayableAction;
 
+/// How many recent yanks and deletes the yank ring keeps.
+const YANK_RING_SIZE: usize = 10;
+
 /// Whether or not to enable Vim mode (work in progress).
 ///
 /// Default: false
@@ -165,21 +180,62 @@ fn register(workspace: &mut Workspace, cx: &mut ViewContext<Workspace>) {
     insert::register(workspace, cx);
     motion::register(workspace, cx);
     command::register(workspace, cx);
+    command_window::register(workspace, cx);
     replace::register(workspace, cx);
     object::register(workspace, cx);
     visual::register(workspace, cx);
     change_list::register(workspace, cx);
+    digraph::register(workspace, cx);
+    abbreviation::register(workspace, cx);
+    mapping::register(workspace, cx);
 }
 
 /// Called whenever an keystroke is typed so vim can observe all actions
 /// and keystrokes accordingly.
 fn observe_keystrokes(keystroke_event: &KeystrokeEvent, cx: &mut WindowContext) {
+    // A status message stays until the keystroke after the one that showed it.
+    let workspace_state = &Vim::read(cx).workspace_state;
+    if workspace_state.status_message.is_some() || workspace_state.keep_status_message {
+        Vim::update(cx, |vim, _| {
+            if !std::mem::take(&mut vim.workspace_state.keep_status_message) {
+                vim.workspace_state.status_message = None;
+            }
+        });
+    }
+
+    // Insertion starts wherever the action that entered insert mode left the
+    // cursors, which `ctrl-w` and `ctrl-u` stop at.
+    let state = Vim::read(cx).state();
+    if state.mode == Mode::Insert && state.insert_start.is_none() {
+        Vim::update(cx, |vim, cx| vim.record_insert_start(cx));
+    }
+
+    // The text that was just pasted can only be cycled through the yank
+    // ring until something else happens.
+    if Vim::read(cx).workspace_state.last_paste.is_some()
+        && !cx.has_pending_keystrokes()
+        && !keystroke_event
+            .action
+            .as_ref()
+            .is_some_and(|action| matches!(action.name(), "vim::Paste" | "vim::CyclePaste"))
+    {
+        Vim::update(cx, |vim, cx| {
+            vim.workspace_state.last_paste = None;
+            vim.sync_vim_settings(cx);
+        });
+    }
+
     if let Some(action) = keystroke_event
         .action
         .as_ref()
         .map(|action| action.boxed_clone())
     {
+        // Only the keys that a mapping expands to are recorded, as they run.
+        if action.as_any().is::<mapping::RunMapping>() {
+            return;
+        }
         observe_action(action.boxed_clone(), cx);
+        insert::resume_insert(&*action, cx);
 
         // Keystroke is handled by the vim system, so continue forward
         if action.name().starts_with("vim::") {
@@ -208,6 +264,12 @@ struct Vim {
     editor_states: HashMap<EntityId, EditorState>,
     workspace_state: WorkspaceState,
     default_state: EditorState,
+    prompt_subscription: Option<Subscription>,
+    prompt_history: Option<prompt::HistoryPosition>,
+    substitute_preview: Option<WeakView<Editor>>,
+    /// Replaces the system clipboards, in tests.
+    clipboard: Option<Rc<dyn ClipboardProvider>>,
+    osc52_clipboard: Osc52Clipboard,
 }
 
 impl Global for Vim {}
@@ -248,7 +310,10 @@ impl Vim {
             EditorEvent::InputHandled {
                 text,
                 utf16_range_to_replace: range_to_replace,
-            } => observe_insertion(text, range_to_replace.clone(), cx),
+            } => {
+                observe_insertion(text, range_to_replace.clone(), cx);
+                abbreviation::expand_abbreviation(text, cx)
+            }
             EditorEvent::TransactionBegun { transaction_id } => Vim::update(cx, |vim, cx| {
                 vim.transaction_begun(*transaction_id, cx);
             }),
@@ -302,24 +367,32 @@ impl Vim {
     /// When doing an action that modifies the buffer, we start recording so that `.`
     /// will replay the action.
     pub fn start_recording(&mut self, cx: &mut WindowContext) {
+        // A command run with `ctrl-o` is part of the insertion being recorded.
+        if self.state().temp_mode && self.workspace_state.dot_recording {
+            return;
+        }
         if !self.workspace_state.dot_replaying {
             self.workspace_state.dot_recording = true;
             self.workspace_state.recorded_actions = Default::default();
             self.workspace_state.recorded_count = None;
+            self.workspace_state.recorded_register = self.state().selected_register;
 
+            let mode = self.state().mode;
             let selections = self
                 .active_editor
                 .as_ref()
                 .and_then(|editor| editor.upgrade())
                 .map(|editor| {
                     let editor = editor.read(cx);
+                    let block = (mode == Mode::VisualBlock).then(|| recorded_block(editor, cx));
                     (
                         editor.selections.oldest::<Point>(cx),
                         editor.selections.newest::<Point>(cx),
+                        block,
                     )
                 });
 
-            if let Some((oldest, newest)) = selections {
+            if let Some((oldest, newest, block)) = selections {
                 self.workspace_state.recorded_selection = match self.state().mode {
                     Mode::Visual if newest.end.row == newest.start.row => {
                         RecordedSelection::SingleLine {
@@ -333,10 +406,7 @@ impl Vim {
                     Mode::VisualLine => RecordedSelection::VisualLine {
                         rows: newest.end.row - newest.start.row,
                     },
-                    Mode::VisualBlock => RecordedSelection::VisualBlock {
-                        rows: newest.end.row.abs_diff(oldest.start.row),
-                        cols: newest.end.column.abs_diff(oldest.start.column),
-                    },
+                    Mode::VisualBlock => block.unwrap_or_default(),
                     _ => RecordedSelection::None,
                 }
             } else {
@@ -356,7 +426,7 @@ impl Vim {
     /// as you usually call this within a keystroke handler we also ensure that
     /// the current action is recorded.
     pub fn stop_recording(&mut self) {
-        if self.workspace_state.dot_recording {
+        if self.workspace_state.dot_recording && !self.state().temp_mode {
             self.workspace_state.stop_recording_after_next_action = true;
         }
     }
@@ -366,7 +436,7 @@ impl Vim {
     ///
     /// This doesn't include the current action.
     pub fn stop_recording_immediately(&mut self, action: Box<dyn Action>) {
-        if self.workspace_state.dot_recording {
+        if self.workspace_state.dot_recording && !self.state().temp_mode {
             self.workspace_state
                 .recorded_actions
                 .push(ReplayableAction::Action(action.boxed_clone()));
@@ -381,6 +451,18 @@ impl Vim {
         self.stop_recording();
     }
 
+    fn record_insert_start(&mut self, cx: &mut WindowContext) {
+        self.update_active_editor(cx, |vim, editor, _| {
+            let heads = editor
+                .selections
+                .disjoint_anchors()
+                .iter()
+                .map(|selection| selection.head())
+                .collect();
+            vim.update_state(|state| state.insert_start = Some(heads));
+        });
+    }
+
     // When handling an action, you must create visual marks if you will switch to normal
     // mode without the default selection behavior.
     fn store_visual_marks(&mut self, cx: &mut WindowContext) {
@@ -390,6 +472,20 @@ impl Vim {
         }
     }
 
+    /// Shows `message` in the mode indicator if `lines` is more than the
+    /// `report` setting, as vim does for operations on many lines.
+    fn report(&mut self, lines: u32, message: String, cx: &AppContext) {
+        if lines > VimSettings::get_global(cx).report {
+            self.set_status_message(message);
+        }
+    }
+
+    /// Shows `message` in the status line until the next keystroke.
+    fn set_status_message(&mut self, message: String) {
+        self.workspace_state.status_message = Some(message.into());
+        self.workspace_state.keep_status_message = true;
+    }
+
     fn switch_mode(&mut self, mode: Mode, leave_selections: bool, cx: &mut WindowContext) {
         let state = self.state();
         let last_mode = state.mode;
@@ -403,6 +499,7 @@ impl Vim {
             if mode == Mode::Normal || mode != last_mode {
                 state.current_tx.take();
                 state.current_anchor.take();
+                state.insert_start.take();
             }
         });
         if mode != Mode::Insert && mode != Mode::Replace {
@@ -419,6 +516,18 @@ impl Vim {
         if !mode.is_visual() && last_mode.is_visual() {
             create_visual_marks(self, last_mode, cx);
         }
+        if last_mode == Mode::VisualBlock && mode == Mode::Insert {
+            let block_start = self.update_active_editor(cx, |_, editor, cx| {
+                let start = editor.selections.first::<Point>(cx).start;
+                editor.buffer().read(cx).snapshot(cx).anchor_before(start)
+            });
+            self.update_state(|state| state.block_start = block_start);
+        }
+        let block_start = if last_mode == Mode::Insert && prior_mode == Mode::VisualBlock {
+            self.update_state(|state| state.block_start.take())
+        } else {
+            None
+        };
 
         // Adjust selections
         self.update_active_editor(cx, |_, editor, cx| {
@@ -446,7 +555,7 @@ impl Vim {
                     && prior_mode == Mode::VisualBlock
                     && mode != Mode::VisualBlock
                 {
-                    let pos = s.first_anchor().head();
+                    let pos = block_start.unwrap_or_else(|| s.first_anchor().head());
                     s.select_anchor_ranges(vec![pos..pos])
                 }
 
@@ -546,6 +655,15 @@ impl Vim {
         cx: &mut ViewContext<Editor>,
     ) {
         if let Some(register) = register {
+            // Nothing, not even the unnamed register, is written by a delete or
+            // yank into the black hole register.
+            if register == '_' {
+                return;
+            }
+            if register != '"' && VimSettings::get_global(cx).report_registers {
+                let verb = if is_yank { "yanked" } else { "deleted" };
+                self.set_status_message(format!("{verb} into register {register}"));
+            }
             let lower = register.to_lowercase().next().unwrap_or(register);
             if lower != register {
                 let current = self.workspace_state.registers.entry(lower).or_default();
@@ -557,15 +675,14 @@ impl Vim {
             } else {
                 self.workspace_state.registers.insert('"', content.clone());
                 match lower {
-                    '_' | ':' | '.' | '%' | '#' | '=' | '/' => {}
+                    ':' | '.' | '%' | '#' | '=' | '/' => {}
                     '+' => {
-                        cx.write_to_clipboard(content.into());
+                        self.clipboard(cx)
+                            .write(ClipboardKind::Clipboard, content.into(), cx);
                     }
                     '*' => {
-                        #[cfg(target_os = "linux")]
-                        cx.write_to_primary(content.into());
-                        #[cfg(not(target_os = "linux"))]
-                        cx.write_to_clipboard(content.into());
+                        self.clipboard(cx)
+                            .write(ClipboardKind::Primary, content.into(), cx);
                     }
                     '"' => {
                         self.workspace_state.registers.insert('0', content.clone());
@@ -582,10 +699,12 @@ impl Vim {
                 || setting == UseSystemClipboard::OnYank && is_yank
             {
                 self.workspace_state.last_yank.replace(content.text.clone());
-                cx.write_to_clipboard(content.clone().into());
+                self.clipboard(cx)
+                    .write(ClipboardKind::Clipboard, content.clone().into(), cx);
             } else {
-                self.workspace_state.last_yank = cx
-                    .read_from_clipboard()
+                self.workspace_state.last_yank = self
+                    .clipboard(cx)
+                    .read(ClipboardKind::Clipboard, cx)
                     .and_then(|item| item.text().map(|string| string.into()))
             }
 
@@ -609,6 +728,22 @@ impl Vim {
                 }
             }
         }
+
+        if let Some(content) = self.workspace_state.registers.get(&'"').cloned() {
+            let ring = &mut self.workspace_state.yank_ring;
+            ring.insert(0, content);
+            ring.truncate(YANK_RING_SIZE);
+        }
+    }
+
+    fn clipboard(&self, cx: &AppContext) -> &dyn ClipboardProvider {
+        if let Some(clipboard) = self.clipboard.as_deref() {
+            return clipboard;
+        }
+        match VimSettings::get_global(cx).clipboard {
+            ClipboardBackend::System => &SystemClipboard,
+            ClipboardBackend::Osc52 => &self.osc52_clipboard,
+        }
     }
 
     fn read_register(
@@ -620,27 +755,44 @@ impl Vim {
         let Some(register) = register.filter(|reg| *reg != '"') else {
             let setting = VimSettings::get_global(cx).use_system_clipboard;
             return match setting {
-                UseSystemClipboard::Always => cx.read_from_clipboard().map(|item| item.into()),
-                UseSystemClipboard::OnYank if self.system_clipboard_is_newer(cx) => {
-                    cx.read_from_clipboard().map(|item| item.into())
-                }
+                UseSystemClipboard::Always => self
+                    .clipboard(cx)
+                    .read(ClipboardKind::Clipboard, cx)
+                    .map(|item| {
+                        // The clipboard can't record everything about a register, so
+                        // prefer our own copy while it still holds the same text.
+                        match self.workspace_state.registers.get(&'"') {
+                            Some(register)
+                                if item.text().as_deref() == Some(register.text.as_ref()) =>
+                            {
+                                register.clone()
+                            }
+                            _ => item.into(),
+                        }
+                    }),
+                UseSystemClipboard::OnYank if self.system_clipboard_is_newer(cx) => self
+                    .clipboard(cx)
+                    .read(ClipboardKind::Clipboard, cx)
+                    .map(|item| item.into()),
                 _ => self.workspace_state.registers.get(&'"').cloned(),
             };
         };
         let lower = register.to_lowercase().next().unwrap_or(register);
         match lower {
-            '_' | ':' | '.' | '#' | '=' => None,
-            '+' => cx.read_from_clipboard().map(|item| item.into()),
-            '*' => {
-                #[cfg(target_os = "linux")]
-                {
-                    cx.read_from_primary().map(|item| item.into())
-                }
-                #[cfg(not(target_os = "linux"))]
-                {
-                    cx.read_from_clipboard().map(|item| item.into())
-                }
-            }
+            '_' | ':' | '=' => None,
+            '.' => self
+                .workspace_state
+                .last_inserted_text
+                .as_ref()
+                .map(|text| text.to_string().into()),
+            '+' => self
+                .clipboard(cx)
+                .read(ClipboardKind::Clipboard, cx)
+                .map(|item| item.into()),
+            '*' => self
+                .clipboard(cx)
+                .read(ClipboardKind::Primary, cx)
+                .map(|item| item.into()),
             '%' => editor.and_then(|editor| {
                 let selection = editor.selections.newest::<Point>(cx);
                 if let Some((_, buffer, _)) = editor
@@ -656,18 +808,34 @@ impl Vim {
                     None
                 }
             }),
+            // The alternate file is the one that was active in the pane
+            // before the current one.
+            '#' => {
+                let workspace = editor?.workspace()?;
+                let pane = workspace.read(cx).active_pane().read(cx);
+                let active_id = pane.active_item()?.item_id();
+                pane.activation_history()
+                    .iter()
+                    .rev()
+                    .filter(|entry| entry.entity_id != active_id)
+                    .find_map(|entry| pane.items().find(|item| item.item_id() == entry.entity_id))
+                    .and_then(|item| item.project_path(cx))
+                    .map(|path| path.path.to_string_lossy().to_string().into())
+            }
             _ => self.workspace_state.registers.get(&lower).cloned(),
         }
     }
 
     fn system_clipboard_is_newer(&self, cx: &mut AppContext) -> bool {
-        cx.read_from_clipboard().is_some_and(|item| {
-            if let Some(last_state) = &self.workspace_state.last_yank {
-                Some(last_state.as_ref()) != item.text().as_deref()
-            } else {
-                true
-            }
-        })
+        self.clipboard(cx)
+            .read(ClipboardKind::Clipboard, cx)
+            .is_some_and(|item| {
+                if let Some(last_state) = &self.workspace_state.last_yank {
+                    Some(last_state.as_ref()) != item.text().as_deref()
+                } else {
+                    true
+                }
+            })
     }
 
     fn push_operator(&mut self, operator: Operator, cx: &mut WindowContext) {
@@ -678,10 +846,12 @@ impl Vim {
                 | Operator::Replace
                 | Operator::Indent
                 | Operator::Outdent
+                | Operator::AutoIndent
                 | Operator::Lowercase
                 | Operator::Uppercase
                 | Operator::OppositeCase
                 | Operator::ToggleComments
+                | Operator::Format { .. }
         ) {
             self.start_recording(cx)
         };
@@ -695,7 +865,12 @@ impl Vim {
                 | Operator::DeleteSurrounds
         ) {
             self.update_state(|state| state.operator_stack.clear());
-            if let Operator::AddSurrounds { target: None } = operator {
+            // Record from the surround operator itself so that `.` repeats it
+            // without the `c`, `d` or `y` that led to it.
+            if let Operator::AddSurrounds { target: None }
+            | Operator::ChangeSurrounds { target: None }
+            | Operator::DeleteSurrounds = operator
+            {
                 self.start_recording(cx);
             }
         };
@@ -879,6 +1054,7 @@ impl Vim {
                     });
                 }
             }
+            Some(Operator::Literal { prefix }) => digraph::insert_literal(prefix, &text, cx),
             Some(Operator::AddSurrounds { target }) => match Vim::read(cx).state().mode {
                 Mode::Normal => {
                     if let Some(target) = target {
@@ -923,6 +1099,7 @@ impl Vim {
                                 &register.text.to_string(),
                                 register.clipboard_selections.clone(),
                                 false,
+                                true,
                                 cx,
                             )
                         }
@@ -961,7 +1138,7 @@ impl Vim {
             filter.show_namespace(Self::NAMESPACE);
         });
         CommandPaletteInterceptor::update_global(cx, |interceptor, _| {
-            interceptor.set(Box::new(command::command_interceptor));
+            interceptor.set(Box::new(command::history_command_interceptor));
         });
 
         if let Some(active_window) = cx
@@ -1003,6 +1180,15 @@ impl Vim {
         ret
     }
 
+    /// Runs `f` with the active editor's object cache, which can't be borrowed
+    /// from the state while the editor is being updated.
+    fn with_object_cache<T>(&mut self, f: impl FnOnce(&mut ObjectCache) -> T) -> T {
+        let mut cache = self.update_state(|state| std::mem::take(&mut state.object_cache));
+        let result = f(&mut cache);
+        self.update_state(|state| state.object_cache = cache);
+        result
+    }
+
     fn sync_vim_settings(&mut self, cx: &mut WindowContext) {
         self.update_active_editor(cx, |vim, editor, cx| {
             let state = vim.state();
@@ -1012,8 +1198,21 @@ impl Vim {
             editor.set_input_enabled(state.editor_input_enabled());
             editor.set_autoindent(state.should_autoindent());
             editor.selections.line_mode = matches!(state.mode, Mode::VisualLine);
+            Self::apply_scroll_off(editor, cx);
             if editor.is_focused(cx) || editor.mouse_menu_is_focused(cx) {
-                editor.set_keymap_context_layer::<Self>(state.keymap_context_layer(), cx);
+                let mut context = state.keymap_context_layer();
+                if vim.workspace_state.last_paste.is_some() {
+                    context.add("VimPasted");
+                }
+                if vim
+                    .workspace_state
+                    .command_window
+                    .as_ref()
+                    .is_some_and(|window| window.editor.entity_id() == cx.view().entity_id())
+                {
+                    context.add("VimCommandWindow");
+                }
+                editor.set_keymap_context_layer::<Self>(context, cx);
                 // disable vim mode if a sub-editor (inline assist, rename, etc.) is focused
             } else if editor.focus_handle(cx).contains_focused(cx) {
                 editor.remove_keymap_context_layer::<Self>(cx);
@@ -1021,6 +1220,26 @@ impl Vim {
         });
     }
 
+    /// Makes the `scroll_off` and `side_scroll_off` settings, when they're
+    /// set, take the place of the editor's scroll margins.
+    fn apply_scroll_off(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
+        if editor.mode() != EditorMode::Full {
+            return;
+        }
+        let settings = VimSettings::get_global(cx);
+        let (scroll_off, side_scroll_off) = (settings.scroll_off, settings.side_scroll_off);
+        if let Some(scroll_off) = scroll_off {
+            if editor.vertical_scroll_margin() != scroll_off {
+                editor.set_vertical_scroll_margin(scroll_off, cx);
+            }
+        }
+        if let Some(side_scroll_off) = side_scroll_off {
+            if editor.horizontal_scroll_margin() != side_scroll_off {
+                editor.set_horizontal_scroll_margin(side_scroll_off, cx);
+            }
+        }
+    }
+
     fn unhook_vim_settings(editor: &mut Editor, cx: &mut ViewContext<Editor>) {
         if editor.mode() == EditorMode::Full {
             editor.set_cursor_shape(CursorShape::Bar, cx);
@@ -1029,6 +1248,14 @@ impl Vim {
             editor.set_input_enabled(true);
             editor.set_autoindent(true);
             editor.selections.line_mode = false;
+            let settings = VimSettings::get_global(cx);
+            if settings.scroll_off.is_some() {
+                let margin = EditorSettings::get_global(cx).vertical_scroll_margin;
+                editor.set_vertical_scroll_margin(margin as usize, cx);
+            }
+            if settings.side_scroll_off.is_some() {
+                editor.set_horizontal_scroll_margin(DEFAULT_HORIZONTAL_SCROLL_MARGIN as usize, cx);
+            }
         }
         editor.remove_keymap_context_layer::<Self>(cx)
     }
@@ -1046,6 +1273,17 @@ impl Settings for VimModeSetting {
     }
 }
 
+/// What `Q` does in normal mode.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QBehavior {
+    /// Enter Ex mode, where the command line is shown again after each
+    /// command until `:visual`.
+    Ex,
+    /// Replay the macro recorded last, as in Neovim.
+    LastMacro,
+}
+
 /// Controls when to use system clipboard.
 #[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -1058,20 +1296,63 @@ pub enum UseSystemClipboard {
     OnYank,
 }
 
+/// The kinds of number that `ctrl-a` and `ctrl-x` recognize besides decimal,
+/// as in vim's `nrformats`.
+#[derive(Copy, Clone, Debug, Serialize, Deserialize, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum NumberFormat {
+    /// Binary numbers starting with `0b` or `0B`.
+    Bin,
+    /// Hexadecimal numbers starting with `0x` or `0X`.
+    Hex,
+    /// Octal numbers starting with `0`.
+    Octal,
+    /// Single letters, which are advanced through the alphabet.
+    Alpha,
+    /// ISO dates like `2024-01-31`, which are advanced by a number of days.
+    Date,
+}
+
 #[derive(Deserialize)]
 struct VimSettings {
     pub use_system_clipboard: UseSystemClipboard,
+    pub clipboard: ClipboardBackend,
     pub use_multiline_find: bool,
     pub use_smartcase_find: bool,
+    pub ignorecase: bool,
+    pub smartcase: bool,
+    pub report: u32,
+    pub report_registers: bool,
+    pub showmode: bool,
+    pub nrformats: Vec<NumberFormat>,
+    pub textwidth: usize,
     pub custom_digraphs: HashMap<String, Arc<str>>,
+    pub inccommand: bool,
+    pub leader: String,
+    pub scroll_off: Option<usize>,
+    pub side_scroll_off: Option<usize>,
+    pub q_behavior: QBehavior,
 }
 
 #[derive(Clone, Default, Serialize, Deserialize, JsonSchema)]
 struct VimSettingsContent {
     pub use_system_clipboard: Option<UseSystemClipboard>,
+    pub clipboard: Option<ClipboardBackend>,
     pub use_multiline_find: Option<bool>,
     pub use_smartcase_find: Option<bool>,
+    pub ignorecase: Option<bool>,
+    pub smartcase: Option<bool>,
+    pub report: Option<u32>,
+    pub report_registers: Option<bool>,
+    pub showmode: Option<bool>,
+    pub nrformats: Option<Vec<NumberFormat>>,
+    pub textwidth: Option<usize>,
     pub custom_digraphs: Option<HashMap<String, Arc<str>>>,
+    pub inccommand: Option<bool>,
+    pub leader: Option<String>,
+    pub scroll_off: Option<usize>,
+    pub side_scroll_off: Option<usize>,
+    pub q_behavior: Option<QBehavior>,
 }
 
 impl Settings for VimSettings {
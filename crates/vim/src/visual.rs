@@ -1,23 +1,24 @@
-use std::sync::Arc;
+use std::{cmp, sync::Arc};
 
 use collections::HashMap;
 use editor::{
     display_map::{DisplaySnapshot, ToDisplayPoint},
     movement,
     scroll::Autoscroll,
-    Bias, DisplayPoint, Editor, ToOffset,
+    Bias, DisplayPoint, Editor,
 };
-use gpui::{actions, ViewContext, WindowContext};
+use gpui::{actions, px, ViewContext, WindowContext};
 use language::{Point, Selection, SelectionGoal};
 use multi_buffer::MultiBufferRow;
 use search::BufferSearchBar;
+use unicode_segmentation::UnicodeSegmentation;
 use util::ResultExt;
 use workspace::{searchable::Direction, Workspace};
 
 use crate::{
     motion::{start_of_line, Motion},
     normal::yank::{copy_selections_content, yank_selections_content},
-    normal::{mark::create_visual_marks, substitute::substitute},
+    normal::{delete::delete_selections, mark::create_visual_marks, substitute::substitute},
     object::Object,
     state::{Mode, Operator},
     Vim,
@@ -32,7 +33,10 @@ actions!(
         VisualDelete,
         VisualDeleteLine,
         VisualYank,
+        VisualYankLine,
+        VisualChangeLine,
         OtherEnd,
+        OtherEndInLine,
         SelectNext,
         SelectPrevious,
         SelectNextMatch,
@@ -54,6 +58,7 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
         },
     );
     workspace.register_action(other_end);
+    workspace.register_action(other_end_in_line);
     workspace.register_action(|_, _: &VisualDelete, cx| {
         Vim::update(cx, |vim, cx| {
             vim.record_current_action(cx);
@@ -68,7 +73,32 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
     });
     workspace.register_action(|_, _: &VisualYank, cx| {
         Vim::update(cx, |vim, cx| {
-            yank(vim, cx);
+            yank(vim, false, cx);
+        });
+    });
+    workspace.register_action(|_, _: &VisualYankLine, cx| {
+        Vim::update(cx, |vim, cx| {
+            yank(vim, true, cx);
+        });
+    });
+    workspace.register_action(|_, _: &VisualChangeLine, cx| {
+        Vim::update(cx, |vim, cx| {
+            vim.start_recording(cx);
+            if vim.state().mode == Mode::VisualBlock {
+                // In visual block mode, `C` changes from the left edge of the
+                // block to the end of each line.
+                vim.update_active_editor(cx, |_, editor, cx| {
+                    editor.change_selections(None, cx, |s| {
+                        s.move_with(|map, selection| {
+                            *selection.end.column_mut() = map.line_len(selection.end.row());
+                        })
+                    })
+                });
+                substitute(vim, None, false, cx);
+            } else {
+                vim.switch_mode(Mode::VisualLine, false, cx);
+                substitute(vim, None, true, cx);
+            }
         });
     });
 
@@ -87,7 +117,7 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
 
     workspace.register_action(|_, _: &RestoreVisualSelection, cx| {
         Vim::update(cx, |vim, cx| {
-            let Some((stored_mode, reversed)) =
+            let Some((stored_mode, directions)) =
                 vim.update_state(|state| state.stored_visual_mode.take())
             else {
                 return;
@@ -99,8 +129,8 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
             let ranges = start
                 .into_iter()
                 .zip(end)
-                .zip(reversed)
-                .map(|((start, end), reversed)| (*start, *end, reversed))
+                .zip(directions)
+                .map(|((start, end), (reversed, goal))| (*start, *end, reversed, goal))
                 .collect::<Vec<_>>();
 
             if vim.state().mode.is_visual() {
@@ -112,15 +142,19 @@ pub fn register(workspace: &mut Workspace, _: &mut ViewContext<Workspace>) {
                     let map = s.display_map();
                     let ranges = ranges
                         .into_iter()
-                        .map(|(start, end, reversed)| {
-                            let new_end =
-                                movement::saturating_right(&map, end.to_display_point(&map));
+                        .map(|(start, end, reversed, goal)| {
+                            let start = start.to_display_point(&map);
+                            // The marks are kept in order by the edits that
+                            // shift or remove the selection, except for
+                            // text inserted where it starts and ends.
+                            let end = cmp::max(end.to_display_point(&map), start);
+                            let new_end = movement::saturating_right(&map, end);
                             Selection {
                                 id: s.new_selection_id(),
-                                start: start.to_offset(&map.buffer_snapshot),
+                                start: start.to_offset(&map, Bias::Left),
                                 end: new_end.to_offset(&map, Bias::Left),
                                 reversed,
-                                goal: SelectionGoal::None,
+                                goal,
                             }
                         })
                         .collect();
@@ -136,18 +170,31 @@ pub fn visual_motion(motion: Motion, times: Option<usize>, cx: &mut WindowContex
     Vim::update(cx, |vim, cx| {
         vim.update_active_editor(cx, |vim, editor, cx| {
             let text_layout_details = editor.text_layout_details(cx);
-            if vim.state().mode == Mode::VisualBlock
-                && !matches!(
+            if vim.state().mode == Mode::VisualBlock {
+                let to_end_of_line = matches!(
                     motion,
                     Motion::EndOfLine {
                         display_lines: false
                     }
-                )
-            {
+                );
                 let is_up_or_down = matches!(motion, Motion::Up { .. } | Motion::Down { .. });
-                visual_block_motion(is_up_or_down, editor, cx, |map, point, goal| {
-                    motion.move_point(map, point, goal, times, &text_layout_details)
-                })
+                visual_block_motion(
+                    is_up_or_down || to_end_of_line,
+                    editor,
+                    cx,
+                    |map, point, goal| {
+                        let (point, goal) =
+                            motion.move_point(map, point, goal, times, &text_layout_details)?;
+                        if to_end_of_line {
+                            let goal = SelectionGoal::HorizontalRange {
+                                start: 0.,
+                                end: f32::INFINITY,
+                            };
+                            return Some((point, goal));
+                        }
+                        Some((point, goal))
+                    },
+                )
             } else {
                 editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
                     s.move_with(|map, selection| {
@@ -240,9 +287,15 @@ pub fn visual_block_motion(
             head = movement::saturating_left(map, head);
         }
 
-        let Some((new_head, _)) = move_selection(&map, head, goal) else {
+        let Some((new_head, new_goal)) = move_selection(&map, head, goal) else {
             return;
         };
+        // After `$` every row extends to its own end of line, which the goal
+        // keeps track of while the block is moved up and down.
+        let to_end_of_line = matches!(
+            new_goal,
+            SelectionGoal::HorizontalRange { end, .. } if end == f32::INFINITY
+        );
         head = new_head;
         head_x = map.x_for_display_point(head, &text_layout_details);
 
@@ -259,13 +312,16 @@ pub fn visual_block_motion(
             head_x = map.x_for_display_point(head, &text_layout_details);
         }
 
-        let positions = if is_reversed {
+        let mut positions = if is_reversed {
             head_x..tail_x
         } else {
             tail_x..head_x
         };
+        if to_end_of_line {
+            positions.end = px(f32::INFINITY);
+        }
 
-        if !preserve_goal {
+        if !preserve_goal || to_end_of_line {
             goal = SelectionGoal::HorizontalRange {
                 start: positions.start.0,
                 end: positions.end.0,
@@ -320,66 +376,75 @@ pub fn visual_object(object: Object, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         if let Some(Operator::Object { around }) = vim.active_operator() {
             vim.pop_operator(cx);
+            let times = vim.take_count(cx);
             let current_mode = vim.state().mode;
             let target_mode = object.target_visual_mode(current_mode);
             if target_mode != current_mode {
                 vim.switch_mode(target_mode, true, cx);
             }
 
-            vim.update_active_editor(cx, |_, editor, cx| {
-                editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
-                    s.move_with(|map, selection| {
-                        let mut mut_selection = selection.clone();
-
-                        // all our motions assume that the current character is
-                        // after the cursor; however in the case of a visual selection
-                        // the current character is before the cursor.
-                        // But this will affect the judgment of the html tag
-                        // so the html tag needs to skip this logic.
-                        if !selection.reversed && object != Object::Tag {
-                            mut_selection.set_head(
-                                movement::left(map, mut_selection.head()),
-                                mut_selection.goal,
-                            );
-                        }
+            vim.update_active_editor(cx, |vim, editor, cx| {
+                vim.with_object_cache(|cache| {
+                    editor.change_selections(Some(Autoscroll::fit()), cx, |s| {
+                        s.move_with(|map, selection| {
+                            let mut mut_selection = selection.clone();
+
+                            // all our motions assume that the current character is
+                            // after the cursor; however in the case of a visual selection
+                            // the current character is before the cursor.
+                            // But this will affect the judgment of the html tag
+                            // so the html tag needs to skip this logic.
+                            if !selection.reversed && object != Object::Tag {
+                                mut_selection.set_head(
+                                    movement::left(map, mut_selection.head()),
+                                    mut_selection.goal,
+                                );
+                            }
 
-                        if let Some(range) = object.range(map, mut_selection, around) {
-                            if !range.is_empty() {
-                                let expand_both_ways = object.always_expands_both_ways()
-                                    || selection.is_empty()
-                                    || movement::right(map, selection.start) == selection.end;
-
-                                if expand_both_ways {
-                                    selection.start = range.start;
-                                    selection.end = range.end;
-                                } else if selection.reversed {
-                                    selection.start = range.start;
-                                } else {
-                                    selection.end = range.end;
+                            if let Some(range) =
+                                object.cached_range(map, mut_selection, around, times, cache)
+                            {
+                                if !range.is_empty() {
+                                    let expand_both_ways = object.always_expands_both_ways()
+                                        || selection.is_empty()
+                                        || movement::right(map, selection.start) == selection.end;
+
+                                    if expand_both_ways {
+                                        selection.start = range.start;
+                                        selection.end = range.end;
+                                    } else if selection.reversed {
+                                        selection.start = range.start;
+                                    } else {
+                                        selection.end = range.end;
+                                    }
                                 }
-                            }
 
-                            // In the visual selection result of a paragraph object, the cursor is
-                            // placed at the start of the last line. And in the visual mode, the
-                            // selection end is located after the end character. So, adjustment of
-                            // selection end is needed.
-                            //
-                            // We don't do this adjustment for a one-line blank paragraph since the
-                            // trailing newline is included in its selection from the beginning.
-                            if object == Object::Paragraph && range.start != range.end {
-                                let row_of_selection_end_line = selection.end.to_point(map).row;
-                                let new_selection_end = if map
-                                    .buffer_snapshot
-                                    .line_len(MultiBufferRow(row_of_selection_end_line))
-                                    == 0
+                                // In the visual selection result of a paragraph object, the cursor is
+                                // placed at the start of the last line. And in the visual mode, the
+                                // selection end is located after the end character. So, adjustment of
+                                // selection end is needed.
+                                //
+                                // We don't do this adjustment for a one-line blank paragraph since the
+                                // trailing newline is included in its selection from the beginning.
+                                if matches!(
+                                    object,
+                                    Object::Paragraph | Object::IndentObj { .. } | Object::Section
+                                ) && range.start != range.end
                                 {
-                                    Point::new(row_of_selection_end_line + 1, 0)
-                                } else {
-                                    Point::new(row_of_selection_end_line, 1)
-                                };
-                                selection.end = new_selection_end.to_display_point(map);
+                                    let row_of_selection_end_line = selection.end.to_point(map).row;
+                                    let new_selection_end = if map
+                                        .buffer_snapshot
+                                        .line_len(MultiBufferRow(row_of_selection_end_line))
+                                        == 0
+                                    {
+                                        Point::new(row_of_selection_end_line + 1, 0)
+                                    } else {
+                                        Point::new(row_of_selection_end_line, 1)
+                                    };
+                                    selection.end = new_selection_end.to_display_point(map);
+                                }
                             }
-                        }
+                        });
                     });
                 });
             });
@@ -398,12 +463,42 @@ fn toggle_mode(mode: Mode, cx: &mut ViewContext<Workspace>) {
 }
 
 pub fn other_end(_: &mut Workspace, _: &OtherEnd, cx: &mut ViewContext<Workspace>) {
+    swap_selection_ends(true, cx)
+}
+
+pub fn other_end_in_line(_: &mut Workspace, _: &OtherEndInLine, cx: &mut ViewContext<Workspace>) {
+    swap_selection_ends(false, cx)
+}
+
+/// Moves the cursor to the other end of the selection (`o`). In a block this
+/// is the diagonally opposite corner, unless `swap_rows` is false in which case
+/// it's the other corner on the same line (`O`).
+fn swap_selection_ends(swap_rows: bool, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
+        let swap_rows = swap_rows && vim.state().mode == Mode::VisualBlock;
         vim.update_active_editor(cx, |_, editor, cx| {
+            let mut selections = editor.selections.all::<Point>(cx);
+            for selection in selections.iter_mut() {
+                selection.reversed = !selection.reversed;
+                // A block's goal runs from its tail's column to its head's.
+                if let SelectionGoal::HorizontalRange { start, end } = selection.goal {
+                    if end.is_finite() {
+                        selection.goal = SelectionGoal::HorizontalRange {
+                            start: end,
+                            end: start,
+                        };
+                    }
+                }
+            }
             editor.change_selections(None, cx, |s| {
-                s.move_with(|_, selection| {
-                    selection.reversed = !selection.reversed;
-                })
+                // The block runs from the row of its oldest selection to that
+                // of its newest, so they trade places to move between rows.
+                if swap_rows {
+                    for selection in selections.iter_mut().rev() {
+                        selection.id = s.new_selection_id();
+                    }
+                }
+                s.select(selections);
             })
         })
     });
@@ -420,7 +515,7 @@ pub fn delete(vim: &mut Vim, line_mode: bool, cx: &mut WindowContext) {
                 s.move_with(|map, selection| {
                     if line_mode {
                         let mut position = selection.head();
-                        if !selection.reversed {
+                        if !selection.reversed && !selection.is_empty() {
                             position = movement::left(map, position);
                         }
                         original_columns.insert(selection.id, position.to_point(map).column);
@@ -435,12 +530,23 @@ pub fn delete(vim: &mut Vim, line_mode: bool, cx: &mut WindowContext) {
                                 *selection.end.column_mut() = 0;
                             }
                         }
+                    } else {
+                        expand_to_graphemes(map, selection);
                     }
-                    selection.goal = SelectionGoal::None;
+                    selection.goal = if line_mode && vim.state().mode == Mode::VisualBlock {
+                        SelectionGoal::HorizontalRange {
+                            start: 0.,
+                            end: f32::INFINITY,
+                        }
+                    } else {
+                        SelectionGoal::None
+                    };
                 });
             });
-            copy_selections_content(vim, editor, line_mode, cx);
-            editor.insert("", cx);
+            // Deleting to the end of the lines in a block is still blockwise.
+            let linewise = line_mode && vim.state().mode != Mode::VisualBlock;
+            copy_selections_content(vim, editor, linewise, cx);
+            delete_selections(vim, editor, cx);
 
             // Fixup cursor position after the deletion
             editor.set_clip_at_line_ends(true, cx);
@@ -463,14 +569,53 @@ pub fn delete(vim: &mut Vim, line_mode: bool, cx: &mut WindowContext) {
     vim.switch_mode(Mode::Normal, true, cx);
 }
 
-pub fn yank(vim: &mut Vim, cx: &mut WindowContext) {
+pub fn yank(vim: &mut Vim, line_mode: bool, cx: &mut WindowContext) {
     vim.store_visual_marks(cx);
     vim.update_active_editor(cx, |vim, editor, cx| {
-        let line_mode = editor.selections.line_mode;
+        let mut original_rows: HashMap<_, _> = Default::default();
+        if line_mode && vim.state().mode == Mode::VisualBlock {
+            // Yank the lines of the block once, rather than once per row.
+            let selections = editor.selections.all::<Point>(cx);
+            if let (Some(first), Some(last)) = (selections.first(), selections.last()) {
+                let range = first.start..last.end;
+                editor.change_selections(None, cx, |s| s.select_ranges([range]));
+            }
+        }
+        if line_mode && !editor.selections.line_mode {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| {
+                    let start = selection.start.to_point(map);
+                    let end = selection.end.to_point(map);
+                    original_rows.insert(selection.id, start.row);
+                    if end.row == map.max_buffer_row().0 && start.row > 0 {
+                        // There is no newline after the last line, so take the
+                        // one before it instead (as a linewise delete does).
+                        selection.start = Point::new(
+                            start.row - 1,
+                            map.buffer_snapshot.line_len(MultiBufferRow(start.row - 1)),
+                        )
+                        .to_display_point(map);
+                        selection.end = map.max_point();
+                    } else {
+                        selection.start = Point::new(start.row, 0).to_display_point(map);
+                        selection.end = Point::new(end.row + 1, 0).to_display_point(map);
+                    }
+                    selection.reversed = false;
+                })
+            });
+        }
+        let line_mode = line_mode || editor.selections.line_mode;
+        if !line_mode {
+            editor.change_selections(None, cx, |s| {
+                s.move_with(|map, selection| expand_to_graphemes(map, selection))
+            });
+        }
         yank_selections_content(vim, editor, line_mode, cx);
         editor.change_selections(None, cx, |s| {
             s.move_with(|map, selection| {
-                if line_mode {
+                if let Some(row) = original_rows.get(&selection.id) {
+                    selection.start = Point::new(*row, 0).to_display_point(map);
+                } else if line_mode {
                     selection.start = start_of_line(map, false, selection.start);
                 };
                 selection.collapse_to(selection.start, SelectionGoal::None)
@@ -483,10 +628,43 @@ pub fn yank(vim: &mut Vim, cx: &mut WindowContext) {
     vim.switch_mode(Mode::Normal, true, cx);
 }
 
+/// Widens a charwise selection so that it doesn't split a grapheme. The
+/// cursor moves by codepoints, so it can stop between a letter and its
+/// combining accent, or inside an emoji sequence; like vim, which treats
+/// composing characters as part of the one before them, `d` and `y` then take
+/// the whole thing.
+fn expand_to_graphemes(map: &DisplaySnapshot, selection: &mut Selection<DisplayPoint>) {
+    let start = grapheme_boundary(map, selection.start.to_point(map), Bias::Left);
+    let end = grapheme_boundary(map, selection.end.to_point(map), Bias::Right);
+    selection.start = start.to_display_point(map);
+    selection.end = end.to_display_point(map);
+}
+
+fn grapheme_boundary(map: &DisplaySnapshot, point: Point, bias: Bias) -> Point {
+    let row = MultiBufferRow(point.row);
+    let line = map
+        .buffer_snapshot
+        .text_for_range(
+            Point::new(point.row, 0)..Point::new(point.row, map.buffer_snapshot.line_len(row)),
+        )
+        .collect::<String>();
+    let column = point.column as usize;
+    let mut boundaries = line
+        .grapheme_indices(true)
+        .map(|(ix, _)| ix)
+        .chain([line.len()]);
+    let column = match bias {
+        Bias::Left => boundaries.take_while(|ix| *ix <= column).last(),
+        Bias::Right => boundaries.find(|ix| *ix >= column),
+    };
+    Point::new(point.row, column.unwrap_or(point.column as usize) as u32)
+}
+
 pub(crate) fn visual_replace(text: Arc<str>, cx: &mut WindowContext) {
     Vim::update(cx, |vim, cx| {
         vim.stop_recording();
-        vim.update_active_editor(cx, |_, editor, cx| {
+        vim.update_active_editor(cx, |vim, editor, cx| {
+            let is_block = vim.state().mode == Mode::VisualBlock;
             editor.transact(cx, |editor, cx| {
                 let (display_map, selections) = editor.selections.all_adjusted_display(cx);
 
@@ -511,7 +689,21 @@ pub(crate) fn visual_replace(text: Arc<str>, cx: &mut WindowContext) {
                     {
                         let range = row_range.start.to_offset(&display_map, Bias::Right)
                             ..row_range.end.to_offset(&display_map, Bias::Right);
-                        let text = text.repeat(range.len());
+                        // Each character is replaced, except that within a block
+                        // a tab is replaced by as many as the columns it spans.
+                        let replaced = if is_block {
+                            display_map
+                                .display_chars_at(row_range.start)
+                                .take_while(|(_, point)| *point < row_range.end)
+                                .map(|(c, _)| c)
+                                .collect::<String>()
+                        } else {
+                            display_map
+                                .buffer_snapshot
+                                .text_for_range(range.clone())
+                                .collect::<String>()
+                        };
+                        let text = text.repeat(replaced.graphemes(true).count());
                         edits.push((range, text));
                     }
                 }
@@ -639,7 +831,7 @@ pub fn select_match(
             vim.stop_recording();
             delete(vim, false, cx)
         }
-        Some(Operator::Yank) => yank(vim, cx),
+        Some(Operator::Yank) => yank(vim, false, cx),
         _ => {} // Ignoring other operators
     }
 }
@@ -652,6 +844,7 @@ mod test {
     use crate::{
         state::Mode,
         test::{NeovimBackedTestContext, VimTestContext},
+        Vim,
     };
 
     #[gpui::test]
@@ -948,6 +1141,48 @@ mod test {
             .assert_eq("fox jumps over\nthe lazy dog\n");
     }
 
+    #[gpui::test]
+    async fn test_visual_graphemes(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+        let register = |cx: &mut VimTestContext| {
+            cx.update(|cx| {
+                Vim::read(cx)
+                    .workspace_state
+                    .registers
+                    .get(&'"')
+                    .map(|register| register.text.to_string())
+            })
+        };
+
+        // the combining accent goes with the letter before it
+        cx.set_state("aˇe\u{301}b", Mode::Normal);
+        cx.simulate_keystrokes("v x");
+        cx.assert_state("aˇb", Mode::Normal);
+        assert_eq!(register(&mut cx).as_deref(), Some("e\u{301}"));
+        cx.simulate_keystrokes("p");
+        assert_eq!(cx.update_editor(|editor, cx| editor.text(cx)), "abe\u{301}");
+
+        // an emoji with a skin tone is one grapheme of two codepoints
+        cx.set_state("ˇ👍🏽!", Mode::Normal);
+        cx.simulate_keystrokes("v y");
+        cx.assert_state("ˇ👍🏽!", Mode::Normal);
+        assert_eq!(register(&mut cx).as_deref(), Some("👍🏽"));
+        cx.simulate_keystrokes("$ p");
+        assert_eq!(cx.update_editor(|editor, cx| editor.text(cx)), "👍🏽!👍🏽");
+
+        // precomposed characters are deleted exactly
+        cx.set_state("caˇfé au lait", Mode::Normal);
+        cx.simulate_keystrokes("v l d");
+        cx.assert_state("caˇ au lait", Mode::Normal);
+        assert_eq!(register(&mut cx).as_deref(), Some("fé"));
+
+        // a selection ending inside a flag takes both of its letters
+        cx.set_state("ˇab🇳🇿c", Mode::Normal);
+        cx.simulate_keystrokes("v l l d");
+        cx.assert_state("ˇc", Mode::Normal);
+        assert_eq!(register(&mut cx).as_deref(), Some("ab🇳🇿"));
+    }
+
     #[gpui::test]
     async fn test_visual_block_mode(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1149,6 +1384,142 @@ mod test {
         });
     }
 
+    #[gpui::test]
+    async fn test_visual_block_insert_and_append(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇone\ntwo\nthree").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j shift-i / / space escape")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("ˇ// one\n// two\n// three");
+        cx.simulate_shared_keystrokes(".").await;
+        cx.shared_state()
+            .await
+            .assert_eq("ˇ// // one\n// // two\n// // three");
+
+        // lines that don't reach the block are left alone by `I`
+        cx.set_shared_state("abˇcd\nx\nabcd").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j shift-i - escape")
+            .await;
+        cx.shared_state().await.assert_eq("abˇ-cd\nx\nab-cd");
+        cx.set_shared_state("abˇcd\nab\nabcd").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j shift-i - escape")
+            .await;
+        cx.shared_state().await.assert_eq("abˇ-cd\nab\nab-cd");
+
+        // but are padded out to the end of the block by `A`
+        cx.set_shared_state("aˇbc\nd\nefg").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j l shift-a x escape")
+            .await;
+        cx.shared_state().await.assert_eq("aˇbcx\nd  x\nefgx");
+
+        // after `$`, each line is appended to at its end
+        cx.set_shared_state("ˇab\nc\ndef").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j $ shift-a ; escape")
+            .await;
+        cx.shared_state().await.assert_eq("ˇab;\nc;\ndef;");
+    }
+
+    #[gpui::test]
+    async fn test_visual_block_to_end_of_line(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("The ˇquick\nab\njumps over").await;
+        cx.simulate_shared_keystrokes("ctrl-v j j l shift-d").await;
+        cx.shared_state().await.assert_eq("Theˇ \nab\njump");
+
+        cx.set_shared_state("The ˇquick\nfox jumps").await;
+        cx.simulate_shared_keystrokes("ctrl-v j shift-c x escape")
+            .await;
+        cx.shared_state().await.assert_eq("The ˇx\nfox x");
+
+        cx.set_shared_state("The ˇquick\nfox jumps\nend").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l shift-y shift-g p")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("The quick\nfox jumps\nend\nˇThe quick\nfox jumps");
+    }
+
+    #[gpui::test]
+    async fn test_visual_other_end(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("The ˇquick brown").await;
+        cx.simulate_shared_keystrokes("v l l o h h").await;
+        cx.shared_state().await.assert_eq("Th«ˇe qui»ck brown");
+
+        // in a block `o` goes to the opposite corner
+        cx.set_shared_state("aˇbcd\nefgh\nijkl").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l o h j j d").await;
+        cx.shared_state().await.assert_eq("abcd\nˇh\nl");
+
+        // and `O` to the other corner on the same line
+        cx.set_shared_state("aˇbcd\nefgh\nijkl").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l shift-o h j d")
+            .await;
+        cx.shared_state().await.assert_eq("ˇd\nh\nl");
+
+        cx.set_shared_state("aˇbcd\nefgh\nijkl").await;
+        cx.simulate_shared_keystrokes("ctrl-v l shift-o j d").await;
+        cx.shared_state().await.assert_eq("aˇd\neh\nijkl");
+    }
+
+    #[gpui::test]
+    async fn test_visual_block_replace(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("ˇabcd\nefgh\nijkl\nmnop").await;
+        cx.simulate_shared_keystrokes("ctrl-v 2 j 2 l r x").await;
+        cx.shared_state().await.assert_eq("ˇxxxd\nxxxh\nxxxl\nmnop");
+        cx.simulate_shared_keystrokes("j .").await;
+        cx.shared_state().await.assert_eq("xxxd\nˇxxxh\nxxxl\nxxxp");
+
+        cx.set_shared_state("ˇaéb\ncdé").await;
+        cx.simulate_shared_keystrokes("ctrl-v j l r x").await;
+        cx.shared_state().await.assert_eq("ˇxxb\nxxé");
+
+        cx.set_shared_state("ˇaé b").await;
+        cx.simulate_shared_keystrokes("v l l r -").await;
+        cx.shared_state().await.assert_eq("ˇ---b");
+    }
+
+    #[gpui::test]
+    async fn test_visual_block_replace_tabs(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        // each column a tab spans in the block is replaced, with tabs four wide
+        cx.set_state("ˇ\ta\n\tb", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l r x");
+        cx.assert_state("ˇxxxxx\nxxxxx", Mode::Normal);
+
+        cx.set_state("aˇb\tc\nde\tf", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l l r x");
+        cx.assert_state("aˇxxxx\ndxxxx", Mode::Normal);
+    }
+
+    #[gpui::test]
+    async fn test_visual_block_dollar(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        // `$` stays in effect as the block is extended downwards.
+        cx.set_shared_state("ˇab\nc\ndef").await;
+        cx.simulate_shared_keystrokes("ctrl-v $ j j shift-a ; escape")
+            .await;
+        cx.shared_state().await.assert_eq("ˇab;\nc;\ndef;");
+
+        cx.set_shared_state("The ˇquick\nab\nfox jumps").await;
+        cx.simulate_shared_keystrokes("ctrl-v $ j j d").await;
+        cx.shared_state().await.assert_eq("Theˇ \nab\nfox ");
+
+        cx.set_shared_state("ˇone\nthree\ntwo").await;
+        cx.simulate_shared_keystrokes("ctrl-v j $ j c x escape")
+            .await;
+        cx.shared_state().await.assert_eq("ˇx\nx\nx");
+    }
+
     #[gpui::test]
     async fn test_visual_object(cx: &mut gpui::TestAppContext) {
         let mut cx = NeovimBackedTestContext::new(cx).await;
@@ -1370,4 +1741,93 @@ mod test {
             Mode::Visual,
         );
     }
+
+    #[gpui::test]
+    async fn test_gv_visual_block(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("oˇne\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j l y");
+        cx.assert_state("oˇne\ntwo\nthree", Mode::Normal);
+        cx.simulate_keystrokes("g v");
+        cx.assert_state("o«neˇ»\nt«woˇ»\nthree", Mode::VisualBlock);
+
+        // a block extended with $ still reaches the end of each line
+        cx.set_state("aˇb\nabcd\nabcdefg", Mode::Normal);
+        cx.simulate_keystrokes("ctrl-v j $ y");
+        cx.assert_state("aˇb\nabcd\nabcdefg", Mode::Normal);
+        cx.simulate_keystrokes("g v");
+        cx.assert_state("a«bˇ»\na«bcdˇ»\nabcdefg", Mode::VisualBlock);
+        cx.simulate_keystrokes("j");
+        cx.assert_state("a«bˇ»\na«bcdˇ»\na«bcdefgˇ»", Mode::VisualBlock);
+    }
+
+    #[gpui::test]
+    async fn test_gv_after_edits(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state("one\nThe ˇquick brown").await;
+        cx.simulate_shared_keystrokes("v e escape k shift-i x x escape g v")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("xxone\nThe «quickˇ» brown");
+
+        cx.simulate_shared_keystrokes("escape k shift-o escape g v")
+            .await;
+        cx.shared_state()
+            .await
+            .assert_eq("\nxxone\nThe «quickˇ» brown");
+    }
+
+    #[gpui::test]
+    async fn test_gv_after_deleting_selection(cx: &mut gpui::TestAppContext) {
+        let mut cx = VimTestContext::new(cx, true).await;
+
+        cx.set_state("The ˇquick brown", Mode::Normal);
+        cx.simulate_keystrokes("v e d g v");
+        cx.assert_state("The « ˇ» brown", Mode::Visual);
+
+        cx.set_state("The ˇquick brown", Mode::Normal);
+        cx.simulate_keystrokes("v escape i x x escape g v");
+        cx.assert_state("The xx«qˇ»uick brown", Mode::Visual);
+    }
+
+    #[gpui::test]
+    async fn test_gv_after_yank(cx: &mut gpui::TestAppContext) {
+        let mut cx = NeovimBackedTestContext::new(cx).await;
+
+        cx.set_shared_state(indoc! {
+            "The ˇquick brown
+            fox jumps"
+        })
+        .await;
+        cx.simulate_shared_keystrokes("v e y").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The ˇquick brown
+            fox jumps"
+        });
+        cx.simulate_shared_keystrokes("g v").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The «quickˇ» brown
+            fox jumps"
+        });
+
+        cx.simulate_shared_keystrokes("y j p").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The quick brown
+            fox jquicˇkumps"
+        });
+        cx.simulate_shared_keystrokes("g v").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The «quickˇ» brown
+            fox jquickumps"
+        });
+
+        cx.simulate_shared_keystrokes("o y g v").await;
+        cx.shared_state().await.assert_eq(indoc! {
+            "The «ˇquick» brown
+            fox jquickumps"
+        });
+    }
 }
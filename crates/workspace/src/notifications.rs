@@ -131,6 +131,14 @@ impl Workspace {
             .collect()
     }
 
+    #[cfg(any(test, feature = "test-support"))]
+    pub fn notification<V: Notification>(&self, id: &NotificationId) -> Option<View<V>> {
+        self.notifications
+            .iter()
+            .find(|(existing_id, _)| existing_id == id)
+            .and_then(|(_, notification)| notification.to_any().downcast::<V>().ok())
+    }
+
     pub fn show_notification<V: Notification>(
         &mut self,
         id: NotificationId,
@@ -482,6 +490,11 @@ pub mod simple_message_notification {
             }
         }
 
+        #[cfg(any(test, feature = "test-support"))]
+        pub fn message(&self) -> &SharedString {
+            &self.message
+        }
+
         pub fn with_click_message<S>(mut self, message: S) -> Self
         where
             S: Into<SharedString>,